@@ -0,0 +1,18 @@
+#!/bin/sh
+# Installed by gl (amend-check); remove with `gl --uninstall-hook amend-check`
+#
+# Stops `git commit --amend` if HEAD has already been pushed.  Set GL_ALLOW_AMEND=1 to amend anyway
+
+# Git passes "commit HEAD" as the message source when amending, unless a message is given with -m,
+# in which case we have to look at how git was run
+amending=""
+if [ "$2" = "commit" ] && [ "$3" = "HEAD" ]; then
+    amending=1
+fi
+case "$(ps -o args= -p "$PPID" 2>/dev/null)" in
+    *--amend*) amending=1 ;;
+esac
+
+if [ -n "$amending" ] && [ -z "$GL_ALLOW_AMEND" ] && command -v gl >/dev/null 2>&1; then
+    gl --amend-check >/dev/null || exit 1
+fi
@@ -0,0 +1,8 @@
+#!/bin/sh
+# Installed by gl (commit-count); remove with `gl --uninstall-hook commit-count`
+#
+# Writes the number of commits made to the current branch today to .git/gl-commit-count, for shell
+# prompts to read without walking the history themselves
+
+count_file="$(git rev-parse --git-path gl-commit-count)"
+git rev-list --count --since=midnight HEAD > "$count_file" 2>/dev/null || rm -f "$count_file"
@@ -0,0 +1,224 @@
+use crate::context::Context;
+use crate::opts::GitLogOptions;
+use crate::pathspec::exclude_pathspec_args;
+use chrono::{DateTime, Local};
+use json::JsonValue;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tabular::{row, Table};
+
+const DAY: i64 = 24 * 60 * 60;
+
+// Age brackets (by the time since a file was last changed), each with its upper bound in days
+const AGE_BRACKETS: [(&str, i64); 4] = [
+    ("< 6 months", 182),
+    ("6–12 months", 365),
+    ("1–2 years", 730),
+    ("> 2 years", i64::MAX),
+];
+
+// How old the code in a directory is
+#[derive(Default)]
+pub struct DirectoryAge {
+    pub directory: String,
+    pub files: usize,
+    pub lines: usize,
+    // Lines in each of the age brackets
+    pub bracket_lines: [usize; AGE_BRACKETS.len()],
+    pub oldest: Option<i64>,
+    pub newest: Option<i64>,
+}
+
+impl DirectoryAge {
+    fn add_file(&mut self, lines: usize, last_changed: i64, now: i64) {
+        let age_days = (now - last_changed) / DAY;
+        let bracket = AGE_BRACKETS
+            .iter()
+            .position(|(_, max_days)| age_days < *max_days)
+            .unwrap_or(AGE_BRACKETS.len() - 1);
+
+        self.files += 1;
+        self.lines += lines;
+        self.bracket_lines[bracket] += lines;
+        self.oldest = Some(self.oldest.map_or(last_changed, |t| t.min(last_changed)));
+        self.newest = Some(self.newest.map_or(last_changed, |t| t.max(last_changed)));
+    }
+
+    fn add(&mut self, other: &DirectoryAge) {
+        self.files += other.files;
+        self.lines += other.lines;
+        for (a, b) in self.bracket_lines.iter_mut().zip(other.bracket_lines) {
+            *a += b;
+        }
+        self.oldest = self.oldest.into_iter().chain(other.oldest).min();
+        self.newest = self.newest.into_iter().chain(other.newest).max();
+    }
+
+    // The percentage of lines in each age bracket
+    fn bracket_percentages(&self) -> Vec<f64> {
+        self.bracket_lines
+            .iter()
+            .map(|n| {
+                if self.lines == 0 {
+                    0.0
+                } else {
+                    (n * 100) as f64 / self.lines as f64
+                }
+            })
+            .collect()
+    }
+}
+
+// The age of the code in each top-level directory of the repository.  Each file's lines are dated
+// by the last commit to touch the file, which is much cheaper than blaming every line (see blame.rs
+// for that), and close enough to find the stale parts of a repository
+pub fn code_age(ctx: &Context, opts: &GitLogOptions) -> Vec<DirectoryAge> {
+    let Some(top_level_path) = ctx.top_level_path() else {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return vec![];
+    };
+
+    let last_changed = last_changed_times(top_level_path, opts);
+    let now = Local::now().timestamp();
+    let mut directories: BTreeMap<String, DirectoryAge> = BTreeMap::new();
+    for (file, time) in last_changed {
+        let Ok(bytes) = std::fs::read(Path::new(top_level_path).join(&file)) else {
+            continue;
+        };
+        let lines = String::from_utf8_lossy(&bytes).lines().count();
+        let directory = match file.split_once('/') {
+            Some((directory, _)) => directory.to_string(),
+            None => String::from("."),
+        };
+        directories
+            .entry(directory.clone())
+            .or_insert_with(|| DirectoryAge {
+                directory,
+                ..Default::default()
+            })
+            .add_file(lines, time, now);
+    }
+
+    directories.into_values().collect()
+}
+
+// The time of the last commit to change each file that is in HEAD, keyed by its path relative to
+// the top level of the repository
+fn last_changed_times(top_level_path: &str, opts: &GitLogOptions) -> HashMap<String, i64> {
+    let mut cmd = Command::new("git");
+    cmd.arg("-C");
+    cmd.arg(top_level_path);
+    cmd.arg("ls-tree");
+    cmd.arg("-r");
+    cmd.arg("-z");
+    cmd.arg("--name-only");
+    cmd.arg("HEAD");
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git ls-tree`");
+    let mut pending: HashSet<String> = String::from_utf8_lossy(&output.stdout)
+        .split_terminator('\0')
+        .map(String::from)
+        .collect();
+
+    // Walk the history from newest to oldest, so the first time we see a file is its last change.
+    // With -z, paths are given as they are rather than quoted, and each path and commit line ends
+    // in a null byte.  Commit lines are marked with a leading \x01 to tell them apart from paths
+    let mut cmd = Command::new("git");
+    cmd.arg("-C");
+    cmd.arg(top_level_path);
+    cmd.arg("log");
+    cmd.arg("-z");
+    cmd.arg("--format=%x01%ct");
+    cmd.arg("--name-only");
+    cmd.arg("--no-renames");
+    cmd.arg("HEAD");
+    cmd.args(exclude_pathspec_args(opts));
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git log`");
+
+    let mut last_changed = HashMap::new();
+    let mut time = 0;
+    for entry in String::from_utf8_lossy(&output.stdout).split_terminator('\0') {
+        if pending.is_empty() {
+            break;
+        }
+        // The first path of each commit follows a line break after the commit line
+        let entry = entry.strip_prefix('\n').unwrap_or(entry);
+        if let Some(t) = entry.strip_prefix('\x01') {
+            time = t.parse().unwrap_or(0);
+        } else if pending.remove(entry) {
+            last_changed.insert(entry.to_string(), time);
+        }
+    }
+
+    last_changed
+}
+
+fn format_date(timestamp: Option<i64>) -> String {
+    timestamp
+        .and_then(|t| DateTime::from_timestamp(t, 0))
+        .map(|d| d.with_timezone(&Local).format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+pub fn display_code_age(directories: Vec<DirectoryAge>, opts: &GitLogOptions) {
+    let mut total = DirectoryAge {
+        directory: String::from("Total"),
+        ..Default::default()
+    };
+    for directory in &directories {
+        total.add(directory);
+    }
+
+    if opts.json {
+        let to_json = |d: &DirectoryAge| {
+            let mut brackets = JsonValue::new_object();
+            for ((bracket, _), percentage) in AGE_BRACKETS.iter().zip(d.bracket_percentages()) {
+                brackets[*bracket] = percentage.into();
+            }
+            json::object! {
+                directory: d.directory.clone(),
+                files: d.files,
+                lines: d.lines,
+                percentages: brackets,
+                oldest: format_date(d.oldest),
+                newest: format_date(d.newest),
+            }
+        };
+        let directories: Vec<JsonValue> = directories.iter().map(to_json).collect();
+        let summary = json::object! {
+            directories: directories,
+            total: to_json(&total),
+        };
+        println!("{}", summary.pretty(2));
+        return;
+    }
+
+    if directories.is_empty() {
+        println!("No committed files found.");
+        return;
+    }
+
+    let mut header = row!("Directory", "Files", "Lines");
+    for (bracket, _) in AGE_BRACKETS {
+        header.add_cell(bracket);
+    }
+    header.add_cell("Last changed");
+    let mut table = Table::new("{:<}  {:>}  {:>}  {:>}  {:>}  {:>}  {:>}  {:<}").with_row(header);
+    for directory in directories.iter().chain(std::iter::once(&total)) {
+        let mut row = row!(&directory.directory, directory.files, directory.lines);
+        for percentage in directory.bracket_percentages() {
+            row.add_cell(format!("{:.1}%", percentage));
+        }
+        row.add_cell(format_date(directory.newest));
+        table.add_row(row);
+    }
+    print!("{}", table);
+}
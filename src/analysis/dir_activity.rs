@@ -0,0 +1,164 @@
+use crate::commit::{date_filter_args, merge_filter_arg};
+use crate::identity::{author_filter_args, Interner};
+use crate::opts::GitLogOptions;
+use crate::pathspec::exclude_pathspec_args;
+use json::JsonValue;
+use std::collections::{BTreeMap, HashSet};
+use std::process::{Command, Stdio};
+
+const BAR_MAX_WIDTH: usize = 30;
+
+// The number of commits that touched a directory, and its subdirectories
+#[derive(Default)]
+pub struct DirectoryNode {
+    pub commits: usize,
+    pub children: BTreeMap<String, DirectoryNode>,
+}
+
+impl DirectoryNode {
+    // Set the number of commits of a directory, given by its path (e.g. "src/analysis")
+    fn insert(&mut self, path: &str, commits: usize) {
+        let mut node = self;
+        for component in path.split('/') {
+            node = node.children.entry(component.to_string()).or_default();
+        }
+        node.commits = commits;
+    }
+
+    // Children with the most commits first
+    fn sorted_children(&self) -> Vec<(&String, &DirectoryNode)> {
+        let mut children: Vec<(&String, &DirectoryNode)> = self.children.iter().collect();
+        children.sort_by(|(a, a_node), (b, b_node)| {
+            b_node.commits.cmp(&a_node.commits).then_with(|| a.cmp(b))
+        });
+        children
+    }
+
+    fn to_json(&self, name: &str) -> JsonValue {
+        let children: Vec<JsonValue> = self
+            .sorted_children()
+            .into_iter()
+            .map(|(name, child)| child.to_json(name))
+            .collect();
+        json::object! {
+            directory: name,
+            commits: self.commits,
+            children: children,
+        }
+    }
+}
+
+// Count the commits that touched each directory, down to the given depth.  A commit counts once
+// for each directory it touched, however many files it changed there
+pub fn dir_activity(depth: usize, opts: &GitLogOptions) -> DirectoryNode {
+    let mut cmd = Command::new("git");
+    cmd.arg("log");
+    cmd.args(merge_filter_arg(opts));
+    if opts.first_parent {
+        cmd.arg("--first-parent");
+    }
+    // With -z, paths are given as they are rather than quoted, and each ends in a null byte
+    cmd.arg("-z");
+    cmd.arg("--name-only");
+    cmd.arg("--format=%x01");
+    cmd.args(author_filter_args(&opts.authors));
+    cmd.args(date_filter_args(opts));
+    if let Some(rev) = &opts.rev {
+        cmd.arg(rev);
+    }
+    cmd.args(exclude_pathspec_args(opts));
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git log`");
+
+    let mut root = DirectoryNode::default();
+    if !output.status.success() {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return root;
+    }
+
+    // Commits are counted against each directory's path, which is interned so that it is only
+    // allocated once however many commits touch it.  The tree is built from the paths at the end
+    let mut directories = Interner::default();
+    let mut commits_per_directory: Vec<usize> = Vec::new();
+
+    // Each commit starts with a \x01, followed by the files it changed.  The first file follows a
+    // line break after the commit's (empty) format
+    for commit in String::from_utf8_lossy(&output.stdout)
+        .split('\x01')
+        .skip(1)
+    {
+        // Each directory containing a changed file, and all of its ancestors
+        let mut touched: HashSet<usize> = HashSet::new();
+        for file in commit
+            .split('\0')
+            .map(|file| file.strip_prefix('\n').unwrap_or(file))
+            .filter(|file| !file.is_empty())
+        {
+            for (end, _) in file.match_indices('/').take(depth) {
+                let id = directories.intern(&file[..end]);
+                if id == commits_per_directory.len() {
+                    commits_per_directory.push(0);
+                }
+                touched.insert(id);
+            }
+        }
+
+        root.commits += 1;
+        for id in touched {
+            commits_per_directory[id] += 1;
+        }
+    }
+
+    for (id, commits) in commits_per_directory.into_iter().enumerate() {
+        root.insert(directories.resolve(id), commits);
+    }
+    root
+}
+
+pub fn display_dir_activity(root: DirectoryNode, opts: &GitLogOptions) {
+    if opts.json {
+        println!("{}", root.to_json(".").pretty(2));
+        return;
+    }
+
+    if root.commits == 0 {
+        println!("No commits found.");
+        return;
+    }
+
+    // Draw the tree, then line up the counts and bars to the right of the longest line
+    let mut lines: Vec<(String, usize)> = vec![(String::from("."), root.commits)];
+    tree_lines(&root, "", &mut lines);
+    let name_width = lines
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let count_width = root.commits.to_string().len();
+    for (name, commits) in lines {
+        let bar_width = (commits * BAR_MAX_WIDTH).div_ceil(root.commits);
+        println!(
+            "{:<name_width$}  {:>count_width$}  {}",
+            name,
+            commits,
+            "█".repeat(bar_width),
+            name_width = name_width,
+            count_width = count_width,
+        );
+    }
+}
+
+fn tree_lines(node: &DirectoryNode, prefix: &str, lines: &mut Vec<(String, usize)>) {
+    let children = node.sorted_children();
+    let n_children = children.len();
+    for (i, (name, child)) in children.into_iter().enumerate() {
+        let last = i + 1 == n_children;
+        let branch = if last { "└── " } else { "├── " };
+        lines.push((format!("{}{}{}", prefix, branch, name), child.commits));
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        tree_lines(child, &child_prefix, lines);
+    }
+}
@@ -0,0 +1,5 @@
+// Analyses of where and when work happens: how stale each part of the current tree is, which
+// directories the commit history has been concentrated in, and at what times of year
+pub mod code_age;
+pub mod dir_activity;
+pub mod seasonality;
@@ -0,0 +1,139 @@
+use crate::chart;
+use crate::commit::{date_filter_args, merge_filter_arg};
+use crate::identity::{author_filter_args, GitIdentity};
+use crate::opts::GitLogOptions;
+use crate::pathspec::exclude_pathspec_args;
+use json::JsonValue;
+use std::process::{Command, Stdio};
+use tabular::{row, Table};
+
+const BAR_MAX_WIDTH: usize = 30;
+
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+// Commits per month of the year and per day of the month, summed over every year of history
+pub struct Seasonality {
+    pub months: [usize; 12],
+    pub days: [usize; 31],
+}
+
+// Count commits by the month and day they were made.  Dates are taken in the time zone of the
+// commit, i.e. when it was for whoever made it, and by author date with --author-date
+pub fn seasonality(opts: &GitLogOptions) -> Seasonality {
+    let mut cmd = Command::new("git");
+    cmd.arg("log");
+    cmd.args(merge_filter_arg(opts));
+    if opts.first_parent {
+        cmd.arg("--first-parent");
+    }
+    cmd.arg("--date=format:%m %d");
+    cmd.arg(if opts.author_date {
+        "--format=%ad%x00%an%x00%ae"
+    } else {
+        "--format=%cd%x00%an%x00%ae"
+    });
+    cmd.args(author_filter_args(&opts.authors));
+    cmd.args(date_filter_args(opts));
+    if let Some(rev) = &opts.rev {
+        cmd.arg(rev);
+    }
+    cmd.args(exclude_pathspec_args(opts));
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git log`");
+
+    let mut seasonality = Seasonality {
+        months: [0; 12],
+        days: [0; 31],
+    };
+    if !output.status.success() {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return seasonality;
+    }
+
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let mut parts = line.split('\0');
+        let (Some(date), Some(name), Some(email)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        if opts.no_bots {
+            let id = GitIdentity {
+                email: email.to_string(),
+                names: vec![name.to_string()],
+            };
+            if id.is_bot() {
+                continue;
+            }
+        }
+        let mut date = date.split(' ').map(|n| n.parse::<usize>().unwrap_or(0));
+        if let (Some(month @ 1..=12), Some(day @ 1..=31)) = (date.next(), date.next()) {
+            seasonality.months[month - 1] += 1;
+            seasonality.days[day - 1] += 1;
+        }
+    }
+
+    seasonality
+}
+
+fn bar(n: usize, max: usize) -> String {
+    if max == 0 {
+        return String::new();
+    }
+    "█".repeat((n * BAR_MAX_WIDTH).div_ceil(max))
+}
+
+fn display_counts<L: std::fmt::Display>(heading: &str, counts: &[(L, usize)]) {
+    let max = counts.iter().map(|(_, n)| *n).max().unwrap_or(0);
+    let mut table = Table::new("{:<}  {:>}  {:<}").with_row(row!(heading, "Commits", ""));
+    for (label, n) in counts {
+        table.add_row(row!(label, n, bar(*n, max)));
+    }
+    print!("{}", table);
+}
+
+pub fn display_seasonality(seasonality: Seasonality, opts: &GitLogOptions) {
+    if opts.json {
+        let months: Vec<JsonValue> = MONTH_NAMES
+            .iter()
+            .zip(seasonality.months)
+            .map(|(month, commits)| json::object! { month: *month, commits: commits })
+            .collect();
+        let days: Vec<JsonValue> = (1..)
+            .zip(seasonality.days)
+            .map(|(day, commits): (usize, usize)| json::object! { day: day, commits: commits })
+            .collect();
+        let value = json::object! { months: months, days: days };
+        println!("{}", value.pretty(2));
+        return;
+    }
+
+    if seasonality.months.iter().all(|n| *n == 0) {
+        println!("No commits found.");
+        return;
+    }
+
+    let months: Vec<(&str, usize)> = MONTH_NAMES
+        .iter()
+        .copied()
+        .zip(seasonality.months)
+        .collect();
+    display_counts("Month", &months);
+    if opts.sparkline {
+        println!("{}", chart::sparkline(&seasonality.months));
+    }
+
+    println!();
+    let days: Vec<(usize, usize)> = (1..).zip(seasonality.days).collect();
+    display_counts("Day", &days);
+    if opts.sparkline {
+        println!("{}", chart::sparkline(&seasonality.days));
+    }
+
+    // Not every month has a 29th, 30th, or 31st, so fewer commits on those days is expected
+    println!("(The 29th, 30th, and 31st occur in fewer months than other days.)");
+}
@@ -0,0 +1,202 @@
+use super::commit::{date_filter_args, HashFormat};
+use super::identity::author_filter_args;
+use super::opts::GitLogOptions;
+use chrono::{DateTime, Local};
+use json::JsonValue;
+use std::process::{Command, Stdio};
+use tabular::{row, Table};
+
+const DAY: i64 = 24 * 60 * 60;
+
+// How many of the worst offenders to show
+const WORST_N: usize = 10;
+
+struct DatedCommit {
+    hash: String,
+    author_time: i64,
+    commit_time: i64,
+    subject: String,
+}
+
+impl DatedCommit {
+    // How long after it was authored the commit was committed (negative if it was committed
+    // "before" it was authored, which only happens if a date was set by hand)
+    fn skew(&self) -> i64 {
+        self.commit_time - self.author_time
+    }
+}
+
+// A first-parent commit that was committed before its parent
+struct OutOfOrder<'a> {
+    commit: &'a DatedCommit,
+    parent: &'a DatedCommit,
+}
+
+impl OutOfOrder<'_> {
+    fn gap(&self) -> i64 {
+        self.parent.commit_time - self.commit.commit_time
+    }
+}
+
+fn dated_commits(first_parent: bool, opts: &GitLogOptions) -> Option<Vec<DatedCommit>> {
+    let mut cmd = Command::new("git");
+    cmd.arg("log");
+    cmd.arg("--format=%H%x00%at%x00%ct%x00%s%x00");
+    if first_parent {
+        cmd.arg("--first-parent");
+    } else {
+        cmd.args(author_filter_args(&opts.authors));
+    }
+    cmd.args(date_filter_args(opts));
+    if let Some(rev) = &opts.rev {
+        cmd.arg(rev);
+    }
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git log`");
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let fields: Vec<&str> = stdout.split('\0').collect();
+    let commits = fields
+        .chunks_exact(4)
+        .map(|entry| DatedCommit {
+            hash: entry[0].trim_start_matches('\n').to_string(),
+            author_time: entry[1].parse().unwrap_or(0),
+            commit_time: entry[2].parse().unwrap_or(0),
+            subject: entry[3].to_string(),
+        })
+        .collect();
+    Some(commits)
+}
+
+fn format_time(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|d| d.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+fn format_days(seconds: i64) -> String {
+    format!("{:.1} days", seconds as f64 / DAY as f64)
+}
+
+// Look for signs that history was rewritten or imported: commits whose committer date is far from
+// their author date (e.g. after a large rebase, or backdating), and commits on the first-parent
+// history that are older than their parent
+pub fn audit_dates(max_skew_days: i64, opts: &GitLogOptions) {
+    let (Some(commits), Some(first_parent)) =
+        (dated_commits(false, opts), dated_commits(true, opts))
+    else {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return;
+    };
+
+    let max_skew = max_skew_days * DAY;
+    let mut skewed: Vec<&DatedCommit> = commits
+        .iter()
+        .filter(|c| c.skew().abs() > max_skew)
+        .collect();
+    skewed.sort_by_key(|c| -c.skew().abs());
+
+    // The log lists each commit before its first parent
+    let mut out_of_order: Vec<OutOfOrder> = first_parent
+        .windows(2)
+        .filter(|pair| pair[0].commit_time < pair[1].commit_time)
+        .map(|pair| OutOfOrder {
+            commit: &pair[0],
+            parent: &pair[1],
+        })
+        .collect();
+    out_of_order.sort_by_key(|o| -o.gap());
+
+    if opts.json {
+        let skewed_json: Vec<JsonValue> = skewed
+            .iter()
+            .map(|c| {
+                json::object! {
+                    hash: c.hash.clone(),
+                    authored: format_time(c.author_time),
+                    committed: format_time(c.commit_time),
+                    skew_days: c.skew() as f64 / DAY as f64,
+                    subject: c.subject.clone(),
+                }
+            })
+            .collect();
+        let out_of_order_json: Vec<JsonValue> = out_of_order
+            .iter()
+            .map(|o| {
+                json::object! {
+                    hash: o.commit.hash.clone(),
+                    committed: format_time(o.commit.commit_time),
+                    parent: o.parent.hash.clone(),
+                    parent_committed: format_time(o.parent.commit_time),
+                    subject: o.commit.subject.clone(),
+                }
+            })
+            .collect();
+        let audit = json::object! {
+            commits: commits.len(),
+            max_skew_days: max_skew_days,
+            skewed: skewed_json,
+            out_of_order: out_of_order_json,
+        };
+        println!("{}", audit.pretty(2));
+        return;
+    }
+
+    println!("Checked {} commits.", commits.len());
+
+    println!(
+        "\n{} commits were committed more than {} days before or after they were authored.",
+        skewed.len(),
+        max_skew_days
+    );
+    if !skewed.is_empty() {
+        let mut table = Table::new("{:<}  {:<}  {:<}  {:>}  {:<}").with_row(row!(
+            "Commit",
+            "Authored",
+            "Committed",
+            "Skew",
+            "Subject"
+        ));
+        for c in skewed.iter().take(WORST_N) {
+            table.add_row(row!(
+                c.hash.short(),
+                format_time(c.author_time),
+                format_time(c.commit_time),
+                format_days(c.skew()),
+                &c.subject
+            ));
+        }
+        print!("{}", table);
+    }
+
+    println!(
+        "\n{} commits on the first-parent history were committed before their parent.",
+        out_of_order.len()
+    );
+    if !out_of_order.is_empty() {
+        let mut table = Table::new("{:<}  {:<}  {:<}  {:>}  {:<}").with_row(row!(
+            "Commit",
+            "Committed",
+            "Parent committed",
+            "Gap",
+            "Subject"
+        ));
+        for o in out_of_order.iter().take(WORST_N) {
+            table.add_row(row!(
+                o.commit.hash.short(),
+                format_time(o.commit.commit_time),
+                format_time(o.parent.commit_time),
+                format_days(o.gap()),
+                &o.commit.subject
+            ));
+        }
+        print!("{}", table);
+    }
+}
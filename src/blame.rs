@@ -0,0 +1,342 @@
+use super::commit::HashFormat;
+use super::contributions::add_author_commits;
+use super::identity::GitIdentity;
+use super::opts::GitLogOptions;
+use chrono::{DateTime, Datelike, Local};
+use json::JsonValue;
+use std::collections::{BTreeMap, HashMap};
+use std::process::{Command, Stdio};
+use tabular::{row, Table};
+
+// The commit that last changed a line of a file
+pub struct BlameLine {
+    pub hash: String,
+    pub subject: String,
+    pub name: String,
+    pub email: String,
+    pub time: i64,
+}
+
+// Attribute each line of a file (at the given revision, or in the working tree) to the commit that
+// last changed it
+pub fn blame(path: &str, rev: Option<&str>) -> Option<Vec<BlameLine>> {
+    let mut cmd = Command::new("git");
+    cmd.arg("blame");
+    cmd.arg("--line-porcelain");
+    if let Some(rev) = rev {
+        cmd.arg(rev);
+    }
+    cmd.arg("--");
+    cmd.arg(path);
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git blame`");
+
+    if !output.status.success() {
+        return None;
+    }
+
+    // Each line of the file is preceded by the details of its commit, and is itself prefixed with a
+    // tab
+    let mut lines = Vec::new();
+    let mut hash = String::new();
+    let mut subject = String::new();
+    let mut name = String::new();
+    let mut email = String::new();
+    let mut time = 0;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if line.starts_with('\t') {
+            lines.push(BlameLine {
+                hash: hash.clone(),
+                subject: subject.clone(),
+                name: name.clone(),
+                email: email.clone(),
+                time,
+            });
+        } else if let Some(author) = line.strip_prefix("author ") {
+            name = author.to_string();
+        } else if let Some(mail) = line.strip_prefix("author-mail ") {
+            email = mail
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_string();
+        } else if let Some(t) = line.strip_prefix("author-time ") {
+            time = t.parse().unwrap_or(0);
+        } else if let Some(s) = line.strip_prefix("summary ") {
+            subject = s.to_string();
+        } else if let Some(h) = line.split(' ').next().filter(|h| is_full_hash(h)) {
+            // Each group of details starts with "<hash> <original line> <final line> ..."
+            hash = h.to_string();
+        }
+    }
+
+    Some(lines)
+}
+
+fn is_full_hash(s: &str) -> bool {
+    (s.len() == 40 || s.len() == 64) && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// How much of a file each author last touched
+pub struct BlameSummary {
+    pub lines: usize,
+    // Each author's identity and number of lines, most lines first
+    pub authors: Vec<(GitIdentity, usize, OldestNewest)>,
+    pub dates: OldestNewest,
+}
+
+// The oldest and newest lines (as timestamps)
+#[derive(Clone, Copy)]
+pub struct OldestNewest {
+    pub oldest: i64,
+    pub newest: i64,
+}
+
+impl OldestNewest {
+    fn new(time: i64) -> Self {
+        OldestNewest {
+            oldest: time,
+            newest: time,
+        }
+    }
+
+    fn add(&mut self, time: i64) {
+        self.oldest = self.oldest.min(time);
+        self.newest = self.newest.max(time);
+    }
+}
+
+pub fn blame_summary(path: &str, opts: &GitLogOptions) -> Option<BlameSummary> {
+    let lines = blame(path, opts.rev.as_deref())?;
+    let first = lines.first()?;
+
+    let mut author_lines = HashMap::new();
+    let mut author_dates: HashMap<String, OldestNewest> = HashMap::new();
+    let mut dates = OldestNewest::new(first.time);
+    for line in &lines {
+        add_author_commits(&mut author_lines, line.name.clone(), line.email.clone(), 1);
+        author_dates
+            .entry(line.email.clone())
+            .and_modify(|d| d.add(line.time))
+            .or_insert_with(|| OldestNewest::new(line.time));
+        dates.add(line.time);
+    }
+
+    let mut authors: Vec<(GitIdentity, usize, OldestNewest)> = author_lines
+        .into_values()
+        .map(|(identity, n)| {
+            let dates = author_dates[&identity.email];
+            (identity, n, dates)
+        })
+        .collect();
+    authors.sort_by(|(a, a_n, _), (b, b_n, _)| b_n.cmp(a_n).then_with(|| a.email.cmp(&b.email)));
+
+    Some(BlameSummary {
+        lines: lines.len(),
+        authors,
+        dates,
+    })
+}
+
+fn format_date(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|d| d.with_timezone(&Local).format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+pub fn display_blame_summary(path: &str, opts: &GitLogOptions) {
+    let Some(summary) = blame_summary(path, opts) else {
+        println!(
+            "Unable to blame {:?}.  Is it a non-empty file tracked by git?",
+            path
+        );
+        return;
+    };
+
+    let percentage = |n: usize| (n * 100) as f64 / summary.lines as f64;
+    if opts.json {
+        let authors: Vec<JsonValue> = summary
+            .authors
+            .iter()
+            .map(|(identity, n, dates)| {
+                json::object! {
+                    email: identity.email.clone(),
+                    names: identity.names.clone(),
+                    lines: *n,
+                    percentage: percentage(*n),
+                    oldest: format_date(dates.oldest),
+                    newest: format_date(dates.newest),
+                }
+            })
+            .collect();
+        let summary = json::object! {
+            path: path,
+            lines: summary.lines,
+            oldest: format_date(summary.dates.oldest),
+            newest: format_date(summary.dates.newest),
+            authors: authors,
+        };
+        println!("{}", summary.pretty(2));
+        return;
+    }
+
+    let mut table = Table::new("{:<}  {:>}  {:>}  {:<}  {:<}")
+        .with_row(row!("Author", "Lines", "Share", "Oldest", "Newest"));
+    for (identity, n, dates) in &summary.authors {
+        let name = identity.names.first().unwrap_or(&identity.email);
+        table.add_row(row!(
+            format!("{} <{}>", name, identity.email),
+            n,
+            format!("{:.1}%", percentage(*n)),
+            format_date(dates.oldest),
+            format_date(dates.newest)
+        ));
+    }
+    print!("{}", table);
+    println!(
+        "\n{} lines, last changed between {} and {}.",
+        summary.lines,
+        format_date(summary.dates.oldest),
+        format_date(summary.dates.newest)
+    );
+}
+
+// The number of commits to list as having written the most surviving lines
+const BLAME_AGE_TOP_COMMITS: usize = 10;
+
+const BAR_MAX_WIDTH: usize = 30;
+
+// How old the lines of a file are, and which commits wrote them
+pub struct BlameAge {
+    pub lines: usize,
+    // The number of lines last changed in each year
+    pub years: BTreeMap<i32, usize>,
+    // The commits that wrote the most surviving lines (hash, subject, date, lines), most first
+    pub commits: Vec<(String, String, i64, usize)>,
+    // The age of the median line, in days
+    pub median_age_days: i64,
+}
+
+pub fn blame_age(path: &str, opts: &GitLogOptions) -> Option<BlameAge> {
+    let lines = blame(path, opts.rev.as_deref())?;
+    if lines.is_empty() {
+        return None;
+    }
+
+    let mut years: BTreeMap<i32, usize> = BTreeMap::new();
+    let mut commits: HashMap<&str, (&str, i64, usize)> = HashMap::new();
+    for line in &lines {
+        if let Some(date) = DateTime::from_timestamp(line.time, 0) {
+            *years.entry(date.with_timezone(&Local).year()).or_insert(0) += 1;
+        }
+        commits
+            .entry(&line.hash)
+            .or_insert((&line.subject, line.time, 0))
+            .2 += 1;
+    }
+
+    let mut commits: Vec<(String, String, i64, usize)> = commits
+        .into_iter()
+        .map(|(hash, (subject, time, n))| (hash.to_string(), subject.to_string(), time, n))
+        .collect();
+    commits.sort_by(|(a, _, _, a_n), (b, _, _, b_n)| b_n.cmp(a_n).then_with(|| a.cmp(b)));
+    commits.truncate(BLAME_AGE_TOP_COMMITS);
+
+    let mut times: Vec<i64> = lines.iter().map(|line| line.time).collect();
+    times.sort_unstable();
+    let median = times[times.len() / 2];
+    let median_age_days = (Local::now().timestamp() - median).max(0) / (24 * 60 * 60);
+
+    Some(BlameAge {
+        lines: lines.len(),
+        years,
+        commits,
+        median_age_days,
+    })
+}
+
+pub fn display_blame_age(path: &str, opts: &GitLogOptions) {
+    let Some(age) = blame_age(path, opts) else {
+        println!(
+            "Unable to blame {:?}.  Is it a non-empty file tracked by git?",
+            path
+        );
+        return;
+    };
+
+    let percentage = |n: usize| (n * 100) as f64 / age.lines as f64;
+    if opts.json {
+        let years: Vec<JsonValue> = age
+            .years
+            .iter()
+            .map(|(year, n)| json::object! { year: *year, lines: *n, percentage: percentage(*n) })
+            .collect();
+        let commits: Vec<JsonValue> = age
+            .commits
+            .iter()
+            .map(|(hash, subject, time, n)| {
+                json::object! {
+                    hash: hash.clone(),
+                    subject: subject.clone(),
+                    date: format_date(*time),
+                    lines: *n,
+                }
+            })
+            .collect();
+        let age = json::object! {
+            path: path,
+            lines: age.lines,
+            median_age_days: age.median_age_days,
+            years: years,
+            commits: commits,
+        };
+        println!("{}", age.pretty(2));
+        return;
+    }
+
+    let max_lines = age.years.values().copied().max().unwrap_or(0);
+    let mut table =
+        Table::new("{:<}  {:>}  {:>}  {:<}").with_row(row!("Year", "Lines", "Share", ""));
+    // Include years in which none of the surviving lines were written, so that gaps are visible
+    let first = age.years.keys().next().copied().unwrap_or(0);
+    let last = age.years.keys().next_back().copied().unwrap_or(0);
+    for year in first..=last {
+        let n = age.years.get(&year).copied().unwrap_or(0);
+        table.add_row(row!(
+            year,
+            n,
+            format!("{:.1}%", percentage(n)),
+            "█".repeat((n * BAR_MAX_WIDTH).div_ceil(max_lines))
+        ));
+    }
+    print!("{}", table);
+
+    println!();
+    let mut table =
+        Table::new("{:<}  {:<}  {:>}  {:<}").with_row(row!("Commit", "Date", "Lines", "Subject"));
+    for (hash, subject, time, n) in &age.commits {
+        table.add_row(row!(hash.short(), format_date(*time), n, subject));
+    }
+    print!("{}", table);
+
+    println!(
+        "\n{} lines; half of them were last changed more than {} ago.",
+        age.lines,
+        format_age(age.median_age_days)
+    );
+}
+
+fn format_age(days: i64) -> String {
+    if days >= 365 {
+        format!("{:.1} years", days as f64 / 365.25)
+    } else if days >= 60 {
+        format!("{} months", days / 30)
+    } else if days == 1 {
+        String::from("1 day")
+    } else {
+        format!("{} days", days)
+    }
+}
@@ -1,12 +1,69 @@
+use super::context::Context;
 use super::opts::GitLogOptions;
+use super::settings::SETTINGS;
+use chrono::{DateTime, FixedOffset, Local};
+use colored::*;
+use json::JsonValue;
+use std::convert::TryFrom;
 use std::process::{Command, Stdio};
+use tabular::{row, Table};
 
 pub enum BranchListings {
     Local,
     Remotes,
 }
 
+pub struct LocalBranch {
+    pub name: String,
+    pub hash: String,
+    pub upstream: Option<String>,
+    pub ahead: Option<usize>,
+    pub behind: Option<usize>,
+    pub date: Option<DateTime<FixedOffset>>,
+}
+
+pub struct RemoteBranch {
+    pub name: String,
+    pub hash: String,
+    pub date: Option<DateTime<FixedOffset>>,
+}
+
+impl LocalBranch {
+    fn to_json(&self) -> JsonValue {
+        json::object! {
+            name: self.name.clone(),
+            hash: self.hash.clone(),
+            upstream: self.upstream.clone(),
+            ahead: self.ahead,
+            behind: self.behind,
+            date: self.date.map(|d| d.to_rfc3339()),
+        }
+    }
+}
+
+impl RemoteBranch {
+    fn to_json(&self) -> JsonValue {
+        json::object! {
+            name: self.name.clone(),
+            hash: self.hash.clone(),
+            date: self.date.map(|d| d.to_rfc3339()),
+        }
+    }
+}
+
 pub fn get_branch_names(bt: BranchListings, opts: &GitLogOptions) {
+    if opts.json {
+        let branches: Vec<JsonValue> = match bt {
+            BranchListings::Local => local_branches().iter().map(|b| b.to_json()).collect(),
+            BranchListings::Remotes => remote_branches_detailed()
+                .iter()
+                .map(|b| b.to_json())
+                .collect(),
+        };
+        println!("{}", JsonValue::Array(branches).pretty(2));
+        return;
+    }
+
     let branch_names: Option<String> = match bt {
         BranchListings::Local => branch_names(opts),
         BranchListings::Remotes => remote_branches(opts),
@@ -27,6 +84,390 @@ pub fn get_branch_names(bt: BranchListings, opts: &GitLogOptions) {
     }
 }
 
+// Show every local branch alongside its upstream and how far ahead or behind it is, so that it is
+// clear what needs pushing, pulling, or pruning (e.g. after a fetch)
+pub fn display_branch_sync(opts: &GitLogOptions) {
+    let branches = local_branches();
+    if opts.json {
+        let branches: Vec<JsonValue> = branches.iter().map(|b| b.to_json()).collect();
+        println!("{}", JsonValue::Array(branches).pretty(2));
+        return;
+    }
+
+    let mut table = Table::new("{:<}  {:<}  {:>}  {:>}  {:<}")
+        .with_row(row!("Branch", "Upstream", "Ahead", "Behind", "Status"));
+    for branch in branches {
+        let count = |n: Option<usize>| n.map(|n| n.to_string()).unwrap_or_default();
+        let status = match (&branch.upstream, branch.ahead, branch.behind) {
+            (None, _, _) => "no upstream".normal(),
+            (Some(_), None, _) | (Some(_), _, None) => "gone".red(),
+            (Some(_), Some(0), Some(0)) => "up to date".green(),
+            (Some(_), Some(_), Some(0)) => "push".yellow(),
+            (Some(_), Some(0), Some(_)) => "pull".yellow(),
+            (Some(_), Some(_), Some(_)) => "diverged".red(),
+        };
+        let status = if opts.colour {
+            status.to_string()
+        } else {
+            status.clear().to_string()
+        };
+        table.add_row(row!(
+            &branch.name,
+            branch.upstream.as_deref().unwrap_or(""),
+            count(branch.ahead),
+            count(branch.behind),
+            status
+        ));
+    }
+    print!("{}", table);
+}
+
+// How a local branch relates to the default branch
+pub struct BranchAge {
+    pub name: String,
+    pub diverged: Option<DateTime<FixedOffset>>,
+    pub creator: Option<String>,
+    pub unique_commits: usize,
+    pub last_commit: Option<DateTime<FixedOffset>>,
+}
+
+impl BranchAge {
+    fn to_json(&self) -> JsonValue {
+        json::object! {
+            name: self.name.clone(),
+            diverged: self.diverged.map(|d| d.to_rfc3339()),
+            creator: self.creator.clone(),
+            unique_commits: self.unique_commits,
+            last_commit: self.last_commit.map(|d| d.to_rfc3339()),
+        }
+    }
+}
+
+// For each local branch other than the default branch, find when it diverged from the default
+// branch, who authored its first unique commit, and how many unique commits it has
+pub fn branch_ages(ctx: &Context) -> Option<(String, Vec<BranchAge>)> {
+    let default = ctx.default_branch()?.to_string();
+    let ages = local_branches()
+        .into_iter()
+        .filter(|branch| branch.name != default)
+        .map(|branch| {
+            let diverged = git_output(&["merge-base", &default, &branch.name]).and_then(|base| {
+                let date = git_output(&["show", "-s", "--format=%cI", base.trim()])?;
+                DateTime::parse_from_rfc3339(date.trim()).ok()
+            });
+            let unique = git_output(&[
+                "log",
+                "--reverse",
+                "--format=%an",
+                &format!("{}..{}", default, branch.name),
+            ])
+            .unwrap_or_default();
+            BranchAge {
+                creator: unique.lines().next().map(String::from),
+                unique_commits: unique.lines().count(),
+                name: branch.name,
+                diverged,
+                last_commit: branch.date,
+            }
+        })
+        .collect();
+    Some((default, ages))
+}
+
+pub fn display_branch_ages(ctx: &Context, opts: &GitLogOptions) {
+    let Some((default, ages)) = branch_ages(ctx) else {
+        println!("Unable to determine the default branch of this repository.");
+        return;
+    };
+    if opts.json {
+        let ages: Vec<JsonValue> = ages.iter().map(|b| b.to_json()).collect();
+        println!("{}", JsonValue::Array(ages).pretty(2));
+        return;
+    }
+
+    let now = Local::now();
+    let format_date = |d: Option<DateTime<FixedOffset>>| {
+        d.map(|d| {
+            let days = (now - d.with_timezone(&Local)).num_days();
+            format!("{} ({} days ago)", d.format("%Y-%m-%d"), days)
+        })
+        .unwrap_or_default()
+    };
+    let mut table = Table::new("{:<}  {:<}  {:<}  {:>}  {:<}").with_row(row!(
+        "Branch",
+        format!("Diverged from {}", default),
+        "Creator",
+        "Unique commits",
+        "Last commit"
+    ));
+    for age in ages {
+        table.add_row(row!(
+            &age.name,
+            format_date(age.diverged),
+            age.creator.as_deref().unwrap_or(""),
+            age.unique_commits,
+            format_date(age.last_commit)
+        ));
+    }
+    print!("{}", table);
+}
+
+// The remote-tracking branches that already contain HEAD.  If the current branch has an upstream,
+// only the upstream is checked; otherwise, any remote-tracking branch counts
+pub fn head_pushed_to() -> Vec<String> {
+    if let Some(upstream) =
+        git_output(&["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"])
+    {
+        let upstream = upstream.trim().to_string();
+        let pushed = git_output(&["merge-base", "--is-ancestor", "HEAD", &upstream]).is_some();
+        return if pushed { vec![upstream] } else { vec![] };
+    }
+
+    git_output(&[
+        "branch",
+        "--remotes",
+        "--contains",
+        "HEAD",
+        "--format=%(refname:short)",
+    ])
+    .unwrap_or_default()
+    .lines()
+    .map(String::from)
+    .collect()
+}
+
+// Check whether it is safe to amend HEAD, warning if it has already been pushed (as amending it
+// would rewrite published history).  Returns whether it is safe
+pub fn amend_check() -> bool {
+    if git_output(&["rev-parse", "--verify", "--quiet", "HEAD"]).is_none() {
+        println!("There is no commit to amend.");
+        return true;
+    }
+
+    let pushed_to = head_pushed_to();
+    if pushed_to.is_empty() {
+        println!("HEAD has not been pushed; it is safe to amend.");
+        return true;
+    }
+
+    eprintln!(
+        "{} HEAD has already been pushed to {}.  Amending it will rewrite published history, and \
+        you will need to force push.",
+        "WARNING:".bold().red(),
+        pushed_to.join(", ")
+    );
+    false
+}
+
+// A protected branch (see config::PROTECTED_BRANCHES) and how it compares with the remote
+pub struct ProtectedBranch {
+    pub name: String,
+    // The upstream, or else the branch of the same name on origin
+    pub remote: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+impl ProtectedBranch {
+    // Local and remote have each got commits the other hasn't, so pushing would need a force push,
+    // which would throw away the remote's commits: usually a sign that published history has been
+    // rewritten locally (e.g. by a rebase or an amend)
+    fn diverged(&self) -> bool {
+        self.remote.is_some() && self.ahead > 0 && self.behind > 0
+    }
+
+    fn status(&self) -> ColoredString {
+        match (&self.remote, self.ahead, self.behind) {
+            (None, _, _) => "not on remote".normal(),
+            (Some(_), 0, 0) => "up to date".green(),
+            (Some(_), _, 0) => "push".yellow(),
+            (Some(_), 0, _) => "pull".yellow(),
+            (Some(_), _, _) => "force push needed".bold().red(),
+        }
+    }
+
+    fn to_json(&self) -> JsonValue {
+        json::object! {
+            name: self.name.clone(),
+            remote: self.remote.clone(),
+            ahead: self.ahead,
+            behind: self.behind,
+            status: self.status().clear().to_string(),
+        }
+    }
+}
+
+// Local branches matching any of the given globs, compared with their remotes
+pub fn protected_branches(patterns: &[String]) -> Vec<ProtectedBranch> {
+    let mut cmd = Command::new("git");
+    cmd.arg("for-each-ref");
+    cmd.arg("--format=%(refname:short)%00%(upstream)");
+    cmd.args(
+        patterns
+            .iter()
+            .map(|pattern| format!("refs/heads/{}", pattern)),
+    );
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git for-each-ref`");
+
+    if !output.status.success() {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return vec![];
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (name, upstream) = line.split_once('\0')?;
+            let remote = if upstream.is_empty() {
+                format!("refs/remotes/origin/{}", name)
+            } else {
+                upstream.to_string()
+            };
+            if !ref_exists(&remote) {
+                return Some(ProtectedBranch {
+                    name: name.to_string(),
+                    remote: None,
+                    ahead: 0,
+                    behind: 0,
+                });
+            }
+            let counts = git_output(&[
+                "rev-list",
+                "--left-right",
+                "--count",
+                &format!("refs/heads/{}...{}", name, remote),
+            ])?;
+            let mut counts = counts.split_whitespace().map(|n| n.parse().unwrap_or(0));
+            Some(ProtectedBranch {
+                name: name.to_string(),
+                remote: Some(remote.trim_start_matches("refs/remotes/").to_string()),
+                ahead: counts.next().unwrap_or(0),
+                behind: counts.next().unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+// Show how each protected branch compares with its remote, warning about any that would need a
+// force push.  The comparison is only as recent as the last fetch.  Returns whether none would
+pub fn display_protected_branches(patterns: &[String], opts: &GitLogOptions) -> bool {
+    let branches = protected_branches(patterns);
+    let safe = !branches.iter().any(|branch| branch.diverged());
+
+    if opts.json {
+        let branches: Vec<JsonValue> = branches.iter().map(|b| b.to_json()).collect();
+        println!("{}", JsonValue::Array(branches).pretty(2));
+        return safe;
+    }
+
+    if branches.is_empty() {
+        println!("No local branches match {}.", patterns.join(", "));
+        return safe;
+    }
+
+    let mut table = Table::new("{:<}  {:<}  {:>}  {:>}  {:<}")
+        .with_row(row!("Branch", "Remote", "Ahead", "Behind", "Status"));
+    for branch in &branches {
+        let status = if opts.colour {
+            branch.status().to_string()
+        } else {
+            branch.status().clear().to_string()
+        };
+        table.add_row(row!(
+            &branch.name,
+            branch.remote.as_deref().unwrap_or(""),
+            branch.ahead,
+            branch.behind,
+            status
+        ));
+    }
+    print!("{}", table);
+
+    for branch in branches.iter().filter(|branch| branch.diverged()) {
+        eprintln!(
+            "{} {} has diverged from {}; pushing it would need a force push, discarding {} commit{} on the remote.",
+            "WARNING:".bold().red(),
+            branch.name,
+            branch.remote.as_deref().unwrap_or(""),
+            branch.behind,
+            if branch.behind == 1 { "" } else { "s" }
+        );
+    }
+    safe
+}
+
+// Names commonly used for the default branch, in order of preference
+const DEFAULT_BRANCH_NAMES: [&str; 3] = ["main", "master", "trunk"];
+
+// The branch that others are compared against.  In order, this is:
+//   1. The branch configured as the default (see settings.rs);
+//   2. The branch that the remote's HEAD points to (origin, or else upstream, the usual name for the
+//      original repository of a fork), as this is what the forge considers the default;
+//   3. git's init.defaultBranch, main, master, or trunk, whichever exists first locally, or else on
+//      origin
+// A local branch is preferred to its remote-tracking branch, so that unpushed commits count
+pub fn default_branch() -> Option<String> {
+    if let Some(default_branch) = &SETTINGS.default_branch {
+        return Some(default_branch.clone());
+    }
+
+    for remote in ["origin", "upstream"] {
+        let remote_head_ref = format!("refs/remotes/{}/HEAD", remote);
+        if let Some(remote_head) = git_output(&["symbolic-ref", "--short", &remote_head_ref]) {
+            let remote_head = remote_head.trim();
+            let name = remote_head
+                .strip_prefix(&format!("{}/", remote))
+                .unwrap_or(remote_head);
+            if ref_exists(&format!("refs/heads/{}", name)) {
+                return Some(name.to_string());
+            }
+            return Some(remote_head.to_string());
+        }
+    }
+
+    let names: Vec<&str> = SETTINGS
+        .init_default_branch
+        .iter()
+        .map(String::as_str)
+        .chain(DEFAULT_BRANCH_NAMES)
+        .collect();
+    let local = names
+        .iter()
+        .find(|name| ref_exists(&format!("refs/heads/{}", name)));
+    if let Some(name) = local {
+        return Some(name.to_string());
+    }
+    names
+        .iter()
+        .find(|name| ref_exists(&format!("refs/remotes/origin/{}", name)))
+        .map(|name| format!("origin/{}", name))
+}
+
+// Whether a fully-qualified ref (e.g. "refs/heads/main") exists
+fn ref_exists(full_ref: &str) -> bool {
+    git_output(&["show-ref", "--verify", "--quiet", full_ref]).is_some()
+}
+
+// Run a git command, returning its output if it succeeded
+fn git_output(args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git`");
+
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        None
+    }
+}
+
 pub fn current_branch() -> Option<String> {
     let mut cmd = Command::new("git");
     cmd.arg("rev-parse");
@@ -97,3 +538,95 @@ fn remote_branches(opts: &GitLogOptions) -> Option<String> {
         None
     }
 }
+
+pub fn local_branches() -> Vec<LocalBranch> {
+    for_each_ref(
+        "refs/heads",
+        "%(refname:short)%00%(objectname)%00%(committerdate:iso-strict)%00%(upstream:short)%00%(upstream:track,nobracket)",
+    )
+    .into_iter()
+    .filter_map(|fields| {
+        let [name, hash, date, upstream, track] = <[String; 5]>::try_from(fields).ok()?;
+        let (ahead, behind) = parse_upstream_track(&track, !upstream.is_empty());
+        Some(LocalBranch {
+            name,
+            hash,
+            upstream: if upstream.is_empty() {
+                None
+            } else {
+                Some(upstream)
+            },
+            ahead,
+            behind,
+            date: DateTime::parse_from_rfc3339(&date).ok(),
+        })
+    })
+    .collect()
+}
+
+pub fn remote_branches_detailed() -> Vec<RemoteBranch> {
+    for_each_ref(
+        "refs/remotes",
+        "%(refname:short)%00%(objectname)%00%(committerdate:iso-strict)%00%(symref)",
+    )
+    .into_iter()
+    .filter_map(|fields| {
+        let [name, hash, date, symref] = <[String; 4]>::try_from(fields).ok()?;
+
+        // Skip symbolic refs such as origin/HEAD, as they are not branches in their own right
+        if !symref.is_empty() {
+            return None;
+        }
+
+        Some(RemoteBranch {
+            name,
+            hash,
+            date: DateTime::parse_from_rfc3339(&date).ok(),
+        })
+    })
+    .collect()
+}
+
+// Parse the upstream tracking information from `git for-each-ref` (e.g. "ahead 1, behind 2").
+// If the branch has an upstream, the counts default to zero; if the upstream is gone, there are no
+// counts to report
+fn parse_upstream_track(track: &str, has_upstream: bool) -> (Option<usize>, Option<usize>) {
+    if !has_upstream || track == "gone" {
+        return (None, None);
+    }
+
+    let mut ahead = 0;
+    let mut behind = 0;
+    for part in track.split(", ") {
+        if let Some(n) = part.strip_prefix("ahead ") {
+            ahead = n.parse().unwrap_or(0);
+        } else if let Some(n) = part.strip_prefix("behind ") {
+            behind = n.parse().unwrap_or(0);
+        }
+    }
+
+    (Some(ahead), Some(behind))
+}
+
+// List refs matching the pattern, returning the NUL-separated fields of the given format per ref
+pub fn for_each_ref(pattern: &str, format: &str) -> Vec<Vec<String>> {
+    let mut cmd = Command::new("git");
+    cmd.arg("for-each-ref");
+    cmd.arg(format!("--format={}", format));
+    cmd.arg(pattern);
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git for-each-ref`");
+
+    if output.status.success() {
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.split('\0').map(String::from).collect())
+            .collect()
+    } else {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        vec![]
+    }
+}
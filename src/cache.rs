@@ -0,0 +1,41 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// Responses from forges are cached on disk so that repeated runs are fast and stay within API rate
+// limits.  The cache lives in $XDG_CACHE_HOME/gl (or ~/.cache/gl), and can be safely deleted
+fn cache_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("gl"))
+}
+
+// The cache file for a key such as "github/owner/repo/pulls/1234.json"
+fn cache_path(key: &str) -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join(key))
+}
+
+pub fn read(key: &str) -> Option<String> {
+    fs::read_to_string(cache_path(key)?).ok()
+}
+
+// Failing to write to the cache is not an error, as we can always fetch the value again
+pub fn write(key: &str, contents: &str) {
+    let Some(path) = cache_path(key) else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        if fs::create_dir_all(parent).is_err() {
+            return;
+        }
+    }
+    if let Err(e) = fs::write(&path, contents) {
+        eprintln!(
+            "[WARN] Unable to write to cache at {}: {}",
+            path.display(),
+            e
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use super::opts::GitLogOptions;
+use super::settings::SETTINGS;
+use super::tags::{self, TagSort};
+use chrono::{Local, NaiveDate};
+use clap::ValueEnum;
+use std::cmp::max;
+use textplots::Chart;
+
+// Charts are drawn by textplots on a Braille canvas, where each character is a grid of 2x4 dots.
+// Some fonts render Braille poorly, so the canvas can instead be redrawn with block characters
+// (2x2 quadrants per character) or plain ASCII
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum ChartStyle {
+    Braille,
+    Block,
+    Ascii,
+}
+
+// Quadrant blocks, indexed by upper left (1), upper right (2), lower left (4), and lower right (8)
+const QUADRANT_BLOCKS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+// The size of the chart canvas in dots, from --width and --height (in characters) if given, or else
+// from the size of the terminal.  Textplots requires the canvas to be at least 32x3 dots
+pub fn chart_size(opts: &GitLogOptions) -> (u32, u32) {
+    let tsz = termsize::get().unwrap_or(termsize::Size { rows: 0, cols: 0 });
+
+    // Set reasonable defaults for graph size
+    let w = match opts.chart_width {
+        Some(w) => w as u32 * 2,
+        None if tsz.cols == 0 => 40,
+        None => tsz.cols.into(),
+    };
+    let h = match opts.chart_height {
+        Some(h) => h as u32 * 4,
+        None if tsz.rows == 0 => 60,
+        None => tsz.rows.into(),
+    };
+
+    (max(w, 32), max(h, 3))
+}
+
+// A date to mark on a chart, such as a release
+pub struct ChartMarker {
+    pub date: NaiveDate,
+    pub label: String,
+}
+
+// Tags (dated by the commit they point to) and the milestones given in settings, in date order
+pub fn chart_markers() -> Vec<ChartMarker> {
+    let mut markers: Vec<ChartMarker> = tags::tags(None, &TagSort::Date)
+        .into_iter()
+        .filter_map(|tag| {
+            Some(ChartMarker {
+                date: tag.date?.with_timezone(&Local).date_naive(),
+                label: tag.name,
+            })
+        })
+        .collect();
+
+    for milestone in &SETTINGS.chart_markers {
+        let parsed = milestone.split_once('=').and_then(|(date, label)| {
+            let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok()?;
+            Some(ChartMarker {
+                date,
+                label: label.trim().to_string(),
+            })
+        });
+        match parsed {
+            Some(marker) => markers.push(marker),
+            None => eprintln!(
+                "[WARN] Ignoring chart marker {:?}: expected \"YYYY-MM-DD=label\"",
+                milestone
+            ),
+        }
+    }
+
+    markers.sort_by_key(|marker| marker.date);
+    markers
+}
+
+// Eighths of a block, for sparklines
+const SPARK_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// A one-line chart of the values, scaled so that the largest is a full block
+pub fn sparkline(values: &[usize]) -> String {
+    let max = values.iter().copied().max().unwrap_or(0);
+    values
+        .iter()
+        .map(|n| {
+            let level = (n * (SPARK_BLOCKS.len() - 1)).checked_div(max).unwrap_or(0);
+            SPARK_BLOCKS[level]
+        })
+        .collect()
+}
+
+// Print the chart (with its axes and labels) in the style given in the options
+pub fn display_chart(chart: &mut Chart, opts: &GitLogOptions) {
+    if opts.chart_style == ChartStyle::Braille {
+        chart.nice();
+        return;
+    }
+
+    // Borders are drawn as dotted Braille lines, which don't translate to other styles, so we only
+    // draw the axes
+    chart.axis();
+    chart.figures();
+    let rendered: String = chart
+        .to_string()
+        .chars()
+        .map(|c| restyle_braille(c, opts.chart_style))
+        .collect();
+    println!("{}", rendered);
+}
+
+// Redraw a Braille character in the given style, leaving any other character as is
+fn restyle_braille(c: char, style: ChartStyle) -> char {
+    let dots = match (c as u32).checked_sub(0x2800) {
+        Some(dots) if dots <= 0xFF => dots,
+        _ => return c,
+    };
+
+    // Braille dots are numbered down the left column (1, 2, 3, 7) then the right (4, 5, 6, 8)
+    let upper_left = dots & 0x03 != 0;
+    let upper_right = dots & 0x18 != 0;
+    let lower_left = dots & 0x44 != 0;
+    let lower_right = dots & 0xA0 != 0;
+
+    match style {
+        ChartStyle::Braille => c,
+        ChartStyle::Block => {
+            let i = upper_left as usize
+                | (upper_right as usize) << 1
+                | (lower_left as usize) << 2
+                | (lower_right as usize) << 3;
+            QUADRANT_BLOCKS[i]
+        }
+        ChartStyle::Ascii => match (upper_left || upper_right, lower_left || lower_right) {
+            (true, true) => '|',
+            (true, false) => '\'',
+            (false, true) => '.',
+            (false, false) => ' ',
+        },
+    }
+}
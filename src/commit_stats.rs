@@ -0,0 +1,268 @@
+use super::commit::HashFormat;
+use super::diff::{diff_stat_args, renamed_path};
+use super::opts::GitLogOptions;
+use super::theme::THEME;
+use super::topics::{conventional_commit, ConventionalCommit};
+use super::trailers::{parse_trailers, Trailer};
+use colored::*;
+use json::JsonValue;
+use std::collections::BTreeMap;
+use std::process::{Command, Stdio};
+use tabular::{row, Table};
+
+// Lines changed in one file.  Binary files have no line counts
+struct FileChange {
+    path: String,
+    added: Option<usize>,
+    deleted: Option<usize>,
+}
+
+pub struct CommitStats {
+    hash: String,
+    author: String,
+    date: String,
+    subject: String,
+    conventional: Option<ConventionalCommit>,
+    trailers: Vec<Trailer>,
+    files: Vec<FileChange>,
+    local_branches: usize,
+    remote_branches: usize,
+    // Oldest first
+    tags: Vec<String>,
+}
+
+impl CommitStats {
+    fn lines_added(&self) -> usize {
+        self.files.iter().filter_map(|file| file.added).sum()
+    }
+
+    fn lines_deleted(&self) -> usize {
+        self.files.iter().filter_map(|file| file.deleted).sum()
+    }
+
+    // Files grouped by the directory they are in ("." for the top level)
+    fn files_by_directory(&self) -> BTreeMap<&str, Vec<&FileChange>> {
+        let mut directories: BTreeMap<&str, Vec<&FileChange>> = BTreeMap::new();
+        for file in &self.files {
+            let directory = file.path.rsplit_once('/').map_or(".", |(dir, _)| dir);
+            directories.entry(directory).or_default().push(file);
+        }
+        directories
+    }
+}
+
+// Statistics for a single commit.  A merge is compared with its first parent, i.e. the changes it
+// brought in to the branch it was merged into
+pub fn commit_stats(rev: &str, opts: &GitLogOptions) -> Option<CommitStats> {
+    let mut cmd = Command::new("git");
+    cmd.arg("show");
+    cmd.arg("--numstat");
+    cmd.arg("--diff-merges=first-parent");
+    cmd.args(diff_stat_args(opts));
+    cmd.arg("--date=format:%Y-%m-%d %H:%M");
+    cmd.arg("--format=%H%x00%an <%ae>%x00%ad%x00%B%x00");
+    cmd.arg(format!("{}^{{commit}}", rev));
+    cmd.arg("--");
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git show`");
+
+    if !output.status.success() {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut parts = stdout.splitn(5, '\0');
+    let hash = parts.next().unwrap_or("").to_string();
+    let author = parts.next().unwrap_or("").to_string();
+    let date = parts.next().unwrap_or("").to_string();
+    let message = parts.next().unwrap_or("");
+    let numstat = parts.next().unwrap_or("");
+
+    let subject = message.lines().next().unwrap_or("").to_string();
+    let files = numstat
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let (added, deleted, path) = (parts.next()?, parts.next()?, parts.next()?);
+            Some(FileChange {
+                path: renamed_path(path),
+                added: added.parse().ok(),
+                deleted: deleted.parse().ok(),
+            })
+        })
+        .collect();
+
+    let branches = refs_containing(&hash, &["branch", "--all"], "%(refname)");
+    let remote_branches = branches
+        .iter()
+        .filter(|branch| branch.starts_with("refs/remotes/"))
+        .count();
+
+    Some(CommitStats {
+        conventional: conventional_commit(&subject),
+        trailers: parse_trailers(message),
+        files,
+        local_branches: branches.len() - remote_branches,
+        remote_branches,
+        tags: refs_containing(&hash, &["tag", "--sort=creatordate"], "%(refname:short)"),
+        hash,
+        author,
+        date,
+        subject,
+    })
+}
+
+// The refs of a kind (branches or tags) that contain the given commit
+fn refs_containing(hash: &str, args: &[&str], format: &str) -> Vec<String> {
+    let mut cmd = Command::new("git");
+    cmd.args(args);
+    cmd.arg(format!("--format={}", format));
+    cmd.arg("--contains");
+    cmd.arg(hash);
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git`");
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        // Symbolic refs such as origin/HEAD are not branches of their own
+        .filter(|line| !line.is_empty() && !line.ends_with("/HEAD"))
+        .map(String::from)
+        .collect()
+}
+
+fn plural(n: usize, word: &str, plural: &str) -> String {
+    format!("{} {}", n, if n == 1 { word } else { plural })
+}
+
+fn line_count(n: Option<usize>, sign: char) -> String {
+    n.map_or_else(|| String::from("bin"), |n| format!("{}{}", sign, n))
+}
+
+pub fn display_commit_stats(stats: CommitStats, opts: &GitLogOptions) {
+    if opts.json {
+        let conventional = match &stats.conventional {
+            Some(conventional) => json::object! {
+                type: conventional.kind.clone(),
+                scope: conventional.scope.clone(),
+                breaking: conventional.breaking,
+            },
+            None => JsonValue::Null,
+        };
+        let files: Vec<JsonValue> = stats
+            .files
+            .iter()
+            .map(|file| {
+                json::object! { path: file.path.clone(), added: file.added, deleted: file.deleted }
+            })
+            .collect();
+        let trailers: Vec<JsonValue> = stats
+            .trailers
+            .iter()
+            .map(|trailer| json::object! { key: trailer.key.clone(), value: trailer.value.clone() })
+            .collect();
+        let value = json::object! {
+            hash: stats.hash.clone(),
+            author: stats.author.clone(),
+            date: stats.date.clone(),
+            subject: stats.subject.clone(),
+            conventional: conventional,
+            trailers: JsonValue::Array(trailers),
+            files: JsonValue::Array(files),
+            added: stats.lines_added(),
+            deleted: stats.lines_deleted(),
+            local_branches: stats.local_branches,
+            remote_branches: stats.remote_branches,
+            tags: stats.tags.clone(),
+        };
+        println!("{}", value.pretty(2));
+        return;
+    }
+
+    let hash = stats.hash.short();
+    if opts.colour {
+        println!("{}  {}", THEME.hash.paint(&hash), stats.subject.bold());
+        println!("Author:   {}", THEME.author.paint(&stats.author));
+        println!("Date:     {}", THEME.date.paint(&stats.date));
+    } else {
+        println!("{}  {}", hash, stats.subject);
+        println!("Author:   {}", stats.author);
+        println!("Date:     {}", stats.date);
+    }
+
+    if let Some(conventional) = &stats.conventional {
+        let mut kind = conventional.kind.clone();
+        if let Some(scope) = &conventional.scope {
+            kind.push_str(&format!(" ({})", scope));
+        }
+        if conventional.breaking {
+            kind.push_str(", breaking");
+        }
+        println!("Type:     {}", kind);
+    }
+    for (i, trailer) in stats.trailers.iter().enumerate() {
+        let label = if i == 0 { "Trailers:" } else { "" };
+        println!("{:<10}{}: {}", label, trailer.key, trailer.value);
+    }
+
+    println!();
+    if stats.files.is_empty() {
+        println!("No files changed.");
+    } else {
+        let mut table = Table::new("{:<}  {:>}  {:>}");
+        for (directory, files) in stats.files_by_directory() {
+            let added: usize = files.iter().filter_map(|file| file.added).sum();
+            let deleted: usize = files.iter().filter_map(|file| file.deleted).sum();
+            table.add_row(row!(
+                format!("{}/", directory),
+                format!("+{}", added),
+                format!("-{}", deleted)
+            ));
+            for file in files {
+                let name = file.path.rsplit('/').next().unwrap_or(&file.path);
+                table.add_row(row!(
+                    format!("  {}", name),
+                    line_count(file.added, '+'),
+                    line_count(file.deleted, '-')
+                ));
+            }
+        }
+        print!("{}", table);
+        let summary = format!(
+            "{} changed: +{} -{}",
+            plural(stats.files.len(), "file", "files"),
+            stats.lines_added(),
+            stats.lines_deleted()
+        );
+        if opts.colour {
+            println!("{}", summary.bold());
+        } else {
+            println!("{}", summary);
+        }
+    }
+
+    println!();
+    println!(
+        "Contained in {} ({} local, {} remote) and {}{}",
+        plural(
+            stats.local_branches + stats.remote_branches,
+            "branch",
+            "branches"
+        ),
+        stats.local_branches,
+        stats.remote_branches,
+        plural(stats.tags.len(), "tag", "tags"),
+        stats
+            .tags
+            .first()
+            .map(|tag| format!(", first released in {}", tag))
+            .unwrap_or_default()
+    );
+}
@@ -0,0 +1,260 @@
+use super::commit::{date_filter_args, merge_filter_arg};
+use super::diff::{diff_stat_args, numstat_usable, parse_numstat_line};
+use super::identity::{normalise_email, GitIdentity};
+use super::opts::GitLogOptions;
+use super::pathspec::exclude_pathspec_args;
+use super::settings::SETTINGS;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::process::{Command, Stdio};
+use tabular::{row, Table};
+
+// Aggregated changes over some set of commits
+#[derive(Default)]
+pub struct ChangeSummary {
+    commits: usize,
+    authors: HashSet<String>,
+    lines_added: usize,
+    lines_deleted: usize,
+    days: BTreeSet<String>,
+    files: HashMap<String, usize>,
+}
+
+// A time window given as "<since>..<until>", where either end may be omitted (e.g.
+// "2024-01-01..2024-04-01", or "3 months ago..")
+#[derive(Clone)]
+pub struct DateRange {
+    repr: String,
+    since: Option<String>,
+    until: Option<String>,
+}
+
+impl DateRange {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let Some((since, until)) = s.split_once("..") else {
+            return Err(String::from(
+                "expected a range of the form \"<since>..<until>\"",
+            ));
+        };
+        let bound = |b: &str| {
+            let b = b.trim();
+            if b.is_empty() {
+                None
+            } else {
+                Some(b.to_string())
+            }
+        };
+        Ok(DateRange {
+            repr: s.to_string(),
+            since: bound(since),
+            until: bound(until),
+        })
+    }
+}
+
+impl ChangeSummary {
+    fn lines_changed(&self) -> usize {
+        self.lines_added + self.lines_deleted
+    }
+
+    fn first_commit(&self) -> &str {
+        self.days.iter().next().map(|s| s.as_str()).unwrap_or("-")
+    }
+
+    fn last_commit(&self) -> &str {
+        self.days
+            .iter()
+            .next_back()
+            .map(|s| s.as_str())
+            .unwrap_or("-")
+    }
+
+    // The files with the most lines changed, and how many lines were changed in each
+    fn busiest_files(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut files: Vec<(&str, usize)> = self
+            .files
+            .iter()
+            .map(|(file, lines)| (file.as_str(), *lines))
+            .collect();
+        files.sort_by_key(|(file, lines)| (Reverse(*lines), *file));
+        files.truncate(n);
+        files
+    }
+}
+
+// Summarise the commits selected by the given git log arguments (in addition to the filters in the
+// options)
+fn change_summary(filter_args: &[String], opts: &GitLogOptions) -> ChangeSummary {
+    // git log --no-merges --numstat --date=short --format=... <filters>
+    let mut cmd = Command::new("git");
+    cmd.arg("log");
+    cmd.args(merge_filter_arg(opts));
+    if opts.first_parent {
+        cmd.arg("--first-parent");
+    }
+    cmd.arg("--numstat");
+    cmd.args(diff_stat_args(opts));
+    cmd.arg("--date=short");
+    cmd.arg("--format=%x00%ad%x00%ae%x00%an");
+    cmd.args(date_filter_args(opts));
+    cmd.args(filter_args);
+    cmd.args(exclude_pathspec_args(opts));
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git log`");
+
+    let mut summary = ChangeSummary::default();
+    if !numstat_usable(&output, opts) {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return summary;
+    }
+
+    // Numstat lines belong to the most recent header, so we need to know whether to skip them
+    let mut skip_commit = false;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(header) = line.strip_prefix('\0') {
+            let mut parts = header.split('\0');
+            let date = parts.next().unwrap_or("").to_string();
+            let id = GitIdentity {
+                email: parts.next().unwrap_or("").to_string(),
+                names: parts.next().map(String::from).into_iter().collect(),
+            };
+            skip_commit = opts.no_bots && id.is_bot();
+            if !skip_commit {
+                summary.commits += 1;
+                summary.days.insert(date);
+                summary.authors.insert(normalise_email(&id.email));
+            }
+        } else if skip_commit {
+            continue;
+        } else if let Some((added, deleted, file)) = parse_numstat_line(line, opts) {
+            summary.lines_added += added;
+            summary.lines_deleted += deleted;
+            *summary.files.entry(file).or_insert(0) += added + deleted;
+        }
+    }
+
+    summary
+}
+
+// Authors are matched the same way as --author (i.e., by regex against name or email)
+pub fn compare_authors(author1: &str, author2: &str, opts: &GitLogOptions) {
+    let summary1 = change_summary(&[format!("--author={}", author1)], opts);
+    let summary2 = change_summary(&[format!("--author={}", author2)], opts);
+
+    let mut table = Table::new("{:<}  {:<}  {:<}").with_row(row!("", author1, author2));
+    table.add_row(row!("Commits", summary1.commits, summary2.commits));
+    table.add_row(row!(
+        "Lines added",
+        summary1.lines_added,
+        summary2.lines_added
+    ));
+    table.add_row(row!(
+        "Lines deleted",
+        summary1.lines_deleted,
+        summary2.lines_deleted
+    ));
+    table.add_row(row!(
+        "Active days",
+        summary1.days.len(),
+        summary2.days.len()
+    ));
+    table.add_row(row!(
+        "First commit",
+        summary1.first_commit(),
+        summary2.first_commit()
+    ));
+    table.add_row(row!(
+        "Last commit",
+        summary1.last_commit(),
+        summary2.last_commit()
+    ));
+
+    let busiest1 = summary1.busiest_files(SETTINGS.compare_top_n_files);
+    let busiest2 = summary2.busiest_files(SETTINGS.compare_top_n_files);
+    for i in 0..busiest1.len().max(busiest2.len()) {
+        let label = if i == 0 { "Busiest files" } else { "" };
+        let file_cell = |busiest: &[(&str, usize)]| {
+            busiest
+                .get(i)
+                .map(|(file, lines)| format!("{} ({})", file, lines))
+                .unwrap_or_default()
+        };
+        table.add_row(row!(label, file_cell(&busiest1), file_cell(&busiest2)));
+    }
+
+    println!("{}", table);
+}
+
+pub fn compare_periods(range1: &DateRange, range2: &DateRange, opts: &GitLogOptions) {
+    // The ranges replace any date filters given in the options
+    let summarise = |range: &DateRange| {
+        let opts = GitLogOptions {
+            since: range.since.clone(),
+            until: range.until.clone(),
+            ..opts.clone()
+        };
+        change_summary(&[], &opts)
+    };
+    let summary1 = summarise(range1);
+    let summary2 = summarise(range2);
+
+    let mut table = Table::new("{:<}  {:>}  {:>}  {:>}  {:>}").with_row(row!(
+        "",
+        &range1.repr,
+        &range2.repr,
+        "Change",
+        "%"
+    ));
+    let rows = [
+        ("Commits", summary1.commits, summary2.commits),
+        (
+            "Active authors",
+            summary1.authors.len(),
+            summary2.authors.len(),
+        ),
+        ("Lines added", summary1.lines_added, summary2.lines_added),
+        (
+            "Lines deleted",
+            summary1.lines_deleted,
+            summary2.lines_deleted,
+        ),
+        (
+            "Lines changed",
+            summary1.lines_changed(),
+            summary2.lines_changed(),
+        ),
+    ];
+    for (label, before, after) in rows {
+        table.add_row(row!(
+            label,
+            before,
+            after,
+            format!("{:+}", after as isize - before as isize),
+            percentage_change(before, after),
+        ));
+    }
+    println!("{}", table);
+
+    for (range, summary) in [(range1, &summary1), (range2, &summary2)] {
+        let mut table = Table::new("{:<}  {:>}")
+            .with_row(row!(format!("Top files ({})", range.repr), "Lines changed"));
+        for (file, lines) in summary.busiest_files(SETTINGS.compare_top_n_files) {
+            table.add_row(row!(file, lines));
+        }
+        print!("\n{}", table);
+    }
+}
+
+fn percentage_change(before: usize, after: usize) -> String {
+    if before == 0 {
+        String::from("-")
+    } else {
+        format!(
+            "{:+.1}%",
+            (after as f64 - before as f64) / before as f64 * 100.0
+        )
+    }
+}
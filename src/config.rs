@@ -1,3 +1,8 @@
+use super::identity::AuthorGroupConfig;
+
+// Any of these settings (other than author groups) can be overridden by environment variables;
+// see settings.rs
+
 // Git log
 // Update this list for your own identity!
 pub const ME_IDENTITY: [&str; 5] = [
@@ -8,8 +13,144 @@ pub const ME_IDENTITY: [&str; 5] = [
     "jakeireland",
 ];
 
+// Colour theme
+//
+// Each element may be a named colour (e.g. "yellow", "bright blue"), a hex colour (e.g.
+// "#c0cfe3"), or "normal" (the terminal's own colour), optionally preceded by "bold" and/or "dim".
+// This is the "default" palette; the other palettes are "colourblind", which uses the Okabe-Ito
+// colours throughout (including for languages and git status), and "monochrome", which uses only
+// bold and dim text.  Theme colours given in settings are applied on top of the palette
+pub const PALETTE: &str = "default";
+pub const THEME_HASH: &str = "bold yellow";
+// Refs are coloured by kind, like git does; the refs colour is for the surrounding punctuation
+pub const THEME_REFS: &str = "bold green";
+pub const THEME_REF_HEAD: &str = "bold cyan";
+pub const THEME_REF_BRANCH: &str = "bold green";
+pub const THEME_REF_REMOTE: &str = "bold red";
+pub const THEME_REF_TAG: &str = "bold yellow";
+pub const THEME_DATE: &str = "bold red";
+pub const THEME_AUTHOR: &str = "bold blue";
+pub const THEME_GRAPH_LINE: &str = "#0a64c8";
+// Patches: lines added and removed, and hunk headers ("@@ -1,2 +1,3 @@")
+pub const THEME_DIFF_ADDED: &str = "green";
+pub const THEME_DIFF_REMOVED: &str = "red";
+pub const THEME_DIFF_HUNK: &str = "cyan";
+
+// The colour scheme used to highlight the syntax of code in patches (--show and --patch), under the
+// colours for removed and added lines.  Any of syntect's default themes: "base16-ocean.dark",
+// "base16-eighties.dark", "base16-mocha.dark", "base16-ocean.light", "InspiredGitHub",
+// "Solarized (dark)", or "Solarized (light)".  Empty to turn syntax highlighting off
+pub const SYNTAX_THEME: &str = "base16-ocean.dark";
+
+// Authors whose name or email matches any of these regex patterns are considered automation
+// accounts.  These are the patterns of the "bots" author group (see below), and the authors excluded
+// with --no-bots
+pub const BOT_PATTERNS: [&str; 5] = [
+    r"\[bot\]($|@)",
+    r"^dependabot",
+    r"^renovate",
+    r"^github-actions",
+    r"^pre-commit-ci",
+];
+
+// Author groups
+//
+// Commits whose author matches a group are highlighted in the group's colour (see theme above for
+// the colour format).  An author matches a group if their name or email is one of the group's
+// identities, or matches one of its regex patterns.  Your own identity (ME_IDENTITY) and bots
+// (BOT_PATTERNS) are always the first two groups, named "me" and "bots"; after that, groups are
+// checked in order, and the first matching group wins.  For example:
+//
+//   AuthorGroupConfig {
+//       name: "team",
+//       identities: &["Alice Smith", "bob@corp.com"],
+//       patterns: &[r"@corp\.com$"],
+//       colour: "cyan",
+//   },
+pub const AUTHOR_GROUPS: &[AuthorGroupConfig] = &[];
+pub const THEME_ME_AUTHOR: &str = "#c0cfe3";
+pub const THEME_BOT_AUTHOR: &str = "dim white";
+
 // Top n results
 pub const DEFAULT_TOP_N_LOG: usize = 10;
 
+// Maximum width of the bar displayed next to each language in the language breakdown
+pub const LANGUAGE_BAR_MAX_WIDTH: usize = 40;
+
+// Number of languages to track when displaying language history
+pub const LANGUAGE_HISTORY_TOP_N: usize = 5;
+
+// Generated contributors list (--generate-contributors)
+//
+// Contributors with fewer commits than the threshold are left out.  Each contributor is written
+// using the template for the chosen format, where {name}, {email}, and {commits} are replaced
+// with the contributor's details, and {s} with "s" if they have more than one commit.  Set the
+// email template to "" to leave emails out
+pub const CONTRIBUTORS_MIN_COMMITS: usize = 1;
+pub const CONTRIBUTORS_MARKDOWN_HEADER: &str = "# Contributors\n\n";
+pub const CONTRIBUTORS_MARKDOWN_TEMPLATE: &str = "- {name}{email} ({commits} commit{s})";
+pub const CONTRIBUTORS_TEXT_HEADER: &str = "";
+pub const CONTRIBUTORS_TEXT_TEMPLATE: &str = "{name}{email}";
+pub const CONTRIBUTORS_EMAIL_TEMPLATE: &str = " <{email}>";
+
+// Number of busiest files to show for each side of a comparison
+pub const COMPARE_TOP_N_FILES: usize = 3;
+
+// Self-hosted forges, as "host=kind" where the kind is "github", "gitlab", or "gitea" (which
+// includes Forgejo).  github.com, gitlab.com, codeberg.org, and gitea.com are recognised without
+// being listed here.  For example:
+//
+//   pub const FORGE_HOSTS: [&str; 1] = ["git.example.com=gitlab"];
+pub const FORGE_HOSTS: [&str; 0] = [];
+
+// The directory under which your repositories live, for modes that work across all of them (with
+// --global).  A leading "~" is your home directory
+pub const BASE_DIR: &str = "~/projects";
+
+// How long --fetch waits for the remote (in seconds) before giving up and reporting on what was
+// last fetched
+pub const FETCH_TIMEOUT: u64 = 10;
+
+// Your commit goal, shown with -c along with how you are tracking against it, as a number of commits
+// per day, week, or month.  Empty for no goal.  For example:
+//
+//   pub const COMMIT_GOAL: &str = "5/week";
+pub const COMMIT_GOAL: &str = "";
+
+// Milestones to mark on the contribution graph (with --markers) alongside tags, as "date=label"
+// with dates in YYYY-MM-DD format.  For example:
+//
+//   pub const CHART_MARKERS: [&str; 1] = ["2024-03-01=Launch"];
+pub const CHART_MARKERS: [&str; 0] = [];
+
+// Similarity (as a percentage) above which a deleted file and an added file count as a rename in
+// line statistics, so that moving a file is not counted as deleting and rewriting it.  Set to 100 to
+// only detect exact renames, or 0 to turn off rename detection
+pub const RENAME_THRESHOLD: usize = 50;
+
+// Generated files, which are left out of line statistics unless --include-generated is given.  These
+// are pathspec globs, like --exclude, so a wildcard can match across directories, and as in
+// .gitignore a leading "**/" matches in any directory, including the root
+pub const GENERATED_FILES: [&str; 14] = [
+    "*.lock",
+    "**/package-lock.json",
+    "**/pnpm-lock.yaml",
+    "**/go.sum",
+    "*.min.js",
+    "*.min.css",
+    "*.js.map",
+    "*.snap",
+    "*__snapshots__/*",
+    "*.pb.go",
+    "*.pb.cc",
+    "*.pb.h",
+    "*_pb2.py",
+    "*_pb2_grpc.py",
+];
+
+// Branches whose published history should never be rewritten, checked by --protected.  These are
+// glob patterns matched against local branch names (as by `git for-each-ref`)
+pub const PROTECTED_BRANCHES: [&str; 4] = ["main", "master", "trunk", "release/*"];
+
 // Misc
 pub const SHORT_HASH_LENGTH: usize = 7;
@@ -0,0 +1,49 @@
+use super::branch;
+use super::repo::{self, PartialClone};
+use std::cell::OnceCell;
+use std::path::Path;
+
+// Facts about the repository that several subsystems need.  The context is created once in main
+// and passed to whatever needs it; each fact is looked up the first time it is asked for, so that
+// modes which never need it (e.g. --prompt) don't pay for it, and then remembered for the rest of
+// the run
+#[derive(Default)]
+pub struct Context {
+    top_level_path: OnceCell<Option<String>>,
+    current_branch: OnceCell<Option<String>>,
+    default_branch: OnceCell<Option<String>>,
+    partial_clone: OnceCell<Option<PartialClone>>,
+}
+
+impl Context {
+    // The root of the working tree, or None if we are not in a git repository
+    pub fn top_level_path(&self) -> Option<&str> {
+        self.top_level_path
+            .get_or_init(repo::top_level_repo_path)
+            .as_deref()
+    }
+
+    // The name of the repository (the basename of its root)
+    pub fn repo_name(&self) -> Option<&str> {
+        let path = Path::new(self.top_level_path()?);
+        path.file_name()?.to_str()
+    }
+
+    // The checked-out branch, or "HEAD" if it is detached
+    pub fn current_branch(&self) -> Option<&str> {
+        self.current_branch
+            .get_or_init(branch::current_branch)
+            .as_deref()
+    }
+
+    pub fn default_branch(&self) -> Option<&str> {
+        self.default_branch
+            .get_or_init(branch::default_branch)
+            .as_deref()
+    }
+
+    // The promisor remote, if this is a partial clone
+    pub fn partial_clone(&self) -> Option<&PartialClone> {
+        self.partial_clone.get_or_init(repo::partial_clone).as_ref()
+    }
+}
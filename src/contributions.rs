@@ -1,36 +1,68 @@
-use super::commit::{git_log, GitCommit};
-use super::identity::GitIdentity;
-use chrono::{Duration, Local, NaiveDate};
-use regex::Regex;
-use std::cmp::max;
-use std::collections::HashMap;
+use super::chart;
+use super::commit::{date_filter_args, git_log_iter};
+use super::diff::{diff_stat_args, numstat_usable, parse_numstat_line};
+use super::identity::{
+    author_filter_args, cluster_identities, normalise_email, parse_shortlog_line, AuthorFilter,
+    GitIdentity, Interner,
+};
+use super::interrupt;
+use super::opts::GitLogOptions;
+use super::pathspec::exclude_pathspec_args;
+use super::pool;
+use super::progress::Progress;
+use super::repo;
+use super::settings::SETTINGS;
+use super::theme::THEME;
+use super::timings;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate};
+use clap::ValueEnum;
+use colored::*;
+use json::JsonValue;
+use std::cmp::{max, min, Reverse};
+use std::collections::{HashMap, HashSet};
 use std::process::{Command, Stdio};
-use tabular::{row, Table};
+use std::sync::Mutex;
+use tabular::{row, Row, Table};
 use textplots::{
     Chart, ColorPlot, LabelBuilder, LabelFormat, Shape, TickDisplay, TickDisplayBuilder,
 };
 
 // Types
 
-#[derive(Clone)]
 pub struct GitContributor {
     id: GitIdentity,
     contributions: GitContributions,
 }
 
-#[derive(Clone)]
 struct GitContributions {
-    commits: Vec<GitCommit>,
+    commits: usize,
+    // The number of commits made on each day
+    commit_dates: HashMap<NaiveDate, usize>,
     file_contributions: Vec<GitFileContributions>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 struct GitFileContributions {
     lines_added: usize,
     lines_deleted: usize,
     lines_written: isize,
 }
 
+// A person's contributions across every repository under the base directory, with all of their
+// identities merged
+pub struct GlobalContributor {
+    // The email they made the most commits with
+    email: String,
+    // Commits and line statistics in each repository, in the order of GlobalContributors::repos
+    per_repo: Vec<(usize, GitFileContributions)>,
+}
+
+pub struct GlobalContributors {
+    // Repositories with at least one contributor
+    repos: Vec<String>,
+    contributors: Vec<GlobalContributor>,
+}
+
 struct ContributionStats {
     #[allow(dead_code)]
     commits: usize,
@@ -48,9 +80,25 @@ trait ContributorStats {
     fn contribution_stats(&self) -> ContributionStats;
 }
 
+impl GlobalContributor {
+    fn commits(&self) -> usize {
+        self.per_repo.iter().map(|(commits, _)| commits).sum()
+    }
+
+    fn file_contributions(&self) -> GitFileContributions {
+        let mut total = GitFileContributions::default();
+        for (_, lines) in &self.per_repo {
+            total.lines_added += lines.lines_added;
+            total.lines_deleted += lines.lines_deleted;
+            total.lines_written += lines.lines_written;
+        }
+        total
+    }
+}
+
 impl ContributorStats for GitContributor {
     fn commits(&self) -> usize {
-        self.contributions.commits.len()
+        self.contributions.commits
     }
 
     fn file_contributions(&self) -> GitFileContributions {
@@ -72,14 +120,7 @@ impl ContributorStats for GitContributor {
     }
 
     fn commit_dates(&self) -> HashMap<NaiveDate, usize> {
-        let mut commit_dates = HashMap::new();
-        for commit in &self.contributions.commits {
-            commit_dates
-                .entry(commit.date.abs.date_naive())
-                .and_modify(|n| *n += 1)
-                .or_insert(1);
-        }
-        commit_dates
+        self.contributions.commit_dates.clone()
     }
 
     fn contribution_stats(&self) -> ContributionStats {
@@ -93,16 +134,19 @@ impl ContributorStats for GitContributor {
 
 // Display methods
 
-pub fn display_git_contributions_per_author(contributors: Vec<GitContributor>) {
-    let mut contributors_with_summary: Vec<(GitContributor, ContributionStats)> = Vec::new();
+pub fn display_git_contributions_per_author(contributors: &[GitContributor]) {
+    interrupt::display_partial_banner(false);
+    let mut contributors_with_summary: Vec<(&GitContributor, ContributionStats)> = Vec::new();
     for contributor in contributors {
         let contrib_summary = contributor.contribution_stats();
         contributors_with_summary.push((contributor, contrib_summary));
     }
-    // Sort by sum of lines added and deleted (in reverse order)
+    // Sort by sum of lines added and deleted (in reverse order), breaking ties by email so that
+    // the output is stable
     contributors_with_summary.sort_by(|a, b| {
         (b.1.file_contributions.lines_added + b.1.file_contributions.lines_deleted)
             .cmp(&(a.1.file_contributions.lines_added + a.1.file_contributions.lines_deleted))
+            .then_with(|| a.0.id.email.cmp(&b.0.id.email))
     });
 
     let mut table = Table::new("{:<}  {:>}  {:>}  {:>}").with_row(row!(
@@ -114,7 +158,7 @@ pub fn display_git_contributions_per_author(contributors: Vec<GitContributor>) {
 
     for (contributor, contrib_summary) in contributors_with_summary {
         table.add_row(row!(
-            contributor.id.email,
+            &contributor.id.email,
             contrib_summary.file_contributions.lines_added,
             contrib_summary.file_contributions.lines_deleted,
             contrib_summary.file_contributions.lines_written,
@@ -123,70 +167,426 @@ pub fn display_git_contributions_per_author(contributors: Vec<GitContributor>) {
     println!("{}", table);
 }
 
-pub fn display_git_author_frequency(contributors: Vec<GitContributor>) {
-    // Sort by commits (in reverse order)
-    let mut contributors_sorted = contributors;
-    contributors_sorted.sort_by(|a, b| {
-        b.contributions
-            .commits
-            .len()
-            .cmp(&a.contributions.commits.len())
-    });
+// Like --author-contrib-stats and --author-commit-counts, with a column for each repository
+pub fn display_global_contributors(
+    global: &GlobalContributors,
+    line_stats: bool,
+    opts: &GitLogOptions,
+) {
+    interrupt::display_partial_banner(opts.json);
+    let mut contributors: Vec<(&GlobalContributor, usize, GitFileContributions)> = global
+        .contributors
+        .iter()
+        .map(|c| (c, c.commits(), c.file_contributions()))
+        .collect();
+    // Sorted as in the single-repository tables
+    if line_stats {
+        contributors.sort_by_key(|(c, _, lines)| {
+            (Reverse(lines.lines_added + lines.lines_deleted), &c.email)
+        });
+    } else {
+        contributors.sort_by_key(|(c, commits, _)| (Reverse(*commits), &c.email));
+    }
+
+    if opts.json {
+        let contributors: Vec<JsonValue> = contributors
+            .iter()
+            .map(|(c, commits, lines)| {
+                let mut repos = JsonValue::new_object();
+                for (name, (commits, lines)) in global.repos.iter().zip(&c.per_repo) {
+                    if *commits > 0 {
+                        repos[name.as_str()] = json::object! {
+                            commits: *commits,
+                            lines_added: lines.lines_added,
+                            lines_deleted: lines.lines_deleted,
+                        };
+                    }
+                }
+                json::object! {
+                    email: c.email.clone(),
+                    commits: *commits,
+                    lines_added: lines.lines_added,
+                    lines_deleted: lines.lines_deleted,
+                    repos: repos,
+                }
+            })
+            .collect();
+        println!("{}", JsonValue::Array(contributors).pretty(2));
+        return;
+    }
+
+    let totals = if line_stats {
+        "{:<}  {:>}  {:>}  {:>}"
+    } else {
+        "{:<}  {:>}"
+    };
+    let mut table = Table::new(&format!(
+        "{}{}",
+        totals,
+        "  {:>}".repeat(global.repos.len())
+    ));
+    let mut header = if line_stats {
+        Row::new()
+            .with_cell("Author")
+            .with_cell("Lines added")
+            .with_cell("Lines deleted")
+            .with_cell("Lines of code")
+    } else {
+        Row::new().with_cell("Author").with_cell("Commits")
+    };
+    for name in &global.repos {
+        header.add_cell(name);
+    }
+    table.add_row(header);
+
+    // With line statistics, each repository's column shows the lines of code written there
+    for (contributor, commits, lines) in contributors {
+        let mut row = Row::new().with_cell(&contributor.email);
+        if line_stats {
+            row.add_cell(lines.lines_added);
+            row.add_cell(lines.lines_deleted);
+            row.add_cell(lines.lines_written);
+        } else {
+            row.add_cell(commits);
+        }
+        for (repo_commits, repo_lines) in &contributor.per_repo {
+            if *repo_commits == 0 {
+                row.add_cell("-");
+            } else if line_stats {
+                row.add_cell(repo_lines.lines_written);
+            } else {
+                row.add_cell(repo_commits);
+            }
+        }
+        table.add_row(row);
+    }
+    println!("{}", table);
+}
+
+pub fn display_git_author_frequency(contributors: &[GitContributor], opts: &GitLogOptions) {
+    interrupt::display_partial_banner(opts.json);
+
+    // Sort by commits (in reverse order), breaking ties by email so that the output is stable
+    let mut contributors_sorted: Vec<&GitContributor> = contributors.iter().collect();
+    contributors_sorted.sort_by_key(|c| (Reverse(c.contributions.commits), &c.id.email));
 
     let mut table = Table::new("{:<}  {:>}").with_row(row!("Author", "Commits"));
 
     for contributor in contributors_sorted {
         table.add_row(row!(
-            contributor.id.email,
-            contributor.contributions.commits.len()
+            &contributor.id.email,
+            contributor.contributions.commits
         ));
     }
 
     println!("{}", table);
 }
 
-pub fn display_git_contributions_graph(contributors: Vec<GitContributor>) {
+// Print author frequencies in the same format as `git shortlog -sne`, so that scripts parsing
+// shortlog output can use gl instead.  As in shortlog, each (mailmapped) name and email is counted
+// separately, rather than merging identities that share an email as the other author statistics do
+pub fn display_git_shortlog(opts: &GitLogOptions) {
+    for (n_commits, name, email) in git_shortlog(opts).unwrap_or_default() {
+        let identity = GitIdentity {
+            email: email.clone(),
+            names: vec![name.clone()],
+        };
+        if opts.no_bots && identity.is_bot() {
+            continue;
+        }
+        println!("{:>6}\t{} <{}>", n_commits, name, email);
+    }
+}
+
+pub fn display_git_contributions_graph(contributors: &[GitContributor], opts: &GitLogOptions) {
+    interrupt::display_partial_banner(opts.json);
     let commit_dates_map = git_contributions_by_date(contributors);
     let commit_dates = git_contributions_by_date_vec(&commit_dates_map);
+    if commit_dates.is_empty() {
+        println!("No commits to plot.");
+        return;
+    }
 
-    // Get terminal size to inform graph size
-    let tsz = termsize::get().unwrap_or(termsize::Size { rows: 0, cols: 0 });
-
-    // Set reasonable defaults for graph size
-    let w: u32 = max(if tsz.cols == 0 { 40 } else { tsz.cols.into() }, 32);
-    let h: u32 = max(if tsz.rows == 0 { 60 } else { tsz.rows.into() }, 3);
+    let (w, h) = chart::chart_size(opts);
 
-    // Compute points
-    let points = commit_dates
+    // Compute points, applying any transformations to the series
+    let mut points = commit_dates
         .iter()
         .enumerate()
         .map(|(i, (_d, n))| (i as f32, *n as f32))
         .collect::<Vec<_>>();
 
-    // Get x bounds
-    let xmax = commit_dates.len();
+    if opts.cumulative {
+        points = cumulative_points(&points);
+    }
+    if let Some(window) = opts.smooth {
+        points = moving_average(&points, window);
+    }
+    let smoothed = opts.smooth.is_some();
+    let log_scale = opts.log_scale;
+    if log_scale {
+        points = log_scale_points(&points);
+    }
+
+    // A single day of history would be plotted as a lone point, so extend it across the day to draw
+    // a line instead.  This comes after the transformations, which would otherwise count the day
+    // twice (e.g. in a cumulative total), and only the day itself is labelled
+    let single_day = points.len() == 1;
+    if let [(x, y)] = points[..] {
+        points.push((x + 1.0, y));
+    }
+
+    // Get x bounds (at least one day, so that the chart has some width)
+    let xmax = max(commit_dates.len(), 1);
     let xstart = commit_dates[0].0;
 
+    // Markers are drawn as vertical lines spanning the series, with their labels listed below
+    let markers: Vec<chart::ChartMarker> = if opts.markers {
+        let xend = commit_dates[commit_dates.len() - 1].0;
+        chart::chart_markers()
+            .into_iter()
+            .filter(|marker| xstart <= marker.date && marker.date <= xend)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let ymax = points.iter().map(|(_x, y)| *y).fold(0.0, f32::max);
+    let marker_lines: Vec<Vec<(f32, f32)>> = markers
+        .iter()
+        .map(|marker| {
+            let x = (marker.date - xstart).num_days() as f32;
+            vec![(x, 0.0), (x, ymax)]
+        })
+        .collect();
+    let marker_shapes: Vec<Shape> = marker_lines.iter().map(|l| Shape::Lines(l)).collect();
+
     // Construct chart
     // See: github.com/loony-bean/textplots-rs/blob/63a418da/examples/label.rs
-    Chart::new(w, h, 0.0, xmax as f32)
+    let shape = Shape::Lines(&points);
+    let mut chart = Chart::new(w, h, 0.0, xmax as f32);
+    let mut chart = &mut chart;
+    for marker_shape in &marker_shapes {
+        chart = chart.linecolorplot(marker_shape, THEME.ref_tag.rgb());
+    }
+    let chart = chart
         .linecolorplot(
-            &Shape::Lines(&points),
+            &shape,
             // TODO: consider a more dynamic approach to colour selection as terminal background colour may differ
-            rgb::RGB {
-                r: 10,
-                g: 100,
-                b: 200,
-            },
+            THEME.graph_line.rgb(),
         )
         .x_label_format(LabelFormat::Custom(Box::new(move |val| {
-            format!("{}", xstart + Duration::days(val as i64))
+            if single_day && val >= 1.0 {
+                String::new()
+            } else {
+                format!("{}", xstart + Duration::days(val as i64))
+            }
         })))
         .y_label_format(LabelFormat::Custom(Box::new(move |val| {
-            format!("{}", val as isize)
+            // Labels should show the number of commits, even if the axis is log-scaled
+            let val = if log_scale {
+                inverse_log_scale(val)
+            } else {
+                val
+            };
+            if smoothed && val < 10.0 {
+                format!("{:.1}", val)
+            } else {
+                format!("{}", val.round() as isize)
+            }
         })))
-        .y_tick_display(TickDisplay::Dense)
-        .nice();
+        .y_tick_display(TickDisplay::Dense);
+    chart::display_chart(chart, opts);
+
+    for marker in &markers {
+        let line = format!("│ {}  {}", marker.date, marker.label);
+        if opts.colour {
+            println!("{}", THEME.ref_tag.paint(&line));
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+pub fn display_authors_graph(opts: &GitLogOptions) {
+    let authors = authors_per_month(opts);
+    interrupt::display_partial_banner(opts.json);
+
+    if opts.json {
+        let months: Vec<JsonValue> = authors
+            .iter()
+            .map(|(month, n)| {
+                json::object! { month: month.format("%Y-%m").to_string(), authors: *n }
+            })
+            .collect();
+        println!("{}", JsonValue::Array(months).pretty(2));
+        return;
+    }
+
+    if authors.is_empty() {
+        println!("No commits to plot.");
+        return;
+    }
+
+    let (w, h) = chart::chart_size(opts);
+    let mut points = authors
+        .iter()
+        .enumerate()
+        .map(|(i, (_month, n))| (i as f32, *n as f32))
+        .collect::<Vec<_>>();
+    // As with the contributions graph, extend a single month into a line
+    if let [(x, y)] = points[..] {
+        points.push((x + 1.0, y));
+    }
+    let xmax = max(authors.len(), 1);
+    let xstart = authors[0].0;
+    let ymax = points.iter().map(|(_x, y)| *y).fold(0.0, f32::max);
+
+    // Markers are placed within their month by day
+    let markers: Vec<chart::ChartMarker> = if opts.markers {
+        chart::chart_markers()
+            .into_iter()
+            .filter(|marker| xstart <= marker.date)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let marker_lines: Vec<Vec<(f32, f32)>> = markers
+        .iter()
+        .map(|marker| {
+            let x = months_between(xstart, marker.date) as f32 + (marker.date.day0() as f32 / 31.0);
+            vec![(x, 0.0), (x, ymax)]
+        })
+        .collect();
+    let marker_shapes: Vec<Shape> = marker_lines.iter().map(|l| Shape::Lines(l)).collect();
+
+    let shape = Shape::Lines(&points);
+    let mut chart = Chart::new(w, h, 0.0, xmax as f32);
+    let mut chart = &mut chart;
+    for marker_shape in &marker_shapes {
+        chart = chart.linecolorplot(marker_shape, THEME.ref_tag.rgb());
+    }
+    let chart = chart
+        .linecolorplot(&shape, THEME.graph_line.rgb())
+        .x_label_format(LabelFormat::Custom(Box::new(move |val| {
+            add_months(xstart, val as u32).format("%Y-%m").to_string()
+        })))
+        .y_label_format(LabelFormat::Custom(Box::new(|val| {
+            format!("{}", val.round() as isize)
+        })))
+        .y_tick_display(TickDisplay::Dense);
+    chart::display_chart(chart, opts);
+
+    for marker in &markers {
+        let line = format!("│ {}  {}", marker.date, marker.label);
+        if opts.colour {
+            println!("{}", THEME.ref_tag.paint(&line));
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+pub fn display_active_author_count(days: usize, opts: &GitLogOptions) {
+    let (active, total) = active_author_count(days, opts);
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    let out_message = format!(
+        "{} author{} committed in the past {} day{} ({} author{} in total).",
+        active,
+        plural(active),
+        days,
+        plural(days),
+        total,
+        plural(total),
+    );
+
+    if opts.colour {
+        println!("{}", out_message.green().bold());
+    } else {
+        println!("{}", out_message);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum ContributorsFormat {
+    #[value(alias = "md")]
+    Markdown,
+    #[value(alias = "txt")]
+    Text,
+}
+
+// Print a contributors list suitable for committing as CONTRIBUTORS.md (or AUTHORS), using the
+// thresholds and templates in the config
+pub fn generate_contributors_file(format: ContributorsFormat, opts: &GitLogOptions) {
+    let settings = &SETTINGS.contributors;
+    let (header, template) = match format {
+        ContributorsFormat::Markdown => (&settings.markdown_header, &settings.markdown_template),
+        ContributorsFormat::Text => (&settings.text_header, &settings.text_template),
+    };
+
+    // Shortlog respects the mailmap, so each contributor should only appear once
+    let mut contributors: Vec<(GitIdentity, usize)> = git_author_frequency(opts)
+        .into_values()
+        .filter(|(identity, n_commits)| {
+            *n_commits >= settings.min_commits && !(opts.no_bots && identity.is_bot())
+        })
+        .collect();
+    contributors.sort_by(|(a, a_commits), (b, b_commits)| {
+        b_commits
+            .cmp(a_commits)
+            .then_with(|| a.names.first().cmp(&b.names.first()))
+    });
+
+    print!("{}", header);
+    for (identity, n_commits) in contributors {
+        let email = settings.email_template.replace("{email}", &identity.email);
+        let line = template
+            .replace("{name}", identity.names.first().unwrap_or(&identity.email))
+            .replace("{email}", &email)
+            .replace("{commits}", &n_commits.to_string())
+            .replace("{s}", if n_commits == 1 { "" } else { "s" });
+        println!("{}", line);
+    }
+}
+
+// Running total of the series
+fn cumulative_points(points: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let mut total = 0.0;
+    points
+        .iter()
+        .map(|(x, y)| {
+            total += y;
+            (*x, total)
+        })
+        .collect()
+}
+
+// Trailing moving average over the given window (in days) of the series
+fn moving_average(points: &[(f32, f32)], window: usize) -> Vec<(f32, f32)> {
+    let window = max(window, 1);
+    let mut sum = 0.0;
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, (x, y))| {
+            sum += y;
+            if i >= window {
+                sum -= points[i - window].1;
+            }
+            (*x, sum / min(i + 1, window) as f32)
+        })
+        .collect()
+}
+
+// Offset by one so that days with no commits remain at zero
+fn log_scale_points(points: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    points
+        .iter()
+        .map(|(x, y)| (*x, (y + 1.0).log10()))
+        .collect()
+}
+
+fn inverse_log_scale(val: f32) -> f32 {
+    10_f32.powf(val) - 1.0
 }
 
 #[allow(dead_code)]
@@ -208,10 +608,18 @@ fn coarsen_contributions_by_date_vec(
 fn git_contributions_by_date_vec(
     contributions_by_date: &HashMap<NaiveDate, usize>,
 ) -> Vec<(NaiveDate, usize)> {
-    let d1 = contributions_by_date.keys().min().unwrap();
-    let d2 = Local::now().date_naive();
-
     let mut contributions = Vec::new();
+    let Some(d1) = contributions_by_date.keys().min() else {
+        return contributions;
+    };
+
+    // Commits dated in the future (e.g. from a skewed clock) extend the range to the latest commit
+    let today = Local::now().date_naive();
+    let d2 = contributions_by_date
+        .keys()
+        .max()
+        .map_or(today, |d| max(*d, today));
+
     let mut d = *d1;
     while d <= d2 {
         let n = contributions_by_date.get(&d).unwrap_or(&0);
@@ -222,10 +630,10 @@ fn git_contributions_by_date_vec(
     contributions
 }
 
-fn git_contributions_by_date(contributors: Vec<GitContributor>) -> HashMap<NaiveDate, usize> {
+fn git_contributions_by_date(contributors: &[GitContributor]) -> HashMap<NaiveDate, usize> {
     let mut commit_dates: HashMap<NaiveDate, usize> = HashMap::new();
     for contributor in contributors {
-        for (date, count) in contributor.commit_dates().iter() {
+        for (date, count) in &contributor.contributions.commit_dates {
             commit_dates
                 .entry(*date)
                 .and_modify(|n| *n += count)
@@ -237,137 +645,440 @@ fn git_contributions_by_date(contributors: Vec<GitContributor>) -> HashMap<Naive
 
 // Constructor methods
 
-pub fn git_contributors() -> Vec<GitContributor> {
-    // Step 1: calculate author-specific contributions
-    let logs: Vec<GitCommit> = git_log(None, None);
-    let mut commits_per_author: HashMap<String, Vec<GitCommit>> = HashMap::new();
-    for log in logs {
-        let email = log.clone().id.email;
-        commits_per_author
-            .entry(email)
-            .and_modify(|v| (*v).push(log.clone()))
-            .or_insert(vec![log]);
+// The contributors to the current repository, with their line statistics only if asked for, as
+// those take a `git log --numstat` per author
+pub fn git_contributors(opts: &GitLogOptions, line_stats: bool) -> Vec<GitContributor> {
+    let no_bots = opts.no_bots;
+
+    // Only the starting revision, author and date filters, traversal mode, and diff options are relevant to contributor statistics
+    let opts = GitLogOptions {
+        rev: opts.rev.clone(),
+        authors: opts.authors.clone(),
+        since: opts.since.clone(),
+        until: opts.until.clone(),
+        first_parent: opts.first_parent,
+        excludes: opts.excludes.clone(),
+        ignore_whitespace: opts.ignore_whitespace,
+        include_generated: opts.include_generated,
+        no_fetch: opts.no_fetch,
+        no_bots,
+        ..Default::default()
+    };
+
+    // Step 1: count each author's commits, and the days they were made on, in a single pass over
+    // the log, so that we only ever hold counters for each author rather than the whole history
+    let mut emails = Interner::default();
+    let mut commits_per_author: Vec<(usize, HashMap<NaiveDate, usize>)> = Vec::new();
+    for log in git_log_iter(&opts).filter(|log| !(no_bots && log.id.is_bot())) {
+        let id = emails.intern(&log.id.email);
+        if id == commits_per_author.len() {
+            commits_per_author.push(Default::default());
+        }
+        let (commits, commit_dates) = &mut commits_per_author[id];
+        *commits += 1;
+        *commit_dates.entry(log.date.abs.date_naive()).or_insert(0) += 1;
     }
 
     // Step 2: combine previous commit date data with file contributions
-    let author_frequency = git_author_frequency();
-    let mut contributors: Vec<GitContributor> = Vec::new();
-    for (email, (identity, _n_commits)) in author_frequency {
-        contributors.push(GitContributor {
-            id: GitIdentity {
-                email: email.clone(),
-                names: vec![],
-            },
-            contributions: GitContributions {
-                commits: commits_per_author.get(&email).unwrap_or(&vec![]).to_vec(),
-                file_contributions: git_file_contributions_per_author(identity),
-            },
+    let author_frequency = timings::time("author frequency (git shortlog)", || {
+        git_author_frequency(&opts)
+    });
+    let authors: Vec<(String, GitIdentity, usize)> = author_frequency
+        .into_iter()
+        .filter(|(_, (identity, _))| !(no_bots && identity.is_bot()))
+        .map(|(email, (identity, n_commits))| (email, identity, n_commits))
+        .collect();
+
+    // Line statistics are gathered with a `git log --numstat` per author, which can take a while
+    // in large repositories, so several authors are done at once.  If we are interrupted, authors
+    // that were not started or not finished are left out
+    let file_contributions = if line_stats {
+        let total_commits = authors.iter().map(|(_, _, n)| n).sum();
+        let progress = Mutex::new(Progress::new("Line statistics", "commits", total_commits));
+        let file_contributions = timings::time("line statistics (git log --numstat)", || {
+            pool::parallel_map(&authors, |(_, identity, n_commits)| {
+                let file_contributions = git_file_contributions_per_author(identity, &opts);
+                progress.lock().unwrap().inc(*n_commits);
+                file_contributions
+            })
         });
+        progress.into_inner().unwrap().finish();
+        file_contributions
+    } else {
+        authors.iter().map(|_| Some(Some(Vec::new()))).collect()
+    };
+
+    with_line_stats(authors, file_contributions)
+        .into_iter()
+        .map(|((email, identity, _), file_contributions)| {
+            let (commits, commit_dates) = emails
+                .get(&email)
+                .map(|id| std::mem::take(&mut commits_per_author[id]))
+                .unwrap_or_default();
+            GitContributor {
+                contributions: GitContributions {
+                    commits,
+                    commit_dates,
+                    file_contributions,
+                },
+                id: GitIdentity {
+                    email,
+                    names: identity.names,
+                },
+            }
+        })
+        .collect()
+}
+
+// Pair each author with their line statistics, leaving out those we have none for: authors that
+// were never started (None from the pool), and those whose git was interrupted part way through
+fn with_line_stats<A>(
+    authors: Vec<A>,
+    file_contributions: Vec<Option<Option<Vec<GitFileContributions>>>>,
+) -> Vec<(A, Vec<GitFileContributions>)> {
+    authors
+        .into_iter()
+        .zip(file_contributions)
+        .filter_map(|(author, file_contributions)| Some((author, file_contributions.flatten()?)))
+        .collect()
+}
+
+// Contributors in every repository under the base directory (see config::BASE_DIR), merging each
+// person's identities across repositories as --email-domains does.  Line statistics are only
+// gathered if asked for (see git_contributors)
+pub fn global_contributors(opts: &GitLogOptions, line_stats: bool) -> GlobalContributors {
+    // The contributor statistics work on the current repository, so we visit each in turn
+    let cwd = std::env::current_dir().ok();
+    let mut repos: Vec<(String, Vec<GitContributor>)> = Vec::new();
+    for path in repo::repos_under(&SETTINGS.base_dir) {
+        if interrupt::interrupted() {
+            break;
+        }
+        if let Err(e) = std::env::set_current_dir(&path) {
+            eprintln!("[WARN] Skipping {}: {}", path.display(), e);
+            continue;
+        }
+        let contributors = git_contributors(opts, line_stats);
+        if !contributors.is_empty() {
+            let name = path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            );
+            repos.push((name, contributors));
+        }
+    }
+    if let Some(cwd) = cwd {
+        let _ = std::env::set_current_dir(cwd);
+    }
+
+    // Each (name, email) pair of each contributor in each repository, by which we find the people
+    let mut pairs: Vec<(&str, &str)> = Vec::new();
+    let mut owners: Vec<(usize, usize)> = Vec::new();
+    for (i, (_, contributors)) in repos.iter().enumerate() {
+        for (j, contributor) in contributors.iter().enumerate() {
+            let email = contributor.id.email.as_str();
+            if contributor.id.names.is_empty() {
+                pairs.push(("", email));
+                owners.push((i, j));
+            }
+            for name in &contributor.id.names {
+                pairs.push((name, email));
+                owners.push((i, j));
+            }
+        }
+    }
+    let clusters = cluster_identities(&pairs);
+
+    // A contributor's pairs all share an email, so they are in the same cluster
+    let mut person_of: HashMap<(usize, usize), usize> = HashMap::new();
+    for (owner, cluster) in owners.into_iter().zip(clusters) {
+        person_of.entry(owner).or_insert(cluster);
+    }
+    let n_people = person_of.values().map(|p| p + 1).max().unwrap_or(0);
+    let mut people: Vec<GlobalContributor> = (0..n_people)
+        .map(|_| GlobalContributor {
+            email: String::new(),
+            per_repo: vec![Default::default(); repos.len()],
+        })
+        .collect();
+    let mut commits_per_email: Vec<HashMap<&str, usize>> = vec![HashMap::new(); n_people];
+    for (i, (_, contributors)) in repos.iter().enumerate() {
+        for (j, contributor) in contributors.iter().enumerate() {
+            let Some(&p) = person_of.get(&(i, j)) else {
+                continue;
+            };
+            let commits = contributor.commits();
+            let lines = contributor.file_contributions();
+            let (repo_commits, repo_lines) = &mut people[p].per_repo[i];
+            *repo_commits += commits;
+            repo_lines.lines_added += lines.lines_added;
+            repo_lines.lines_deleted += lines.lines_deleted;
+            repo_lines.lines_written += lines.lines_written;
+            *commits_per_email[p]
+                .entry(contributor.id.email.as_str())
+                .or_insert(0) += commits;
+        }
+    }
+    for (person, emails) in people.iter_mut().zip(&commits_per_email) {
+        if let Some((email, _)) = emails
+            .iter()
+            .max_by_key(|(email, n)| (**n, Reverse(**email)))
+        {
+            person.email = email.to_string();
+        }
+    }
+
+    GlobalContributors {
+        repos: repos.into_iter().map(|(name, _)| name).collect(),
+        contributors: people,
+    }
+}
+
+// Returns the number of distinct authors (by email) who committed in the last n days, and the
+// number of distinct authors overall
+fn active_author_count(days: usize, opts: &GitLogOptions) -> (usize, usize) {
+    let opts = GitLogOptions {
+        first_parent: opts.first_parent,
+        excludes: opts.excludes.clone(),
+        no_bots: opts.no_bots,
+        ..Default::default()
+    };
+    let cutoff = Local::now() - Duration::days(days as i64);
+
+    // Emails are normalised once each, rather than once per commit, by interning them as given and
+    // keeping the ID of each one's normal form
+    let mut raw_emails = Interner::default();
+    let mut normal_ids: Vec<usize> = Vec::new();
+    let mut emails = Interner::default();
+    let mut last_commit_per_author: Vec<DateTime<Local>> = Vec::new();
+    for log in git_log_iter(&opts).filter(|log| !(opts.no_bots && log.id.is_bot())) {
+        let raw_id = raw_emails.intern(&log.id.email);
+        if raw_id == normal_ids.len() {
+            normal_ids.push(emails.intern(&normalise_email(&log.id.email)));
+        }
+        let id = normal_ids[raw_id];
+        if id == last_commit_per_author.len() {
+            last_commit_per_author.push(log.date.abs);
+        } else {
+            last_commit_per_author[id] = max(last_commit_per_author[id], log.date.abs);
+        }
     }
 
-    contributors
+    let active = last_commit_per_author
+        .iter()
+        .filter(|d| **d >= cutoff)
+        .count();
+    (active, last_commit_per_author.len())
+}
+
+// The number of distinct authors (by email) who committed in each month, from the month of the
+// first commit up to the current month, which shows the size of the community rather than how much
+// it committed
+fn authors_per_month(opts: &GitLogOptions) -> Vec<(NaiveDate, usize)> {
+    let opts = GitLogOptions {
+        rev: opts.rev.clone(),
+        authors: opts.authors.clone(),
+        since: opts.since.clone(),
+        until: opts.until.clone(),
+        first_parent: opts.first_parent,
+        excludes: opts.excludes.clone(),
+        no_bots: opts.no_bots,
+        all: true,
+        ..Default::default()
+    };
+
+    let mut emails = Interner::default();
+    let mut authors_by_month: HashMap<NaiveDate, HashSet<usize>> = HashMap::new();
+    for log in git_log_iter(&opts).filter(|log| !(opts.no_bots && log.id.is_bot())) {
+        let id = emails.intern(&normalise_email(&log.id.email));
+        let date = log.date.abs.date_naive();
+        authors_by_month
+            .entry(add_months(date, 0))
+            .or_default()
+            .insert(id);
+    }
+
+    let Some(first) = authors_by_month.keys().min().copied() else {
+        return Vec::new();
+    };
+    let last = authors_by_month
+        .keys()
+        .max()
+        .map_or(first, |d| max(*d, add_months(Local::now().date_naive(), 0)));
+    (0..=months_between(first, last))
+        .map(|i| {
+            let month = add_months(first, i);
+            (month, authors_by_month.get(&month).map_or(0, HashSet::len))
+        })
+        .collect()
+}
+
+// The first day of the month n months after the given date's month
+fn add_months(date: NaiveDate, n: u32) -> NaiveDate {
+    let months = date.year() * 12 + date.month0() as i32 + n as i32;
+    NaiveDate::from_ymd_opt(months.div_euclid(12), months.rem_euclid(12) as u32 + 1, 1)
+        .unwrap_or(date)
 }
 
-fn git_file_contributions_per_author(identity: GitIdentity) -> Vec<GitFileContributions> {
-    // git log --no-merges --author="SOME AUTHOR OR EMAIL" --pretty=tformat: --numstat
+// The number of whole calendar months from one date's month to another's
+fn months_between(from: NaiveDate, to: NaiveDate) -> u32 {
+    let months = |d: NaiveDate| d.year() * 12 + d.month0() as i32;
+    (months(to) - months(from)).max(0) as u32
+}
+
+// The lines added and deleted by each file change of an author's commits.  None if we were
+// interrupted, as git is killed along with us, leaving its output incomplete
+fn git_file_contributions_per_author(
+    identity: &GitIdentity,
+    opts: &GitLogOptions,
+) -> Option<Vec<GitFileContributions>> {
+    // git log --no-merges --author="SOME AUTHOR OR EMAIL" --pretty=tformat: --numstat (keeping merges
+    // with --first-parent, as merge_filter_arg does)
     let mut cmd = Command::new("git");
     cmd.arg("log");
-    cmd.arg("--no-merges");
-    cmd.arg(format!("--author={}", identity.email));
+    if !opts.first_parent {
+        cmd.arg("--no-merges");
+    }
+    cmd.args(author_filter_args(&[AuthorFilter::Email(
+        identity.email.clone(),
+    )]));
+    if opts.first_parent {
+        cmd.arg("--first-parent");
+    }
+    cmd.args(date_filter_args(opts));
     cmd.arg("--pretty=tformat:");
     cmd.arg("--numstat");
+    cmd.args(diff_stat_args(opts));
+    if let Some(rev) = &opts.rev {
+        cmd.arg(rev);
+    }
+    cmd.args(exclude_pathspec_args(opts));
 
     let output = cmd
         .stdout(Stdio::piped())
         .output()
         .expect("Failed to execute `git log`");
 
-    if output.status.success() {
-        let git_log = String::from_utf8_lossy(&output.stdout).into_owned();
+    // Git is interrupted along with us, which isn't worth reporting
+    if interrupt::interrupted() {
+        return None;
+    }
+    if !numstat_usable(&output, opts) {
+        println!(
+            "An error has occured while attempting to execute `git log` with author {}.",
+            identity.email
+        );
+        return Some(vec![]);
+    }
 
+    let git_log = String::from_utf8_lossy(&output.stdout);
+    Some(
         git_log
             .split_terminator('\n')
-            .map(|s| {
-                let mut parts = s.split_whitespace();
-
-                let lines_added: usize = parts.next().unwrap().parse::<usize>().unwrap_or(0);
-                let lines_deleted: usize = parts.next().unwrap().parse::<usize>().unwrap_or(0);
-
-                GitFileContributions {
-                    lines_added,
-                    lines_deleted,
-                    lines_written: (lines_added as isize) - (lines_deleted as isize),
-                }
+            .filter_map(|s| parse_numstat_line(s, opts))
+            .map(|(lines_added, lines_deleted, _)| GitFileContributions {
+                lines_added,
+                lines_deleted,
+                lines_written: (lines_added as isize) - (lines_deleted as isize),
             })
-            .collect()
+            .collect(),
+    )
+}
+
+// Add commits to an author frequency map (of email -> (git identity, commits)), merging identities
+// that share an email
+pub fn add_author_commits(
+    author_frequency: &mut HashMap<String, (GitIdentity, usize)>,
+    name: String,
+    email: String,
+    n_commits: usize,
+) {
+    if let Some(p) = author_frequency.get_mut(&email) {
+        if !p.0.names.contains(&name) {
+            p.0.names.push(name);
+        }
+        p.1 += n_commits;
     } else {
-        println!(
-            "An error has occured while attempting to execute `git log` with author {}.",
-            identity.email
-        );
-        vec![]
+        let identity = GitIdentity {
+            email: email.clone(),
+            names: vec![name],
+        };
+
+        author_frequency.insert(email, (identity, n_commits));
     }
 }
 
 // Returns a map of email -> (git identity, commits)
-fn git_author_frequency() -> HashMap<String, (GitIdentity, usize)> {
+fn git_author_frequency(opts: &GitLogOptions) -> HashMap<String, (GitIdentity, usize)> {
+    let mut author_contribution_frequency: HashMap<String, (GitIdentity, usize)> = HashMap::new();
+    for (n_commits, name, email) in git_shortlog(opts).unwrap_or_default() {
+        add_author_commits(&mut author_contribution_frequency, name, email, n_commits);
+    }
+    author_contribution_frequency
+}
+
+// The commits of each (mailmapped) name and email, as (commits, name, email), most commits first
+fn git_shortlog(opts: &GitLogOptions) -> Option<Vec<(usize, String, String)>> {
     // git shortlog -sne --all --no-merges
     let mut cmd = Command::new("git");
     cmd.arg("shortlog");
     cmd.arg("--summary");
     cmd.arg("--numbered");
     cmd.arg("--email");
-    cmd.arg("--no-merges");
-    cmd.arg("--all");
+    if !opts.first_parent {
+        cmd.arg("--no-merges");
+    }
+    if opts.first_parent {
+        cmd.arg("--first-parent");
+    }
+    cmd.args(author_filter_args(&opts.authors));
+    cmd.args(date_filter_args(opts));
+    // Authors are counted across all branches unless a revision or range is given
+    match &opts.rev {
+        Some(rev) => cmd.arg(rev),
+        None => cmd.arg("--all"),
+    };
+    cmd.args(exclude_pathspec_args(opts));
 
     let output = cmd
         .stdout(Stdio::piped())
         .output()
         .expect("Failed to execute `git log`");
 
-    if output.status.success() {
-        let git_shortlog = String::from_utf8_lossy(&output.stdout).into_owned();
-
-        let author_contribution_freq_re =
-            Regex::new(r"\s*(?P<freq>\d+)\s+(?P<author>.*)\s+<(?P<email>.*)>").unwrap();
-        let mut author_contribution_frequency: HashMap<String, (GitIdentity, usize)> =
-            HashMap::new();
-        for line in git_shortlog.lines() {
-            if author_contribution_freq_re.is_match(line) {
-                if let Some(caps) = author_contribution_freq_re.captures(line) {
-                    let freq = caps
-                        .name("freq")
-                        .unwrap()
-                        .as_str()
-                        .parse::<usize>()
-                        .unwrap();
-
-                    let author = caps.name("author").unwrap().as_str().to_string();
-                    let email = caps.name("email").unwrap().as_str().to_string();
-
-                    if let Some(p) = author_contribution_frequency.get_mut(&email) {
-                        p.0.names.push(author);
-                        p.1 += freq;
-                    } else {
-                        let identity = GitIdentity {
-                            email: email.clone(),
-                            names: vec![author],
-                        };
+    if !output.status.success() {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return None;
+    }
 
-                        author_contribution_frequency.insert(email, (identity, freq));
-                    }
-                } else {
-                    println!("WARN: Unable to parse git frequency line \"{}\": no matching captures for regex \"{:?}\"", line, author_contribution_freq_re);
-                }
-            } else {
-                println!("WARN: Unable to parse git frequency line \"{}\": no matching captures for regex \"{:?}\"", line, author_contribution_freq_re);
+    let mut authors = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        match parse_shortlog_line(line) {
+            Some((n_commits, name, email)) => {
+                authors.push((n_commits, name.to_string(), email.to_string()))
             }
+            None => eprintln!("[WARN] Unable to parse git shortlog line {:?}", line),
         }
+    }
+    Some(authors)
+}
 
-        author_contribution_frequency
-    } else {
-        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        HashMap::new()
+    #[test]
+    fn leaves_out_interrupted_authors() {
+        let lines = |n| GitFileContributions {
+            lines_added: n,
+            lines_deleted: 0,
+            lines_written: n as isize,
+        };
+        // The first author finished, the second was interrupted while git was running, and the
+        // third was never started
+        let stats = vec![Some(Some(vec![lines(3)])), Some(None), None];
+        let kept = with_line_stats(vec!["finished", "in flight", "not started"], stats);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].0, "finished");
+        assert_eq!(kept[0].1[0].lines_added, 3);
     }
 }
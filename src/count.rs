@@ -1,44 +1,51 @@
-use super::branch::current_branch;
+use super::commit::{merge_filter_arg, start_args};
+use super::context::Context;
+use super::contributions::add_author_commits;
+use super::dates;
+use super::identity::{author_filter_args, GitIdentity};
 use super::opts::GitLogOptions;
-use super::repo::current_repository;
+use super::timings;
 use chrono::{DateTime, Duration, Local, NaiveTime};
 use colored::*;
+use std::collections::HashMap;
 use std::process::{Command, Output, Stdio};
+use tabular::{row, Table};
 
 // const local: DateTime<Local> = Local::now();
 // const today = Utc.ymd(local.year(), local.month(), local.day())
 // let today: Date<Local> = Local::today();
 // const yesterday = today - Duration::days(1);
 
-pub fn get_commit_count(input: &str, opts: &GitLogOptions) {
-    // determine commit count
-    let commit_count_val: usize;
-
-    if input == "today" {
-        commit_count_val = commit_count_today();
+pub fn get_commit_count(input: &str, ctx: &Context, opts: &GitLogOptions) {
+    // determine the period of interest
+    let mut when = String::from(input);
+    let (since_timestamp, before_timestamp) = if input == "today" {
+        today_window()
     } else if input == "yesterday" {
-        commit_count_val = commit_count_yesterday();
+        yesterday_window()
     } else {
-        let days_ago: usize = input.parse().unwrap_or_else(|e| {
-            panic!("{e}: argument must be a valid integer, but got {:?}", input)
+        let relative_date = dates::parse_relative_date(input).unwrap_or_else(|| {
+            panic!("Argument must be a number of days, a period such as \"2w\", \"3m\", or \"1y\", or \"this week\"/\"this month\"/\"this year\", but got {:?}", input)
         });
-        commit_count_val = commit_count_since(days_ago);
-    }
-    // let commit_count_val = commit_count(days_ago, days_ago_end);
+        when = relative_date.description;
+        since_window(relative_date.start)
+    };
 
     // get repository information
-    let repo_name = current_repository();
-    let branch_name = current_branch();
+    let Some((repo_name, branch_name)) = repo_and_branch(ctx, opts) else {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return;
+    };
+
+    // determine commit count
+    let commit_count_val = commit_count_between(since_timestamp, before_timestamp, opts);
+    // let commit_count_val = commit_count(days_ago, days_ago_end);
 
     // determine human-readable "since when" relative time
     let plural_maybe = match commit_count_val {
         1 => "",
         _ => "s",
     };
-    let when = match input {
-        "today" | "yesterday" => String::from(input),
-        _ => format!("in the past {} days", input),
-    };
     let verb_tense = match input {
         "yesterday" => "were",
         _ => match commit_count_val {
@@ -54,12 +61,7 @@ pub fn get_commit_count(input: &str, opts: &GitLogOptions) {
         // n commits were made to {}/{} yesterday
         // n commits have been made to {}/{} in the past {} days
         "{} commit{} {} to {}/{} {}.",
-        commit_count_val,
-        plural_maybe,
-        verb_tense,
-        repo_name.unwrap(),
-        branch_name.unwrap(),
-        when,
+        commit_count_val, plural_maybe, verb_tense, repo_name, branch_name, when,
     );
 
     if opts.colour {
@@ -67,15 +69,22 @@ pub fn get_commit_count(input: &str, opts: &GitLogOptions) {
     } else {
         println!("{}", out_message);
     }
-}
 
-pub fn get_commit_count_total(opts: &GitLogOptions) {
-    // determine commit count
-    let commit_count_val = commit_count();
+    if opts.by_author {
+        let authors = commit_authors_between(Some(since_timestamp), Some(before_timestamp), opts);
+        display_commit_count_by_author(authors);
+    }
+}
 
+pub fn get_commit_count_total(ctx: &Context, opts: &GitLogOptions) {
     // get repository information
-    let repo_name = current_repository();
-    let branch_name = current_branch();
+    let Some((repo_name, branch_name)) = repo_and_branch(ctx, opts) else {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return;
+    };
+
+    // determine commit count
+    let commit_count_val = commit_count(opts);
 
     let plural_maybe = match commit_count_val {
         1 => "",
@@ -89,11 +98,7 @@ pub fn get_commit_count_total(opts: &GitLogOptions) {
     // format output nicely (and ensure it's lovely and green)
     let out_message = format!(
         "{} commit{} {} been made to {}/{}.",
-        commit_count_val,
-        plural_maybe,
-        have_plural_maybe,
-        repo_name.unwrap(),
-        branch_name.unwrap(),
+        commit_count_val, plural_maybe, have_plural_maybe, repo_name, branch_name,
     );
 
     if opts.colour {
@@ -101,18 +106,33 @@ pub fn get_commit_count_total(opts: &GitLogOptions) {
     } else {
         println!("{}", out_message);
     }
+
+    if opts.by_author {
+        display_commit_count_by_author(commit_authors_between(None, None, opts));
+    }
+}
+
+// The repository name and the branch (or revision given with --ref) that commits are counted on,
+// or "*" for every branch and tag with --all-refs
+fn repo_and_branch<'a>(ctx: &'a Context, opts: &'a GitLogOptions) -> Option<(&'a str, &'a str)> {
+    let branch_name = if opts.all_refs {
+        "*"
+    } else {
+        opts.rev.as_deref().or(ctx.current_branch())?
+    };
+    Some((ctx.repo_name()?, branch_name))
 }
 
-fn commit_count_today() -> usize {
+// Each window is a pair of timestamps (in seconds), from the start of the period to its end
+fn today_window() -> (i64, i64) {
     // get the date of interest as a number of seconds
     let today_start: i64 = Local::now().with_time(NaiveTime::MIN).unwrap().timestamp();
     let now: i64 = Local::now().timestamp();
 
-    // get the commit count for this period
-    commit_count_between(today_start, now)
+    (today_start, now)
 }
 
-fn commit_count_yesterday() -> usize {
+fn yesterday_window() -> (i64, i64) {
     // get the datetimes of interest
     let today_start: DateTime<Local> = Local::now().with_time(NaiveTime::MIN).unwrap();
     let yesterday_start: DateTime<Local> = today_start - Duration::days(1);
@@ -122,23 +142,23 @@ fn commit_count_yesterday() -> usize {
     // let date_of_interest: i64 = (since - before) as i64;
     // let timestamp_of_interest: i64 = (today - Duration::days(date_of_interest)).timestamp();
 
-    // get the commit count for this period
-    commit_count_between(yersterday_timestamp, today_timestamp)
+    (yersterday_timestamp, today_timestamp)
 }
 
-fn commit_count_since(n: usize) -> usize {
+fn since_window(since_start: DateTime<Local>) -> (i64, i64) {
     // get the datetimes of interest
-    let today_start: DateTime<Local> = Local::now().with_time(NaiveTime::MIN).unwrap();
-    let since_start: DateTime<Local> = today_start - Duration::days(n as i64);
     let now: i64 = Local::now().timestamp();
     // calculate those values in seconds
     let since_timestamp: i64 = since_start.timestamp();
 
-    // get the commit count for this period
-    commit_count_between(since_timestamp, now)
+    (since_timestamp, now)
 }
 
-fn commit_count_between(since_timestamp: i64, before_timestamp: i64) -> usize {
+fn commit_count_between(
+    since_timestamp: i64,
+    before_timestamp: i64,
+    opts: &GitLogOptions,
+) -> usize {
     // construct git command line arguments
     let mut since_arg = String::new();
     since_arg.push_str("--since=");
@@ -147,32 +167,123 @@ fn commit_count_between(since_timestamp: i64, before_timestamp: i64) -> usize {
     before_arg.push_str("--before=");
     before_arg.push_str(before_timestamp.to_string().as_str());
 
+    if opts.author_date {
+        return commit_authors_between(Some(since_timestamp), Some(before_timestamp), opts).len();
+    }
+
     // git rev-list --count --since=$START_TODAY --before=$NOW HEAD
     let since = since_arg.as_str();
     let before = before_arg.as_str();
-    commit_count_core(vec![since, before])
+    commit_count_core(vec![since, before], opts)
+}
+
+// The (mailmapped) name and email of the author of each commit in the window (or all commits, if
+// no window is given)
+//
+// Rebasing or amending a commit updates its commit date but not its author date, so counting by
+// author date attributes commits to when they were originally written.  Git can only filter by
+// commit date, but as a commit's author date should never be later than its commit date, we can
+// still ask git for commits since the start of the period and filter the rest ourselves
+fn commit_authors_between(
+    since_timestamp: Option<i64>,
+    before_timestamp: Option<i64>,
+    opts: &GitLogOptions,
+) -> Vec<(String, String)> {
+    // git log --format=%at%x00%ct%x00%aN%x00%aE --since=$START HEAD
+    let mut cmd = Command::new("git");
+    cmd.arg("log");
+    cmd.arg("--format=%at%x00%ct%x00%aN%x00%aE");
+    cmd.args(merge_filter_arg(opts));
+    if opts.first_parent {
+        cmd.arg("--first-parent");
+    }
+    if let Some(since_timestamp) = since_timestamp {
+        cmd.arg(format!("--since={}", since_timestamp));
+    }
+    cmd.args(author_filter_args(&opts.authors));
+    cmd.arg(opts.rev.as_deref().unwrap_or("HEAD"));
+
+    let output = timings::time("commit count (git log)", || {
+        cmd.stdout(Stdio::piped())
+            .output()
+            .expect("Failed to execute `git log`")
+    });
+
+    if !output.status.success() {
+        eprintln!("[ERROR] Failed to get output from `git log`");
+        return vec![];
+    }
+
+    let in_window = |t: i64| {
+        since_timestamp.is_none_or(|since| t >= since)
+            && before_timestamp.is_none_or(|before| t < before)
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\0');
+            let author_time: i64 = parts.next()?.parse().ok()?;
+            let commit_time: i64 = parts.next()?.parse().ok()?;
+            let name = parts.next()?.to_string();
+            let email = parts.next()?.to_string();
+            let t = if opts.author_date {
+                author_time
+            } else {
+                commit_time
+            };
+            if in_window(t) {
+                Some((name, email))
+            } else {
+                None
+            }
+        })
+        .collect()
 }
 
-pub fn commit_count() -> usize {
-    commit_count_core(vec![])
+fn display_commit_count_by_author(authors: Vec<(String, String)>) {
+    let mut author_frequency = HashMap::new();
+    for (name, email) in authors {
+        add_author_commits(&mut author_frequency, name, email, 1);
+    }
+
+    let mut author_frequency: Vec<(GitIdentity, usize)> = author_frequency.into_values().collect();
+    author_frequency.sort_by(|(a, a_commits), (b, b_commits)| {
+        b_commits.cmp(a_commits).then_with(|| a.email.cmp(&b.email))
+    });
+
+    let mut table = Table::new("{:<}  {:>}").with_row(row!("Author", "Commits"));
+    for (identity, n_commits) in author_frequency {
+        let name = identity.names.first().unwrap_or(&identity.email);
+        table.add_row(row!(format!("{} <{}>", name, identity.email), n_commits));
+    }
+    print!("{}", table);
 }
 
-fn commit_count_core(args: Vec<&str>) -> usize {
+pub fn commit_count(opts: &GitLogOptions) -> usize {
+    commit_count_core(vec![], opts)
+}
+
+fn commit_count_core(args: Vec<&str>, opts: &GitLogOptions) -> usize {
     // run command
     // git rev-list --count HEAD
     let mut cmd = Command::new("git");
     cmd.arg("rev-list");
     cmd.arg("--count");
-    cmd.arg("--no-merges");
+    cmd.args(merge_filter_arg(opts));
+    if opts.first_parent {
+        cmd.arg("--first-parent");
+    }
     for arg in args {
         cmd.arg(arg);
     }
-    cmd.arg("HEAD");
+    cmd.args(author_filter_args(&opts.authors));
+    cmd.args(start_args(opts));
 
-    let output = cmd
-        .stdout(Stdio::piped())
-        .output()
-        .expect("Failed to execute `git rev-list`");
+    let output = timings::time("commit count (git rev-list)", || {
+        cmd.stdout(Stdio::piped())
+            .output()
+            .expect("Failed to execute `git rev-list`")
+    });
 
     if let Some(output) = parse_commit_count(output) {
         match output.parse::<usize>() {
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveTime};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::convert::TryFrom;
+
+lazy_static! {
+    // A (possibly fractional) number of days, weeks, months, or years, e.g. "3", "2w", "1.5 months"
+    static ref RELATIVE_DATE_RE: Regex = Regex::new(
+        r"^(?P<n>\d+(?:\.\d+)?)\s*(?P<unit>d|days?|w|weeks?|m|months?|y|years?)?$"
+    )
+    .unwrap();
+}
+
+// The start of a period of time ending now, and how to describe it (e.g. "in the past 2 weeks")
+pub struct RelativeDate {
+    pub start: DateTime<Local>,
+    pub description: String,
+}
+
+// Parse a period such as "3" (days), "2w", "3m", "1.5y", or "this week"/"this month"/"this year".
+// Periods are counted back from the start of today, so "1d" includes all of yesterday
+pub fn parse_relative_date(s: &str) -> Option<RelativeDate> {
+    let s = s.trim().to_lowercase();
+    let today = Local::now().with_time(NaiveTime::MIN).unwrap();
+
+    if let Some(anchor) = s.strip_prefix("this ") {
+        let start = match anchor.trim() {
+            "week" => today - Duration::days(today.weekday().num_days_from_monday() as i64),
+            "month" => today.with_day(1)?,
+            "year" => today.with_day(1)?.with_month(1)?,
+            _ => return None,
+        };
+        return Some(RelativeDate {
+            start,
+            description: s.clone(),
+        });
+    }
+
+    let caps = RELATIVE_DATE_RE.captures(&s)?;
+    let n: f64 = caps.name("n")?.as_str().parse().ok()?;
+    let unit = caps.name("unit").map(|u| u.as_str()).unwrap_or("d");
+    let (unit_name, days_per_unit, months_per_unit) = match unit.chars().next()? {
+        'd' => ("day", 1.0, None),
+        'w' => ("week", 7.0, None),
+        'm' => ("month", 30.4375, Some(1)),
+        _ => ("year", 365.25, Some(12)),
+    };
+
+    // Whole months and years are calendar months and years; otherwise we use their average length.
+    // Periods too long to count back (e.g. "9999999999y") are rejected rather than truncated
+    let start = match months_per_unit {
+        Some(months) if n.fract() == 0.0 => {
+            let months = u32::try_from(n as u64).ok()?.checked_mul(months)?;
+            today.checked_sub_months(Months::new(months))?
+        }
+        _ => {
+            let seconds = (n * days_per_unit * 86400.0).round();
+            if seconds >= i64::MAX as f64 {
+                return None;
+            }
+            today.checked_sub_signed(Duration::try_seconds(seconds as i64)?)?
+        }
+    };
+
+    Some(RelativeDate {
+        start,
+        description: format!(
+            "in the past {} {}{}",
+            n,
+            unit_name,
+            if n == 1.0 { "" } else { "s" }
+        ),
+    })
+}
+
+// Convert a date given to a filter such as --since into something git understands.  Git already
+// understands most formats (e.g. "2023-01-01" or "1 year ago"), but not abbreviated units or
+// calendar anchors, so we resolve those to a timestamp ourselves
+pub fn git_date(s: &str) -> String {
+    let is_plain_number = s.trim().parse::<f64>().is_ok();
+    match parse_relative_date(s) {
+        Some(relative_date) if !is_plain_number => relative_date.start.timestamp().to_string(),
+        _ => s.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_periods_too_long_to_count_back() {
+        assert!(parse_relative_date("3m").is_some());
+        assert!(parse_relative_date("1.5y").is_some());
+        assert!(parse_relative_date("4294967296m").is_none());
+        assert!(parse_relative_date("400000000y").is_none());
+        assert!(parse_relative_date("99999999999999999999d").is_none());
+        assert!(parse_relative_date("1e400").is_none());
+    }
+}
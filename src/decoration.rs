@@ -0,0 +1,160 @@
+use super::theme::THEME;
+use clap::ValueEnum;
+
+// How to show the refs pointing at each commit: with short names (e.g. "main"), full names (e.g.
+// "refs/heads/main"), or not at all
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum DecorateStyle {
+    Short,
+    Full,
+    No,
+}
+
+// The kinds of ref that can decorate a commit in the log, in the order in which they are shown
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RefKind {
+    Head,
+    Branch,
+    Remote,
+    Tag,
+    Other,
+}
+
+// A ref pointing at a commit.  For HEAD, the name is the branch that is checked out (if any)
+#[derive(Clone)]
+pub struct Decoration {
+    pub kind: RefKind,
+    pub name: String,
+}
+
+// Parse the decorations of a commit from git's "%D" placeholder with full ref names (i.e., with
+// `--decorate=full`), e.g. "HEAD -> refs/heads/main, tag: refs/tags/v1.0, refs/remotes/origin/main".
+// Decorations are ordered HEAD first, then local branches, remote branches, and tags
+pub fn parse_decorations(s: &str, style: DecorateStyle) -> Vec<Decoration> {
+    let ref_name = |refname: &str| match style {
+        DecorateStyle::Full => refname.to_string(),
+        _ => short_ref_name(refname).to_string(),
+    };
+    let mut decorations: Vec<Decoration> = s
+        .split(", ")
+        .filter(|d| !d.is_empty())
+        .map(|d| {
+            if let Some(branch) = d.strip_prefix("HEAD -> ") {
+                Decoration {
+                    kind: RefKind::Head,
+                    name: ref_name(branch),
+                }
+            } else if d == "HEAD" {
+                Decoration {
+                    kind: RefKind::Head,
+                    name: String::new(),
+                }
+            } else {
+                let refname = d.strip_prefix("tag: ").unwrap_or(d);
+                Decoration {
+                    kind: ref_kind(refname),
+                    name: ref_name(refname),
+                }
+            }
+        })
+        .collect();
+
+    // The sort is stable, so refs of the same kind stay in git's order
+    decorations.sort_by_key(|d| d.kind);
+    decorations
+}
+
+fn ref_kind(refname: &str) -> RefKind {
+    if refname.starts_with("refs/heads/") {
+        RefKind::Branch
+    } else if refname.starts_with("refs/remotes/") {
+        RefKind::Remote
+    } else if refname.starts_with("refs/tags/") {
+        RefKind::Tag
+    } else {
+        RefKind::Other
+    }
+}
+
+fn short_ref_name(refname: &str) -> &str {
+    ["refs/heads/", "refs/remotes/", "refs/tags/"]
+        .iter()
+        .find_map(|prefix| refname.strip_prefix(prefix))
+        .unwrap_or(refname)
+}
+
+// Arguments to leave refs matching any of the given globs out of the decorations.  Git matches the
+// globs against full ref names, only adding "refs/" if it is missing, so a glob that doesn't start
+// with "refs/" is also tried as the name of a tag, a branch, or a branch on any remote.  Then
+// "dependabot-*" hides tags, branches, and remote branches of that name, while "tags/dependabot-*"
+// and "remotes" still work as in git
+pub fn exclude_refs_args(globs: &[String]) -> Vec<String> {
+    globs
+        .iter()
+        .flat_map(|glob| {
+            if glob.starts_with("refs/") {
+                vec![glob.clone()]
+            } else {
+                ["refs/", "refs/tags/", "refs/heads/", "refs/remotes/*/"]
+                    .iter()
+                    .map(|prefix| format!("{}{}", prefix, glob))
+                    .collect()
+            }
+        })
+        .map(|glob| format!("--decorate-refs-exclude={}", glob))
+        .collect()
+}
+
+// Format decorations as git does, e.g. "(HEAD -> main, origin/main, tag: v1.0)", colouring each
+// kind of ref differently
+pub fn format_decorations(decorations: &[Decoration], colour: bool) -> String {
+    let list = decoration_list(decorations, colour);
+    if colour {
+        format!("{}{}{}", THEME.refs.paint("("), list, THEME.refs.paint(")"))
+    } else {
+        format!("({})", list)
+    }
+}
+
+// The decorations without the surrounding parentheses
+fn decoration_list(decorations: &[Decoration], colour: bool) -> String {
+    let paint = |s: &str, kind: RefKind| {
+        if !colour {
+            return s.to_string();
+        }
+        let theme_colour = match kind {
+            RefKind::Head => &THEME.ref_head,
+            RefKind::Branch => &THEME.ref_branch,
+            RefKind::Remote => &THEME.ref_remote,
+            RefKind::Tag => &THEME.ref_tag,
+            RefKind::Other => &THEME.refs,
+        };
+        theme_colour.paint(s).to_string()
+    };
+    let punctuation = |s: &str| {
+        if colour {
+            THEME.refs.paint(s).to_string()
+        } else {
+            s.to_string()
+        }
+    };
+
+    decorations
+        .iter()
+        .map(|d| match d.kind {
+            RefKind::Head if d.name.is_empty() => paint("HEAD", RefKind::Head),
+            RefKind::Head => format!(
+                "{}{}",
+                paint("HEAD -> ", RefKind::Head),
+                paint(&d.name, RefKind::Branch)
+            ),
+            RefKind::Tag => format!(
+                "{}{}",
+                paint("tag: ", RefKind::Tag),
+                paint(&d.name, RefKind::Tag)
+            ),
+            kind => paint(&d.name, kind),
+        })
+        .collect::<Vec<_>>()
+        .join(&punctuation(", "))
+}
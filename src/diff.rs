@@ -0,0 +1,193 @@
+use super::commit::{date_filter_args, merge_filter_arg, start_args};
+use super::opts::GitLogOptions;
+use super::pathspec::{exclude_pathspec_args, is_generated};
+use super::settings::SETTINGS;
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+use std::sync::Once;
+
+static MISSING_OBJECTS_WARNING: Once = Once::new();
+
+// Arguments that control how git computes line statistics (`--numstat`).  Every subsystem that
+// counts lines added and deleted should use these, so that their numbers agree
+pub fn diff_stat_args(opts: &GitLogOptions) -> Vec<String> {
+    let mut args = Vec::new();
+
+    // Without rename detection, a renamed file counts as every line deleted and added again
+    let rename_threshold = SETTINGS.rename_threshold.min(100);
+    if rename_threshold == 0 {
+        args.push(String::from("--no-renames"));
+    } else {
+        args.push(format!("--find-renames={}%", rename_threshold));
+    }
+
+    // Lines that only changed in whitespace are not counted at all
+    if opts.ignore_whitespace {
+        args.push(String::from("--ignore-all-space"));
+    }
+
+    args
+}
+
+// Whether the output of a `git log --numstat` call can be used.  With --no-fetch, git dies at the
+// first commit whose diff needs an object missing from a partial clone.  The newer commits before it
+// are still complete, so we count those (and warn once that the older ones were left out) rather than
+// fail.  Any other error is still a failure
+pub fn numstat_usable(output: &Output, opts: &GitLogOptions) -> bool {
+    if output.status.success() {
+        return true;
+    }
+    if opts.no_fetch && missing_object_error(&String::from_utf8_lossy(&output.stderr)) {
+        MISSING_OBJECTS_WARNING.call_once(|| {
+            eprintln!("[WARN] Stopped at a commit whose objects are missing from this partial clone; line statistics leave out it and every older commit");
+        });
+        return true;
+    }
+    false
+}
+
+// Whether git died because GIT_NO_LAZY_FETCH stopped it fetching an object it needed.  Git warns that
+// lazy fetching is disabled when it first refuses to fetch, then fails to read the object
+fn missing_object_error(stderr: &str) -> bool {
+    stderr.contains("lazy fetching disabled")
+        && stderr
+            .lines()
+            .any(|line| line.starts_with("fatal: unable to read "))
+}
+
+// The lines added and deleted, and the path, of a line of `--numstat` output.  Binary files have "-"
+// in place of line counts, which count as nothing.  Generated files are left out (see
+// pathspec::is_generated)
+pub fn parse_numstat_line(line: &str, opts: &GitLogOptions) -> Option<(usize, usize, String)> {
+    let mut parts = line.splitn(3, '\t');
+    let (added, deleted, path) = (parts.next()?, parts.next()?, parts.next()?);
+    let path = renamed_path(path);
+    if is_generated(&path, opts) {
+        return None;
+    }
+    Some((
+        added.parse().unwrap_or(0),
+        deleted.parse().unwrap_or(0),
+        path,
+    ))
+}
+
+// The number of lines added plus deleted by each commit in the revision walk given by the options,
+// keyed by full hash
+pub fn commit_churn(opts: &GitLogOptions) -> HashMap<String, usize> {
+    let mut cmd = Command::new("git");
+    cmd.arg("log");
+    cmd.args(merge_filter_arg(opts));
+    if opts.first_parent {
+        cmd.arg("--first-parent");
+    }
+    cmd.arg("--numstat");
+    cmd.args(diff_stat_args(opts));
+    cmd.arg("--format=%x00%H");
+    cmd.args(date_filter_args(opts));
+    // The same starting points as the log itself (see commit::git_log_cmd)
+    cmd.args(start_args(opts));
+    cmd.args(exclude_pathspec_args(opts));
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git log`");
+
+    let mut churn: HashMap<String, usize> = HashMap::new();
+    if !numstat_usable(&output, opts) {
+        return churn;
+    }
+    let mut hash = String::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(h) = line.strip_prefix('\0') {
+            // Commits that change nothing (once exclusions are applied) have no numstat lines
+            hash = h.to_string();
+            churn.insert(hash.clone(), 0);
+        } else if let Some((added, deleted, _)) = parse_numstat_line(line, opts) {
+            *churn.entry(hash.clone()).or_insert(0) += added + deleted;
+        }
+    }
+    churn
+}
+
+// The lines added and deleted by each of the given commits, keyed by full hash
+pub fn commit_line_counts(
+    hashes: &[&str],
+    opts: &GitLogOptions,
+) -> HashMap<String, (usize, usize)> {
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    if hashes.is_empty() {
+        return counts;
+    }
+
+    let mut child = Command::new("git")
+        .arg("log")
+        .arg("--no-walk=unsorted")
+        .arg("--stdin")
+        .arg("--numstat")
+        .args(diff_stat_args(opts))
+        .arg("--format=%x00%H")
+        .args(exclude_pathspec_args(opts))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute `git log`");
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(hashes.join("\n").as_bytes());
+    }
+    let output = child
+        .wait_with_output()
+        .expect("Failed to wait for `git log`");
+
+    if !numstat_usable(&output, opts) {
+        return counts;
+    }
+    let mut hash = String::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(h) = line.strip_prefix('\0') {
+            hash = h.to_string();
+            counts.insert(hash.clone(), (0, 0));
+        } else if let Some((added, deleted, _)) = parse_numstat_line(line, opts) {
+            let count = counts.entry(hash.clone()).or_insert((0, 0));
+            count.0 += added;
+            count.1 += deleted;
+        }
+    }
+    counts
+}
+
+// The new path of a file in numstat output, which shows renames as "old => new", or with the common
+// parts outside braces, as in "src/{old => new}/lib.rs"
+pub fn renamed_path(path: &str) -> String {
+    if let (Some(open), Some(close)) = (path.find('{'), path.rfind('}')) {
+        if let Some((_, new)) = path[open + 1..close].split_once(" => ") {
+            // Either side of the rename may be empty, e.g. "{ => src}/lib.rs"
+            let path = format!("{}{}{}", &path[..open], new, &path[close + 1..]);
+            return path.replace("//", "/").trim_start_matches('/').to_string();
+        }
+    }
+    match path.split_once(" => ") {
+        Some((_, new)) => new.to_string(),
+        None => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_missing_object_errors() {
+        let stderr = "warning: lazy fetching disabled; some objects may not be available\nfatal: unable to read 0123abc\n";
+        assert!(missing_object_error(stderr));
+        assert!(!missing_object_error(
+            "fatal: not a git repository (or any of the parent directories): .git\n"
+        ));
+        assert!(!missing_object_error(
+            "fatal: ambiguous argument 'nope': unknown revision or path not in the working tree.\n"
+        ));
+    }
+}
@@ -0,0 +1,181 @@
+use super::commit::{date_filter_args, merge_filter_arg};
+use super::identity::{author_filter_args, cluster_identities, normalise_email, GitIdentity};
+use super::opts::GitLogOptions;
+use chrono::{DateTime, Local};
+use json::JsonValue;
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+use tabular::{row, Table};
+
+// The commits a person made from one email domain
+pub struct DomainPeriod {
+    pub domain: String,
+    pub first: i64,
+    pub last: i64,
+    pub commits: usize,
+}
+
+// A person who has committed from more than one email domain, e.g. moving from a personal address
+// to a work one
+pub struct DomainChanges {
+    pub names: Vec<String>,
+    pub emails: Vec<String>,
+    // In order of first use
+    pub domains: Vec<DomainPeriod>,
+}
+
+fn email_domain(email: &str) -> Option<String> {
+    let email = normalise_email(email);
+    let (_, domain) = email.rsplit_once('@')?;
+    (!domain.is_empty()).then(|| domain.to_string())
+}
+
+// Find the people (see identity::cluster_identities) who have committed under more than one email
+// domain.  Emails are taken as written in the commits, not as mapped by the mailmap, so that this
+// shows what the mailmap needs to cover; names are mapped, so that people it already unifies are
+// grouped together
+pub fn domain_changes(opts: &GitLogOptions) -> Vec<DomainChanges> {
+    let mut cmd = Command::new("git");
+    cmd.arg("log");
+    cmd.arg("--reverse");
+    cmd.args(merge_filter_arg(opts));
+    if opts.first_parent {
+        cmd.arg("--first-parent");
+    }
+    cmd.arg("--format=%at%x00%aN%x00%ae");
+    cmd.args(author_filter_args(&opts.authors));
+    cmd.args(date_filter_args(opts));
+    match &opts.rev {
+        Some(rev) => cmd.arg(rev),
+        None => cmd.arg("--all"),
+    };
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git log`");
+
+    if !output.status.success() {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return vec![];
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let commits: Vec<(i64, &str, &str)> = stdout
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\0');
+            let time = parts.next()?.parse().ok()?;
+            Some((time, parts.next()?, parts.next()?))
+        })
+        .filter(|(_, name, email)| {
+            !(opts.no_bots
+                && GitIdentity {
+                    email: email.to_string(),
+                    names: vec![name.to_string()],
+                }
+                .is_bot())
+        })
+        .collect();
+    let pairs: Vec<(&str, &str)> = commits.iter().map(|(_, n, e)| (*n, *e)).collect();
+    let clusters = cluster_identities(&pairs);
+
+    // Commits are oldest first, so each person's domains are found in order of first use
+    let mut people: Vec<DomainChanges> = Vec::new();
+    let mut person_ids: HashMap<usize, usize> = HashMap::new();
+    for ((time, name, email), cluster) in commits.iter().zip(clusters) {
+        let id = *person_ids.entry(cluster).or_insert_with(|| {
+            people.push(DomainChanges {
+                names: Vec::new(),
+                emails: Vec::new(),
+                domains: Vec::new(),
+            });
+            people.len() - 1
+        });
+        let person = &mut people[id];
+        if !name.is_empty() && !person.names.iter().any(|n| n == name) {
+            person.names.push(name.to_string());
+        }
+        if !email.is_empty() && !person.emails.iter().any(|e| e == email) {
+            person.emails.push(email.to_string());
+        }
+        let Some(domain) = email_domain(email) else {
+            continue;
+        };
+        match person.domains.iter_mut().find(|d| d.domain == domain) {
+            Some(period) => {
+                period.last = *time;
+                period.commits += 1;
+            }
+            None => person.domains.push(DomainPeriod {
+                domain,
+                first: *time,
+                last: *time,
+                commits: 1,
+            }),
+        }
+    }
+
+    people.retain(|person| person.domains.len() > 1);
+    people
+}
+
+fn format_date(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|d| d.with_timezone(&Local).format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+pub fn display_domain_changes(people: Vec<DomainChanges>, opts: &GitLogOptions) {
+    if opts.json {
+        let people: Vec<JsonValue> = people
+            .iter()
+            .map(|person| {
+                let domains: Vec<JsonValue> = person
+                    .domains
+                    .iter()
+                    .map(|d| {
+                        json::object! {
+                            domain: d.domain.clone(),
+                            first: format_date(d.first),
+                            last: format_date(d.last),
+                            commits: d.commits,
+                        }
+                    })
+                    .collect();
+                json::object! {
+                    names: person.names.clone(),
+                    emails: person.emails.clone(),
+                    domains: domains,
+                }
+            })
+            .collect();
+        println!("{}", JsonValue::Array(people).pretty(2));
+        return;
+    }
+
+    if people.is_empty() {
+        println!("No authors have committed under more than one email domain.");
+        return;
+    }
+
+    let mut table = Table::new("{:<}  {:<}  {:<}  {:<}  {:>}")
+        .with_row(row!("Author", "Domain", "First", "Last", "Commits"));
+    for person in &people {
+        let name = person.names.first().or(person.emails.first());
+        for (i, period) in person.domains.iter().enumerate() {
+            table.add_row(row!(
+                if i == 0 {
+                    name.map_or("", |s| s.as_str())
+                } else {
+                    ""
+                },
+                &period.domain,
+                format_date(period.first),
+                format_date(period.last),
+                period.commits
+            ));
+        }
+    }
+    print!("{}", table);
+}
@@ -0,0 +1,108 @@
+use super::commit::{date_filter_args, merge_filter_arg, HashFormat};
+use super::diff::{diff_stat_args, numstat_usable};
+use super::opts::GitLogOptions;
+use colored::*;
+use std::process::{Command, Stdio};
+use tabular::{row, Table};
+
+pub struct FileCommitStats {
+    hash: String,
+    date: String,
+    author: String,
+    lines_added: usize,
+    lines_deleted: usize,
+}
+
+// Get the line changes made to the given file by each commit that touched it (following renames)
+pub fn file_commit_stats(path: &str, opts: &GitLogOptions) -> Vec<FileCommitStats> {
+    // git log --follow --numstat --format=... -- path
+    let mut cmd = Command::new("git");
+    cmd.arg("log");
+    cmd.arg("--follow");
+    cmd.args(merge_filter_arg(opts));
+    cmd.arg("--numstat");
+    cmd.args(diff_stat_args(opts));
+    cmd.arg("--date=short");
+    cmd.arg("--format=%x00%H%x00%ad%x00%an");
+    cmd.args(date_filter_args(opts));
+    cmd.arg("--");
+    cmd.arg(path);
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git log`");
+
+    if !numstat_usable(&output, opts) {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return vec![];
+    }
+
+    let mut stats: Vec<FileCommitStats> = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(header) = line.strip_prefix('\0') {
+            // Each commit starts with a header line, which is followed by its numstat lines
+            let mut parts = header.split('\0');
+            stats.push(FileCommitStats {
+                hash: parts.next().unwrap_or("").to_string(),
+                date: parts.next().unwrap_or("").to_string(),
+                author: parts.next().unwrap_or("").to_string(),
+                lines_added: 0,
+                lines_deleted: 0,
+            });
+        } else if let Some(commit_stats) = stats.last_mut() {
+            // Binary files have "-" in place of line counts
+            let mut parts = line.split_whitespace();
+            if let (Some(added), Some(deleted)) = (parts.next(), parts.next()) {
+                commit_stats.lines_added += added.parse::<usize>().unwrap_or(0);
+                commit_stats.lines_deleted += deleted.parse::<usize>().unwrap_or(0);
+            }
+        }
+    }
+
+    stats
+}
+
+pub fn display_file_commit_stats(path: &str, stats: Vec<FileCommitStats>, opts: &GitLogOptions) {
+    if stats.is_empty() {
+        println!("No commits found changing {}.", path);
+        return;
+    }
+
+    let mut table = Table::new("{:<}  {:<}  {:<}  {:>}  {:>}")
+        .with_row(row!("Commit", "Date", "Author", "Added", "Deleted"));
+
+    let mut total_added = 0;
+    let mut total_deleted = 0;
+    for commit_stats in &stats {
+        total_added += commit_stats.lines_added;
+        total_deleted += commit_stats.lines_deleted;
+        table.add_row(row!(
+            commit_stats.hash.short(),
+            &commit_stats.date,
+            &commit_stats.author,
+            format!("+{}", commit_stats.lines_added),
+            format!("-{}", commit_stats.lines_deleted),
+        ));
+    }
+    println!("{}", table);
+
+    let plural_maybe = match stats.len() {
+        1 => "",
+        _ => "s",
+    };
+    let summary = format!(
+        "{} commit{} changed {}: +{} -{} ({} lines of churn)",
+        stats.len(),
+        plural_maybe,
+        path,
+        total_added,
+        total_deleted,
+        total_added + total_deleted,
+    );
+    if opts.colour {
+        println!("{}", summary.bold());
+    } else {
+        println!("{}", summary);
+    }
+}
@@ -0,0 +1,580 @@
+use super::cache;
+use super::context::Context;
+use super::opts::GitLogOptions;
+use super::remote::{self, RemoteRepo};
+use super::settings::SETTINGS;
+use chrono::Local;
+use json::JsonValue;
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Details of a pull (or merge) request that are only available from the forge's API
+pub struct PullRequestDetails {
+    pub title: String,
+    pub author: String,
+    pub merged_at: Option<String>,
+    pub reviews: usize,
+    pub labels: Vec<String>,
+}
+
+impl PullRequestDetails {
+    fn to_json(&self) -> JsonValue {
+        json::object! {
+            title: self.title.clone(),
+            author: self.author.clone(),
+            merged_at: self.merged_at.clone(),
+            reviews: self.reviews,
+            labels: self.labels.clone(),
+        }
+    }
+
+    fn from_json(value: &JsonValue) -> Option<Self> {
+        Some(PullRequestDetails {
+            title: value["title"].as_str()?.to_string(),
+            author: value["author"].as_str()?.to_string(),
+            merged_at: value["merged_at"].as_str().map(String::from),
+            reviews: value["reviews"].as_usize()?,
+            // Details cached before labels were fetched have none
+            labels: string_members(&value["labels"]),
+        })
+    }
+}
+
+// A service hosting the repository, such as GitHub, GitLab, or Gitea/Forgejo
+pub trait Forge {
+    fn name(&self) -> &'static str;
+    fn repo(&self) -> &RemoteRepo;
+
+    // Environment variables that may hold an API token, in order of preference
+    fn token_vars(&self) -> &'static [&'static str];
+
+    // The header with which to authenticate API requests
+    fn auth_header(&self, token: &str) -> String;
+
+    fn pull_request_url(&self, number: u64) -> String;
+
+    // How a pull request is referred to in commit messages and Markdown, which the forge links
+    fn pull_request_ref(&self, number: u64) -> String {
+        format!("#{}", number)
+    }
+
+    // The web page for a branch (or other revision) of the repository
+    fn tree_url(&self, rev: &str) -> String;
+
+    // The web page comparing two revisions
+    fn compare_url(&self, from: &str, to: &str) -> String;
+
+    // Fetch the details of a pull request from the API, without caching
+    fn fetch_pull_request_details(&self, number: u64, token: &str) -> Option<PullRequestDetails>;
+
+    fn token(&self) -> Option<String> {
+        self.token_vars()
+            .iter()
+            .find_map(|name| env::var(name).ok().filter(|token| !token.is_empty()))
+    }
+
+    // Fetch the details of a merged pull request.  Once a pull request has been merged its details
+    // rarely change, so they are cached indefinitely
+    fn pull_request_details(&self, number: u64, token: &str) -> Option<PullRequestDetails> {
+        let repo = self.repo();
+        let cache_key = format!("{}/{}/pulls/{}.json", repo.host, repo.path, number);
+        if let Some(details) = cache::read(&cache_key)
+            .and_then(|cached| json::parse(&cached).ok())
+            .and_then(|cached| PullRequestDetails::from_json(&cached))
+        {
+            return Some(details);
+        }
+
+        let details = self.fetch_pull_request_details(number, token)?;
+        if details.merged_at.is_some() {
+            cache::write(&cache_key, &details.to_json().dump());
+        }
+        Some(details)
+    }
+}
+
+pub struct GitHub {
+    repo: RemoteRepo,
+}
+
+pub struct GitLab {
+    repo: RemoteRepo,
+}
+
+// Forgejo (e.g. Codeberg) shares Gitea's API
+pub struct Gitea {
+    repo: RemoteRepo,
+}
+
+impl GitHub {
+    // GitHub Enterprise serves its API under /api/v3 rather than on a subdomain
+    fn api_url(&self) -> String {
+        if self.repo.host == "github.com" {
+            String::from("https://api.github.com")
+        } else {
+            format!("https://{}/api/v3", self.repo.host)
+        }
+    }
+}
+
+impl Forge for GitHub {
+    fn name(&self) -> &'static str {
+        "GitHub"
+    }
+
+    fn repo(&self) -> &RemoteRepo {
+        &self.repo
+    }
+
+    fn token_vars(&self) -> &'static [&'static str] {
+        &["GITHUB_TOKEN", "GH_TOKEN"]
+    }
+
+    fn auth_header(&self, token: &str) -> String {
+        format!("Authorization: Bearer {}", token)
+    }
+
+    fn pull_request_url(&self, number: u64) -> String {
+        format!("{}/pull/{}", self.repo.web_url(), number)
+    }
+
+    fn tree_url(&self, rev: &str) -> String {
+        format!("{}/tree/{}", self.repo.web_url(), rev)
+    }
+
+    fn compare_url(&self, from: &str, to: &str) -> String {
+        format!("{}/compare/{}...{}", self.repo.web_url(), from, to)
+    }
+
+    fn fetch_pull_request_details(&self, number: u64, token: &str) -> Option<PullRequestDetails> {
+        let pr_url = format!(
+            "{}/repos/{}/pulls/{}",
+            self.api_url(),
+            self.repo.path,
+            number
+        );
+        let pr = api_get(self, &pr_url, token)?;
+        let reviews = api_count(self, &format!("{}/reviews?per_page=", pr_url), 100, token)?;
+        Some(PullRequestDetails {
+            title: pr["title"].as_str().unwrap_or("").to_string(),
+            author: pr["user"]["login"].as_str().unwrap_or("").to_string(),
+            merged_at: pr["merged_at"].as_str().map(String::from),
+            reviews,
+            labels: label_names(&pr["labels"]),
+        })
+    }
+}
+
+impl Forge for GitLab {
+    fn name(&self) -> &'static str {
+        "GitLab"
+    }
+
+    fn repo(&self) -> &RemoteRepo {
+        &self.repo
+    }
+
+    fn token_vars(&self) -> &'static [&'static str] {
+        &["GITLAB_TOKEN"]
+    }
+
+    fn auth_header(&self, token: &str) -> String {
+        format!("PRIVATE-TOKEN: {}", token)
+    }
+
+    fn pull_request_url(&self, number: u64) -> String {
+        format!("{}/-/merge_requests/{}", self.repo.web_url(), number)
+    }
+
+    // On GitLab, "#1234" is an issue
+    fn pull_request_ref(&self, number: u64) -> String {
+        format!("!{}", number)
+    }
+
+    fn tree_url(&self, rev: &str) -> String {
+        format!("{}/-/tree/{}", self.repo.web_url(), rev)
+    }
+
+    fn compare_url(&self, from: &str, to: &str) -> String {
+        format!("{}/-/compare/{}...{}", self.repo.web_url(), from, to)
+    }
+
+    // GitLab has no reviews as such, so we count approvals instead
+    fn fetch_pull_request_details(&self, number: u64, token: &str) -> Option<PullRequestDetails> {
+        let mr_url = format!(
+            "https://{}/api/v4/projects/{}/merge_requests/{}",
+            self.repo.host,
+            self.repo.path.replace('/', "%2F"),
+            number
+        );
+        let mr = api_get(self, &mr_url, token)?;
+        let approvals = api_get(self, &format!("{}/approvals", mr_url), token)?;
+        Some(PullRequestDetails {
+            title: mr["title"].as_str().unwrap_or("").to_string(),
+            author: mr["author"]["username"].as_str().unwrap_or("").to_string(),
+            merged_at: mr["merged_at"].as_str().map(String::from),
+            reviews: approvals["approved_by"].len(),
+            labels: string_members(&mr["labels"]),
+        })
+    }
+}
+
+impl Forge for Gitea {
+    fn name(&self) -> &'static str {
+        "Gitea"
+    }
+
+    fn repo(&self) -> &RemoteRepo {
+        &self.repo
+    }
+
+    fn token_vars(&self) -> &'static [&'static str] {
+        &["GITEA_TOKEN", "FORGEJO_TOKEN"]
+    }
+
+    fn auth_header(&self, token: &str) -> String {
+        format!("Authorization: token {}", token)
+    }
+
+    fn pull_request_url(&self, number: u64) -> String {
+        format!("{}/pulls/{}", self.repo.web_url(), number)
+    }
+
+    fn tree_url(&self, rev: &str) -> String {
+        format!("{}/src/branch/{}", self.repo.web_url(), rev)
+    }
+
+    fn compare_url(&self, from: &str, to: &str) -> String {
+        format!("{}/compare/{}...{}", self.repo.web_url(), from, to)
+    }
+
+    fn fetch_pull_request_details(&self, number: u64, token: &str) -> Option<PullRequestDetails> {
+        let pr_url = format!(
+            "https://{}/api/v1/repos/{}/pulls/{}",
+            self.repo.host, self.repo.path, number
+        );
+        let pr = api_get(self, &pr_url, token)?;
+        // Gitea gives at most 50 items a page unless configured otherwise
+        let reviews = api_count(self, &format!("{}/reviews?limit=", pr_url), 50, token)?;
+        Some(PullRequestDetails {
+            title: pr["title"].as_str().unwrap_or("").to_string(),
+            author: pr["user"]["login"].as_str().unwrap_or("").to_string(),
+            merged_at: pr["merged_at"].as_str().map(String::from),
+            reviews,
+            labels: label_names(&pr["labels"]),
+        })
+    }
+}
+
+// The number of items in a paginated API list.  The URL ends in the parameter giving the page size,
+// and we keep asking for the next page until one isn't full
+fn api_count(forge: &dyn Forge, url: &str, per_page: usize, token: &str) -> Option<usize> {
+    let mut count = 0;
+    for page in 1.. {
+        let items = api_get(forge, &format!("{}{}&page={}", url, per_page, page), token)?;
+        count += items.len();
+        if items.len() < per_page {
+            break;
+        }
+    }
+    Some(count)
+}
+
+// GitHub and Gitea give labels as objects, and GitLab as plain names
+fn label_names(labels: &JsonValue) -> Vec<String> {
+    labels
+        .members()
+        .filter_map(|label| label["name"].as_str().map(String::from))
+        .collect()
+}
+
+fn string_members(values: &JsonValue) -> Vec<String> {
+    values
+        .members()
+        .filter_map(|value| value.as_str().map(String::from))
+        .collect()
+}
+
+// The forge hosting the given remote, if we know what it is.  Well-known hosts are recognised, and
+// self-hosted instances can be configured in the settings (see config::FORGE_HOSTS)
+pub fn forge(remote_name: &str) -> Option<Box<dyn Forge>> {
+    let repo = remote::remote_repo(remote_name)?;
+    let configured_kind = SETTINGS.forge_hosts.iter().find_map(|entry| {
+        let (host, kind) = entry.split_once('=')?;
+        (host.trim() == repo.host).then(|| kind.trim().to_lowercase())
+    });
+    let kind = configured_kind.as_deref().or(match repo.host.as_str() {
+        "github.com" => Some("github"),
+        "gitlab.com" => Some("gitlab"),
+        "codeberg.org" | "gitea.com" => Some("gitea"),
+        _ => None,
+    })?;
+
+    match kind {
+        "github" => Some(Box::new(GitHub { repo })),
+        "gitlab" => Some(Box::new(GitLab { repo })),
+        "gitea" | "forgejo" => Some(Box::new(Gitea { repo })),
+        kind => {
+            eprintln!(
+                "[WARN] Unknown forge {:?} for {}: expected \"github\", \"gitlab\", or \"gitea\"",
+                kind, repo.host
+            );
+            None
+        }
+    }
+}
+
+// Print the web page for the current branch (or the revision given with --ref) on the forge
+pub fn display_browse_url(ctx: &Context, opts: &GitLogOptions) {
+    let Some(forge) = forge("origin") else {
+        println!("Unable to determine where the origin remote is hosted.");
+        return;
+    };
+
+    // A detached HEAD has no branch to link to, so we link to the repository itself
+    match opts.rev.as_deref().or(ctx.current_branch()) {
+        Some(rev) if rev != "HEAD" => println!("{}", forge.tree_url(rev)),
+        _ => println!("{}", forge.repo().web_url()),
+    }
+}
+
+// Whether we have already noted that cached responses are being used in place of the API
+static USING_CACHED: AtomicBool = AtomicBool::new(false);
+
+// The cache key for an API response, e.g. "http/api.github.com/repos/owner/repo/pulls/1.json".
+// Every key ends in ".json" so that no key is a directory of another
+fn http_cache_key(url: &str) -> String {
+    let path: String = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '/' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("http/{}.json", path.trim_end_matches('/'))
+}
+
+// An API response we have seen before, with the ETag to revalidate it with
+struct CachedResponse {
+    etag: Option<String>,
+    fetched_at: i64,
+    body: JsonValue,
+}
+
+impl CachedResponse {
+    fn read(key: &str) -> Option<Self> {
+        let cached = json::parse(&cache::read(key)?).ok()?;
+        Some(CachedResponse {
+            etag: cached["etag"].as_str().map(String::from),
+            fetched_at: cached["fetched_at"].as_i64()?,
+            body: cached["body"].clone(),
+        })
+    }
+
+    fn write(&self, key: &str) {
+        let cached = json::object! {
+            etag: self.etag.clone(),
+            fetched_at: self.fetched_at,
+            body: self.body.clone(),
+        };
+        cache::write(key, &cached.dump());
+    }
+
+    // Use the cached response when the API can't be reached, noting (once) that it may be stale
+    fn offline(self, forge: &dyn Forge) -> JsonValue {
+        if !USING_CACHED.swap(true, Ordering::Relaxed) {
+            let age = Local::now().timestamp() - self.fetched_at;
+            eprintln!(
+                "[WARN] Unable to reach the {} API, so showing cached responses, which may be out of date (e.g. from {} ago)",
+                forge.name(),
+                approximate_duration(age)
+            );
+        }
+        self.body
+    }
+}
+
+// A rough length of time for staleness notes, e.g. "5 minutes" or "3 days"
+fn approximate_duration(seconds: i64) -> String {
+    let (n, unit) = match seconds.max(0) {
+        s if s < 60 * 60 => (s / 60, "minute"),
+        s if s < 60 * 60 * 24 => (s / (60 * 60), "hour"),
+        s => (s / (60 * 60 * 24), "day"),
+    };
+    format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" })
+}
+
+// Split a response printed with `curl --include` into its status code, ETag, and body.  After a
+// redirect (or through a proxy) there are several header blocks, of which the last is the response
+fn parse_http_response(response: &str) -> Option<(u32, Option<String>, &str)> {
+    let mut rest = response;
+    let mut headers = "";
+    while rest.starts_with("HTTP/") {
+        let (block, body) = rest
+            .split_once("\r\n\r\n")
+            .or_else(|| rest.split_once("\n\n"))
+            .unwrap_or((rest, ""));
+        headers = block;
+        rest = body;
+    }
+    let status = headers
+        .lines()
+        .next()?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()?;
+    let etag = headers.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("etag")
+            .then(|| value.trim().to_string())
+    });
+    Some((status, etag, rest))
+}
+
+// Make a GET request to the forge's API, using curl so that we needn't bundle an HTTP client.  The
+// token is given to curl on stdin so that it doesn't show up in the process list
+//
+// Responses are cached (see cache.rs) and revalidated with their ETag, which doesn't count towards
+// GitHub's rate limit.  If the API can't be reached (e.g. offline) or is having problems, we use the
+// cached response if there is one
+fn api_get(forge: &dyn Forge, url: &str, token: &str) -> Option<JsonValue> {
+    let cache_key = http_cache_key(url);
+    let cached = CachedResponse::read(&cache_key);
+
+    let child = Command::new("curl")
+        .arg("--silent")
+        .arg("--show-error")
+        .arg("--include")
+        .arg("--location")
+        .arg("--connect-timeout")
+        .arg("10")
+        .arg("--header")
+        .arg("Accept: application/json")
+        .args(
+            cached
+                .as_ref()
+                .and_then(|cached| cached.etag.as_ref())
+                .map(|etag| ["--header".to_string(), format!("If-None-Match: {}", etag)])
+                .into_iter()
+                .flatten(),
+        )
+        .arg("--header")
+        .arg("@-")
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let output = child.and_then(|mut child| {
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "{}", forge.auth_header(token))?;
+        }
+        child.wait_with_output()
+    });
+
+    let output = match output {
+        Ok(output) => output,
+        Err(_) => {
+            eprintln!(
+                "[WARN] Unable to run `curl` to query the {} API",
+                forge.name()
+            );
+            return cached.map(|cached| cached.offline(forge));
+        }
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let response = if output.status.success() {
+        parse_http_response(&stdout)
+    } else {
+        None
+    };
+
+    match (response, cached) {
+        (Some((200..=299, etag, body)), _) => {
+            let body = json::parse(body).ok()?;
+            CachedResponse {
+                etag,
+                fetched_at: Local::now().timestamp(),
+                body: body.clone(),
+            }
+            .write(&cache_key);
+            Some(body)
+        }
+        // Not modified since we cached it
+        (Some((304, _, _)), Some(mut cached)) => {
+            cached.fetched_at = Local::now().timestamp();
+            cached.write(&cache_key);
+            Some(cached.body)
+        }
+        // Server errors and failures to connect are worth falling back on the cache for, unlike
+        // client errors (e.g. a bad token), which would only recur
+        (Some((500..=599, _, _)) | None, Some(cached)) => Some(cached.offline(forge)),
+        (Some((status, _, _)), _) => {
+            eprintln!(
+                "[WARN] {} API request for {} failed with status {}",
+                forge.name(),
+                url,
+                status
+            );
+            None
+        }
+        (None, None) => {
+            eprintln!(
+                "[WARN] {} API request for {} failed: {}",
+                forge.name(),
+                url,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_responses() {
+        let response = "HTTP/1.1 301 Moved Permanently\r\nLocation: /b\r\n\r\nHTTP/2 200\r\netag: W/\"abc\"\r\n\r\n{\"a\": 1}";
+        assert_eq!(
+            parse_http_response(response),
+            Some((200, Some(String::from("W/\"abc\"")), "{\"a\": 1}"))
+        );
+        assert_eq!(
+            parse_http_response("HTTP/2 304\r\n\r\n"),
+            Some((304, None, ""))
+        );
+        assert_eq!(parse_http_response(""), None);
+    }
+
+    #[test]
+    fn refers_to_pull_requests_as_the_forge_does() {
+        let repo = RemoteRepo::parse("https://example.com/owner/repo").unwrap();
+        let github = GitHub { repo: repo.clone() };
+        let gitlab = GitLab { repo };
+        assert_eq!(github.pull_request_ref(12), "#12");
+        assert_eq!(gitlab.pull_request_ref(12), "!12");
+        assert_eq!(
+            gitlab.pull_request_url(12),
+            "https://example.com/owner/repo/-/merge_requests/12"
+        );
+    }
+
+    #[test]
+    fn cache_keys() {
+        assert_eq!(
+            http_cache_key("https://api.github.com/repos/o/r/pulls/1/reviews?per_page=100&page=2"),
+            "http/api.github.com/repos/o/r/pulls/1/reviews_per_page_100_page_2.json"
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use super::commit::{merge_filter_arg, start_args};
+use super::identity::{author_filter_args, own_author_filters};
+use super::opts::GitLogOptions;
+use super::settings::SETTINGS;
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveTime};
+use colored::*;
+use std::process::{Command, Stdio};
+
+// How many periods back to look for a streak
+const MAX_STREAK: usize = 100;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GoalPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+// A number of commits to make each day, week, or month
+#[derive(PartialEq, Debug)]
+pub struct CommitGoal {
+    pub commits: usize,
+    pub period: GoalPeriod,
+}
+
+impl GoalPeriod {
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "d" | "day" | "daily" => Some(GoalPeriod::Day),
+            "w" | "week" | "weekly" => Some(GoalPeriod::Week),
+            "m" | "month" | "monthly" => Some(GoalPeriod::Month),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            GoalPeriod::Day => "day",
+            GoalPeriod::Week => "week",
+            GoalPeriod::Month => "month",
+        }
+    }
+
+    // The start of the period `back` periods before the one containing the given time (days and
+    // weeks start at midnight, and weeks on Monday)
+    fn start(&self, t: DateTime<Local>, back: usize) -> Option<DateTime<Local>> {
+        let today = t.with_time(NaiveTime::MIN).single()?;
+        match self {
+            GoalPeriod::Day => Some(today - Duration::days(back as i64)),
+            GoalPeriod::Week => Some(
+                today
+                    - Duration::days(today.weekday().num_days_from_monday() as i64)
+                    - Duration::weeks(back as i64),
+            ),
+            GoalPeriod::Month => today
+                .with_day(1)?
+                .checked_sub_months(Months::new(back as u32)),
+        }
+    }
+
+    // The start of the period after the one starting at the given time
+    fn next(&self, start: DateTime<Local>) -> Option<DateTime<Local>> {
+        match self {
+            GoalPeriod::Day => Some(start + Duration::days(1)),
+            GoalPeriod::Week => Some(start + Duration::weeks(1)),
+            GoalPeriod::Month => start.checked_add_months(Months::new(1)),
+        }
+    }
+}
+
+impl CommitGoal {
+    // Parse a goal such as "5/week", "20 per month", or "1/day".  The period defaults to a week
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let (n, period) = match s.split_once(|c: char| c == '/' || c.is_whitespace()) {
+            Some((n, period)) => (n, Some(period.trim().trim_start_matches("per "))),
+            None => (s, None),
+        };
+        Self::from_parts(n, period)
+    }
+
+    // A goal from the arguments to --goal: a number of commits, and optionally a period
+    pub fn from_parts(n: &str, period: Option<&str>) -> Option<Self> {
+        let commits = n.trim().parse().ok().filter(|&n| n > 0)?;
+        let period = match period {
+            Some(period) => GoalPeriod::parse(period)?,
+            None => GoalPeriod::Week,
+        };
+        Some(CommitGoal { commits, period })
+    }
+}
+
+// The goal in settings, if there is one
+pub fn configured_goal() -> Option<CommitGoal> {
+    if SETTINGS.commit_goal.trim().is_empty() {
+        return None;
+    }
+    let goal = CommitGoal::parse(&SETTINGS.commit_goal);
+    if goal.is_none() {
+        eprintln!(
+            "[WARN] Ignoring invalid commit goal {:?}; expected e.g. \"5/week\"",
+            SETTINGS.commit_goal
+        );
+    }
+    goal
+}
+
+// The times of your commits (or those of the authors given with --author etc.) since the given time
+fn commit_times(since: DateTime<Local>, opts: &GitLogOptions) -> Vec<i64> {
+    let mut cmd = Command::new("git");
+    cmd.arg("log");
+    cmd.args(merge_filter_arg(opts));
+    if opts.first_parent {
+        cmd.arg("--first-parent");
+    }
+    cmd.arg(if opts.author_date {
+        "--format=%at"
+    } else {
+        "--format=%ct"
+    });
+    // Author dates may be earlier than commit dates, so we filter those ourselves
+    if !opts.author_date {
+        cmd.arg(format!("--since={}", since.timestamp()));
+    }
+    cmd.args(author_filter_args(&own_author_filters(&opts.authors)));
+    cmd.args(start_args(opts));
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git log`");
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.trim().parse().ok())
+        .filter(|&t| t >= since.timestamp())
+        .collect()
+}
+
+pub struct GoalProgress {
+    pub commits: usize,
+    // The fraction of the current period that has passed
+    pub elapsed: f64,
+    // The number of periods before this one in a row in which the goal was met
+    pub streak: usize,
+}
+
+impl GoalProgress {
+    fn on_pace(&self, goal: &CommitGoal) -> bool {
+        self.commits as f64 >= goal.commits as f64 * self.elapsed
+    }
+}
+
+pub fn goal_progress(goal: &CommitGoal, opts: &GitLogOptions) -> Option<GoalProgress> {
+    let now = Local::now();
+    let start = goal.period.start(now, 0)?;
+    let end = goal.period.next(start)?;
+    let oldest = goal.period.start(now, MAX_STREAK)?;
+    let times = commit_times(oldest, opts);
+
+    let in_period = |back: usize| -> Option<usize> {
+        let from = goal.period.start(now, back)?.timestamp();
+        let to = goal.period.start(now, back.checked_sub(1)?)?.timestamp();
+        Some(times.iter().filter(|&&t| t >= from && t < to).count())
+    };
+    let streak = (1..=MAX_STREAK)
+        .take_while(|&back| in_period(back).is_some_and(|n| n >= goal.commits))
+        .count();
+
+    Some(GoalProgress {
+        commits: times.iter().filter(|&&t| t >= start.timestamp()).count(),
+        elapsed: (now - start).num_seconds() as f64 / (end - start).num_seconds() as f64,
+        streak,
+    })
+}
+
+pub fn display_goal_progress(goal: &CommitGoal, opts: &GitLogOptions) {
+    let Some(progress) = goal_progress(goal, opts) else {
+        return;
+    };
+
+    let period = goal.period.name();
+    let status = if progress.commits >= goal.commits {
+        "goal reached"
+    } else if progress.on_pace(goal) {
+        "on pace"
+    } else {
+        "behind pace"
+    };
+    let message = format!(
+        "{}/{} commits {}, {}.",
+        progress.commits,
+        goal.commits,
+        match goal.period {
+            GoalPeriod::Day => String::from("today"),
+            _ => format!("this {}", period),
+        },
+        status
+    );
+    if opts.colour {
+        let message = if progress.on_pace(goal) {
+            message.green()
+        } else {
+            message.yellow()
+        };
+        println!("{}", message.bold());
+    } else {
+        println!("{}", message);
+    }
+
+    // Count this period towards the streak once the goal is reached
+    let streak = progress.streak + usize::from(progress.commits >= goal.commits);
+    match streak {
+        0 => println!("Goal not met last {}.", period),
+        1 => println!("Streak: 1 {}.", period),
+        n if n > MAX_STREAK => println!("Streak: over {} {}s.", MAX_STREAK, period),
+        n => println!("Streak: {} {}s.", n, period),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_goals() {
+        let goal = |commits, period| Some(CommitGoal { commits, period });
+        assert_eq!(CommitGoal::parse("5/week"), goal(5, GoalPeriod::Week));
+        assert_eq!(
+            CommitGoal::parse("20 per month"),
+            goal(20, GoalPeriod::Month)
+        );
+        assert_eq!(CommitGoal::parse(" 1/d "), goal(1, GoalPeriod::Day));
+        assert_eq!(CommitGoal::parse("3"), goal(3, GoalPeriod::Week));
+        assert_eq!(CommitGoal::parse("0/week"), None);
+        assert_eq!(CommitGoal::parse("5/fortnight"), None);
+        assert_eq!(CommitGoal::parse("five"), None);
+    }
+}
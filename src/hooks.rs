@@ -0,0 +1,115 @@
+use super::repo;
+use clap::ValueEnum;
+use std::fs;
+use std::path::PathBuf;
+
+// Git hooks that gl can install.  The templates live in the hooks directory at the root of the
+// crate, and each starts with a marker line so that we only ever overwrite or remove our own hooks
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum HookKind {
+    // post-commit: write today's commit count to a file for shell prompts
+    CommitCount,
+    // prepare-commit-msg: stop amending commits that have been pushed (see --amend-check)
+    AmendCheck,
+}
+
+impl HookKind {
+    fn name(&self) -> &'static str {
+        match self {
+            HookKind::CommitCount => "commit-count",
+            HookKind::AmendCheck => "amend-check",
+        }
+    }
+
+    // The git hook that runs the template
+    fn hook_name(&self) -> &'static str {
+        match self {
+            HookKind::CommitCount => "post-commit",
+            HookKind::AmendCheck => "prepare-commit-msg",
+        }
+    }
+
+    fn template(&self) -> &'static str {
+        match self {
+            HookKind::CommitCount => include_str!("../hooks/commit-count.sh"),
+            HookKind::AmendCheck => include_str!("../hooks/amend-check.sh"),
+        }
+    }
+
+    // The line that marks a hook as installed by gl
+    fn marker(&self) -> String {
+        format!("# Installed by gl ({})", self.name())
+    }
+}
+
+// Where the hook lives, respecting core.hooksPath
+fn hook_path(kind: &HookKind) -> Option<PathBuf> {
+    repo::git_path(&format!("hooks/{}", kind.hook_name()))
+}
+
+pub fn install_hook(kind: HookKind) {
+    let Some(path) = hook_path(&kind) else {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return;
+    };
+
+    // Don't clobber hooks that someone else wrote
+    if let Ok(existing) = fs::read_to_string(&path) {
+        if !existing.contains(&kind.marker()) {
+            eprintln!(
+                "[ERROR] A {} hook already exists at {}; add the contents of gl's {} hook to it by hand",
+                kind.hook_name(),
+                path.display(),
+                kind.name()
+            );
+            return;
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            eprintln!("[ERROR] Unable to create {}: {}", parent.display(), e);
+            return;
+        }
+    }
+    if let Err(e) = fs::write(&path, kind.template()) {
+        eprintln!("[ERROR] Unable to write {}: {}", path.display(), e);
+        return;
+    }
+
+    // Git only runs hooks that are executable
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Err(e) = fs::set_permissions(&path, fs::Permissions::from_mode(0o755)) {
+            eprintln!(
+                "[ERROR] Unable to make {} executable: {}",
+                path.display(),
+                e
+            );
+            return;
+        }
+    }
+
+    println!("Installed the {} hook as {}.", kind.name(), path.display());
+}
+
+pub fn uninstall_hook(kind: HookKind) {
+    let Some(path) = hook_path(&kind) else {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return;
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(existing) if existing.contains(&kind.marker()) => match fs::remove_file(&path) {
+            Ok(()) => println!("Removed the {} hook from {}.", kind.name(), path.display()),
+            Err(e) => eprintln!("[ERROR] Unable to remove {}: {}", path.display(), e),
+        },
+        Ok(_) => eprintln!(
+            "[ERROR] The {} hook at {} was not installed by gl, so it has been left alone",
+            kind.hook_name(),
+            path.display()
+        ),
+        Err(_) => println!("The {} hook is not installed.", kind.name()),
+    }
+}
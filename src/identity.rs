@@ -1,5 +1,410 @@
+use super::config::AUTHOR_GROUPS;
+use super::settings::SETTINGS;
+use super::theme::{ThemeColour, THEME};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+
+lazy_static! {
+    // Your own identity and bots are always matched first
+    static ref AUTHOR_GROUP_RULES: Vec<AuthorGroup> = vec![
+        AuthorGroup::new::<&str>("me", SETTINGS.identity.clone(), &[], THEME.me_author.clone()),
+        AuthorGroup::new("bots", vec![], &SETTINGS.bot_patterns, THEME.bot_author.clone()),
+    ]
+    .into_iter()
+    .chain(AUTHOR_GROUPS.iter().map(AuthorGroup::from_config))
+    .collect();
+}
+
+// The name of the group in AUTHOR_GROUP_RULES that --no-bots excludes, so that the same authors are
+// highlighted and excluded as bots
+const BOTS_GROUP: &str = "bots";
+
 #[derive(Clone)]
 pub struct GitIdentity {
     pub email: String,
     pub names: Vec<String>,
 }
+
+// Interns strings (e.g. emails) as small integer IDs, so that aggregating over every commit in a
+// large repository needn't allocate a String per commit: each distinct string is stored once, and
+// aggregation maps can be plain vectors indexed by ID
+#[derive(Default)]
+pub struct Interner {
+    ids: HashMap<String, usize>,
+    strings: Vec<String>,
+}
+
+impl Interner {
+    // The ID of the given string, which is new (and one more than the previous new ID) if it has
+    // not been seen before
+    pub fn intern(&mut self, s: &str) -> usize {
+        if let Some(id) = self.ids.get(s) {
+            return *id;
+        }
+        let id = self.strings.len();
+        self.strings.push(s.to_string());
+        self.ids.insert(s.to_string(), id);
+        id
+    }
+
+    // The ID of the given string, if it has been seen
+    pub fn get(&self, s: &str) -> Option<usize> {
+        self.ids.get(s).copied()
+    }
+
+    // The string with the given ID
+    pub fn resolve(&self, id: usize) -> &str {
+        &self.strings[id]
+    }
+}
+
+// Group (name, email) pairs into people: two pairs belong to the same person if they share a name
+// or an email, directly or through other pairs.  Returns the cluster of each pair, numbered in order
+// of first appearance.  Names are compared ignoring case and emails as by normalise_email; empty
+// names and emails join nothing, as old repositories have many commits with neither
+pub fn cluster_identities(pairs: &[(&str, &str)]) -> Vec<usize> {
+    // Names and emails are nodes of a union-find forest, and each pair joins its name to its email
+    let mut nodes = Interner::default();
+    let mut parent: Vec<usize> = Vec::new();
+    fn find(parent: &mut [usize], mut node: usize) -> usize {
+        while parent[node] != node {
+            parent[node] = parent[parent[node]];
+            node = parent[node];
+        }
+        node
+    }
+
+    let mut pair_nodes = Vec::with_capacity(pairs.len());
+    for (i, (name, email)) in pairs.iter().enumerate() {
+        let name = name.trim().to_lowercase();
+        let email = normalise_email(email);
+        let mut node = |key: String| {
+            let id = nodes.intern(&key);
+            if id == parent.len() {
+                parent.push(id);
+            }
+            id
+        };
+        // A pair with neither name nor email is a person of its own
+        let name_node = node(if name.is_empty() {
+            format!("\0pair {}", i)
+        } else {
+            format!("name {}", name)
+        });
+        if !email.is_empty() {
+            let email_node = node(format!("email {}", email));
+            let (a, b) = (find(&mut parent, name_node), find(&mut parent, email_node));
+            parent[a] = b;
+        }
+        pair_nodes.push(name_node);
+    }
+
+    // Number the clusters in order of first appearance
+    let mut cluster_ids: HashMap<usize, usize> = HashMap::new();
+    pair_nodes
+        .into_iter()
+        .map(|node| {
+            let root = find(&mut parent, node);
+            let next = cluster_ids.len();
+            *cluster_ids.entry(root).or_insert(next)
+        })
+        .collect()
+}
+
+// How to match the author of a commit
+#[derive(Clone)]
+pub enum AuthorFilter {
+    // Any part of the name or email (like `git log --author`)
+    Pattern(String),
+    // The whole name or the whole email
+    Exact(String),
+    Name(String),
+    Email(String),
+}
+
+impl AuthorFilter {
+    // Git matches --author patterns against "Name <email>"
+    fn git_patterns(&self) -> Vec<String> {
+        match self {
+            AuthorFilter::Pattern(pattern) => vec![pattern.clone()],
+            AuthorFilter::Exact(s) => vec![
+                format!("^{} <", escape_git_regex(s)),
+                format!("<{}>$", escape_git_regex(s)),
+            ],
+            AuthorFilter::Name(name) => vec![format!("^{} <", escape_git_regex(name))],
+            AuthorFilter::Email(email) => vec![format!("<{}>$", escape_git_regex(email))],
+        }
+    }
+}
+
+// Arguments to restrict git's revision walk to commits by any of the given authors.  This is the
+// one place author filters are turned into git arguments, so that the log and statistics agree on
+// which commits an author made
+pub fn author_filter_args(filters: &[AuthorFilter]) -> Vec<String> {
+    filters
+        .iter()
+        .flat_map(|filter| filter.git_patterns())
+        .map(|pattern| format!("--author={}", pattern))
+        .collect()
+}
+
+// Filters for your own identities (see settings::SETTINGS), as given by --me
+pub fn own_identity_filters() -> Vec<AuthorFilter> {
+    SETTINGS
+        .identity
+        .iter()
+        .cloned()
+        .map(AuthorFilter::Exact)
+        .collect()
+}
+
+// The given author filters, or if there are none, filters for your own identities, for modes that
+// are about your own work
+pub fn own_author_filters(authors: &[AuthorFilter]) -> Vec<AuthorFilter> {
+    if authors.is_empty() {
+        own_identity_filters()
+    } else {
+        authors.to_vec()
+    }
+}
+
+// The form of an email address used to tell authors apart: lowercased, and without surrounding
+// whitespace, angle brackets, or quotes (some old or imported repositories have emails such as
+// "“Someone@Example.com”").  Applying it more than once changes nothing
+pub fn normalise_email(email: &str) -> String {
+    let lower = email.to_lowercase();
+    let mut email = lower.trim();
+    while let Some(inner) = ['<', '"', '\'', '“']
+        .iter()
+        .zip(['>', '"', '\'', '”'])
+        .find_map(|(&open, close)| email.strip_prefix(open)?.strip_suffix(close))
+    {
+        email = inner.trim();
+    }
+    email.to_string()
+}
+
+// Parse a line of `git shortlog --summary --email` output ("<commits>\t<name> <<email>>") into the
+// number of commits, name, and email.  The name and email may be empty
+pub fn parse_shortlog_line(line: &str) -> Option<(usize, &str, &str)> {
+    let line = line.trim_start();
+    let digits = line.find(|c: char| !c.is_ascii_digit())?;
+    let commits = line[..digits].parse().ok()?;
+    let rest = &line[digits..];
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let rest = rest.trim().strip_suffix('>')?;
+    let open = rest.rfind('<')?;
+    Some((commits, rest[..open].trim(), &rest[open + 1..]))
+}
+
+// Escape the characters that are special in git's (POSIX basic) regular expressions
+fn escape_git_regex(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | '.' | '[' | ']' | '*' | '^' | '$') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// A group of authors as written in the config file
+pub struct AuthorGroupConfig {
+    pub name: &'static str,
+    pub identities: &'static [&'static str],
+    pub patterns: &'static [&'static str],
+    pub colour: &'static str,
+}
+
+pub struct AuthorGroup {
+    pub name: String,
+    identities: Vec<String>,
+    patterns: Vec<Regex>,
+    pub colour: ThemeColour,
+}
+
+impl AuthorGroup {
+    fn new<S: AsRef<str>>(
+        name: &str,
+        identities: Vec<String>,
+        patterns: &[S],
+        colour: ThemeColour,
+    ) -> Self {
+        AuthorGroup {
+            name: name.to_string(),
+            identities,
+            patterns: compile_patterns(patterns, name),
+            colour,
+        }
+    }
+
+    fn from_config(group: &AuthorGroupConfig) -> Self {
+        AuthorGroup::new(
+            group.name,
+            group.identities.iter().map(|s| s.to_string()).collect(),
+            group.patterns,
+            ThemeColour::parse(group.colour),
+        )
+    }
+
+    fn matches(&self, name: &str, email: &str) -> bool {
+        self.matches_str(name) || self.matches_str(email)
+    }
+
+    // Whether a name or email is one of the group's identities or matches one of its patterns
+    fn matches_str(&self, s: &str) -> bool {
+        !s.is_empty()
+            && (self.identities.iter().any(|identity| identity == s)
+                || self.patterns.iter().any(|re| re.is_match(s)))
+    }
+}
+
+fn compile_patterns<S: AsRef<str>>(patterns: &[S], group_name: &str) -> Vec<Regex> {
+    patterns
+        .iter()
+        .filter_map(|pattern| match Regex::new(pattern.as_ref()) {
+            Ok(re) => Some(re),
+            Err(_) => {
+                eprintln!(
+                    "[WARN] Invalid pattern {:?} in author group {:?}; ignoring",
+                    pattern.as_ref(),
+                    group_name
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+// Find the first configured author group to which the given author belongs
+pub fn author_group(name: &str, email: &str) -> Option<&'static AuthorGroup> {
+    AUTHOR_GROUP_RULES
+        .iter()
+        .find(|group| group.matches(name, email))
+}
+
+impl GitIdentity {
+    // Whether this identity looks like an automation account (see config::BOT_PATTERNS)
+    pub fn is_bot(&self) -> bool {
+        let bots = AUTHOR_GROUP_RULES
+            .iter()
+            .find(|group| group.name == BOTS_GROUP)
+            .expect("The bots author group is always defined");
+        bots.matches_str(&self.email) || self.names.iter().any(|name| bots.matches_str(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arbitrary::{Gen, ANY, CASES};
+
+    #[test]
+    fn normalises_emails() {
+        assert_eq!(normalise_email("Ada@Example.com"), "ada@example.com");
+        assert_eq!(normalise_email(" “Zoë@Example.com” "), "zoë@example.com");
+        assert_eq!(normalise_email("<\"a@b\">"), "a@b");
+        assert_eq!(normalise_email("“"), "“");
+        assert_eq!(normalise_email(""), "");
+    }
+
+    #[test]
+    fn normalising_emails_is_idempotent() {
+        for seed in 0..CASES {
+            let email = normalise_email(&Gen::new(seed).string(30, ANY));
+            assert_eq!(normalise_email(&email), email, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn bots_are_highlighted_and_excluded_alike() {
+        let bot = GitIdentity {
+            email: String::from("49699333+dependabot[bot]@users.noreply.github.com"),
+            names: vec![String::from("dependabot[bot]")],
+        };
+        assert!(bot.is_bot());
+        assert_eq!(
+            author_group(&bot.names[0], &bot.email).map(|group| group.name.as_str()),
+            Some("bots")
+        );
+
+        // Bots are recognised by email alone, and by any of their names
+        let by_email = GitIdentity {
+            email: String::from("renovate@example.com"),
+            names: vec![],
+        };
+        assert!(by_email.is_bot());
+        let by_name = GitIdentity {
+            email: String::from("ci@example.com"),
+            names: vec![String::from("CI"), String::from("github-actions[bot]")],
+        };
+        assert!(by_name.is_bot());
+    }
+
+    #[test]
+    fn clusters_identities_through_shared_names_and_emails() {
+        let pairs = [
+            ("Ada Lovelace", "ada@home.org"),
+            ("Bob", "bob@example.com"),
+            ("ada lovelace", "ada@work.com"),
+            ("A. Lovelace", "ADA@work.com"),
+            ("", ""),
+            ("", ""),
+        ];
+        assert_eq!(cluster_identities(&pairs), vec![0, 1, 0, 0, 2, 3]);
+    }
+
+    #[test]
+    fn parses_shortlog_lines() {
+        assert_eq!(
+            parse_shortlog_line("     2\tAda Lovelace <ada@example.com>"),
+            Some((2, "Ada Lovelace", "ada@example.com"))
+        );
+        assert_eq!(
+            parse_shortlog_line("     1\tAnon <>"),
+            Some((1, "Anon", ""))
+        );
+        assert_eq!(parse_shortlog_line("     1\t <>"), Some((1, "", "")));
+        assert_eq!(
+            parse_shortlog_line("     1\tZoë <“zoe@example.com”>"),
+            Some((1, "Zoë", "“zoe@example.com”"))
+        );
+        assert_eq!(parse_shortlog_line("99999999999999999999999\tA <a>"), None);
+        assert_eq!(parse_shortlog_line("\tA <a>"), None);
+        assert_eq!(parse_shortlog_line("1\tA"), None);
+        assert_eq!(parse_shortlog_line(""), None);
+    }
+
+    #[test]
+    fn arbitrary_shortlog_lines_do_not_panic() {
+        for seed in 0..CASES {
+            let line = Gen::new(seed).string(60, ANY);
+            if let Some((_, name, email)) = parse_shortlog_line(&line) {
+                assert!(!email.contains('<'), "seed {}", seed);
+                assert_eq!(name, name.trim(), "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_shortlog_lines_round_trip() {
+        for seed in 0..CASES {
+            let mut gen = Gen::new(seed);
+            // Git removes angle brackets and newlines from names and emails
+            let name = gen.string_without(20, &['<', '>', '\n', '\r']);
+            let email = gen.string_without(20, &['<', '>', '\n', '\r']);
+            let commits = gen.below(100_000);
+            let line = format!("{:>6}\t{} <{}>", commits, name, email);
+            assert_eq!(
+                parse_shortlog_line(&line),
+                Some((commits, name.trim(), email.as_str())),
+                "seed {}",
+                seed
+            );
+        }
+    }
+}
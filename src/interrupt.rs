@@ -0,0 +1,46 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Whether the user has pressed Ctrl-C.  Like the phase timings, this is global so that we needn't
+// thread it through every subsystem: long-running loops check it between steps and stop early,
+// and whatever was accumulated so far is displayed as partial results
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+// The exit code conventionally used after SIGINT (128 + 2)
+pub const EXIT_CODE: i32 = 130;
+
+// Catch Ctrl-C so that long analyses can stop cleanly.  A second Ctrl-C exits immediately, in case
+// something isn't checking for the first
+#[cfg(unix)]
+pub fn install_handler() {
+    extern "C" fn handle_sigint(_: libc::c_int) {
+        // Only async-signal-safe operations are allowed here
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            unsafe { libc::_exit(EXIT_CODE) };
+        }
+    }
+
+    let handler: extern "C" fn(libc::c_int) = handle_sigint;
+    unsafe {
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn install_handler() {}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+// Mark output as incomplete if we were interrupted.  JSON output is left intact, so the warning
+// goes to stderr instead
+pub fn display_partial_banner(json: bool) {
+    if !interrupted() {
+        return;
+    }
+    if json {
+        eprintln!("[WARN] Interrupted; the results are partial");
+    } else {
+        println!("(partial)");
+    }
+}
@@ -1,16 +1,31 @@
+use super::commit::HashFormat;
+use super::context::Context;
+use super::interrupt;
 use super::opts::GitLogOptions;
+use super::progress::Progress;
 use super::repo;
+use super::settings::SETTINGS;
+use super::theme::THEME;
+use chrono::{Duration, NaiveDate};
 use colored::*;
 use colorsys::Rgb;
-use hyperpolyglot::{get_language_breakdown, Detection, Language};
+use hyperpolyglot::{detect, get_language_breakdown, Detection, Language};
+use ignore::WalkBuilder;
+use json::JsonValue;
+use std::cmp::{max, min};
 use std::collections::HashMap;
 use std::convert::TryFrom;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tabular::{Row, Table};
+use textplots::{Chart, ColorPlot, LabelBuilder, LabelFormat, Shape};
 
 pub struct LanguageSummary {
     language: Option<Language>,
     prevalence_percentage: f64,
     colour: Option<UnsignedRGB>,
+    file_count: usize,
+    line_count: Option<usize>,
 }
 
 pub struct UnsignedRGB {
@@ -19,67 +34,124 @@ pub struct UnsignedRGB {
     b: u8,
 }
 
-pub fn construct_language_summary() -> Vec<LanguageSummary> {
-    let top_level_path = repo::top_level_repo_path();
-
-    if let Some(top_level_path) = top_level_path {
+pub fn construct_language_summary(ctx: &Context, opts: &GitLogOptions) -> Vec<LanguageSummary> {
+    if let Some(top_level_path) = ctx.top_level_path() {
         let language_breakdown: HashMap<&'static str, Vec<(Detection, PathBuf)>> =
-            get_language_breakdown(top_level_path);
+            language_breakdown(top_level_path, opts);
+        summarise_language_breakdown(language_breakdown, opts)
+    } else {
+        // If there is no top-level path (i.e., we may not be in a git repo), return an empty vector,
+        // as we cannot determine any language information
+        // TODO: allow user to optionally input any directory, not just assume it's a git one
+        vec![]
+    }
+}
 
-        // https://github.com/monkslc/hyperpolyglot/blob/40f091679b94057ec925f7f8925e2960d1d9dbf2/src/bin/main.rs#L121-L133
-        let total_file_count = language_breakdown
-            .iter()
-            .fold(0, |acc, (_, files)| acc + files.len()) as f64;
-        let mut lang_summary: Vec<LanguageSummary> = Vec::new();
-        for (language, files) in language_breakdown {
-            // Get the prevalence of this language in the repo
-            let percentage = ((files.len() * 100) as f64) / total_file_count;
-
-            // Get the language from the database
-            let language_struct: Option<Language> = match Language::try_from(language) {
-                Ok(lang) => Some(lang),
-                Err(_) => None,
-            };
+fn summarise_language_breakdown(
+    language_breakdown: HashMap<&'static str, Vec<(Detection, PathBuf)>>,
+    opts: &GitLogOptions,
+) -> Vec<LanguageSummary> {
+    // https://github.com/monkslc/hyperpolyglot/blob/40f091679b94057ec925f7f8925e2960d1d9dbf2/src/bin/main.rs#L121-L133
+    let total_file_count = language_breakdown
+        .iter()
+        .fold(0, |acc, (_, files)| acc + files.len()) as f64;
+    let mut lang_summary: Vec<LanguageSummary> = Vec::new();
+    for (language, files) in language_breakdown {
+        // Get the prevalence of this language in the repo
+        let percentage = ((files.len() * 100) as f64) / total_file_count;
+
+        // Get the language from the database
+        let language_struct: Option<Language> = Language::try_from(language).ok();
 
-            // Get colour information for this language
-            let rgb: Option<UnsignedRGB> = match language_struct {
-                Some(lang) => {
-                    if let Some(lang_colour_str) = lang.color {
-                        let rgb = Rgb::from_hex_str(lang_colour_str).unwrap();
-                        Some(UnsignedRGB {
-                            r: rgb.red().round() as u8,
-                            g: rgb.green().round() as u8,
-                            b: rgb.blue().round() as u8,
-                        })
-                    } else {
-                        None
-                    }
+        // Get colour information for this language
+        let rgb: Option<UnsignedRGB> = match language_struct {
+            Some(lang) => {
+                if let Some(lang_colour_str) = lang.color {
+                    let rgb = Rgb::from_hex_str(lang_colour_str).unwrap();
+                    Some(UnsignedRGB {
+                        r: rgb.red().round() as u8,
+                        g: rgb.green().round() as u8,
+                        b: rgb.blue().round() as u8,
+                    })
+                } else {
+                    None
                 }
-                None => None,
-            };
+            }
+            None => None,
+        };
 
-            // Push our resulting summary data to the vector
-            lang_summary.push(LanguageSummary {
-                language: language_struct,
-                prevalence_percentage: percentage,
-                colour: rgb,
-            });
-        }
+        // Counting lines requires reading every file, so only do so if we need to display them
+        let line_count = if opts.detailed {
+            Some(files.iter().map(|(_, path)| count_lines(path)).sum())
+        } else {
+            None
+        };
 
-        // Sort by percentage (assuming our percentages are never NaN
-        lang_summary.sort_by(|a, b| {
-            b.prevalence_percentage
-                .partial_cmp(&a.prevalence_percentage)
-                .unwrap()
+        // Push our resulting summary data to the vector
+        lang_summary.push(LanguageSummary {
+            language: language_struct,
+            prevalence_percentage: percentage,
+            colour: rgb,
+            file_count: files.len(),
+            line_count,
         });
+    }
 
-        lang_summary
-    } else {
-        // If there is no top-level path (i.e., we may not be in a git repo), return an empty vector,
-        // as we cannot determine any language information
-        // TODO: allow user to optionally input any directory, not just assume it's a git one
-        vec![]
+    // Sort by percentage (assuming our percentages are never NaN
+    lang_summary.sort_by(|a, b| {
+        b.prevalence_percentage
+            .partial_cmp(&a.prevalence_percentage)
+            .unwrap()
+    });
+
+    lang_summary
+}
+
+pub fn language_breakdown(
+    top_level_path: &str,
+    opts: &GitLogOptions,
+) -> HashMap<&'static str, Vec<(Detection, PathBuf)>> {
+    if opts.include_ignored {
+        return language_breakdown_unfiltered(top_level_path);
     }
+
+    // Hyperpolyglot's walker skips some ignored files itself, but it doesn't necessarily agree with
+    // git (for example, on core.excludesFile), so we only keep the files that git itself considers
+    // tracked or trackable
+    let mut language_breakdown = get_language_breakdown(top_level_path);
+    if let Some(trackable_files) = repo::trackable_files(top_level_path) {
+        for files in language_breakdown.values_mut() {
+            files.retain(|(_, path)| trackable_files.contains(path));
+        }
+        language_breakdown.retain(|_, files| !files.is_empty());
+    }
+
+    language_breakdown
+}
+
+// Walk every (non-hidden) file in the repository, regardless of any ignore rules
+fn language_breakdown_unfiltered(
+    top_level_path: &str,
+) -> HashMap<&'static str, Vec<(Detection, PathBuf)>> {
+    let walker = WalkBuilder::new(top_level_path)
+        .standard_filters(false)
+        .hidden(true)
+        .build();
+
+    let mut language_breakdown = HashMap::new();
+    for entry in walker.flatten() {
+        let path = entry.into_path();
+        if !path.is_dir() {
+            if let Ok(Some(detection)) = detect(&path) {
+                language_breakdown
+                    .entry(detection.language())
+                    .or_insert_with(Vec::new)
+                    .push((detection, path));
+            }
+        }
+    }
+
+    language_breakdown
 }
 
 pub fn print_language_summary(
@@ -87,34 +159,425 @@ pub fn print_language_summary(
     languages_summary: Vec<LanguageSummary>,
     opts: &GitLogOptions,
 ) {
-    for language_summary in languages_summary.iter().take(top_n) {
-        // Check if the language was present in the database
-        if let Some(language) = language_summary.language {
-            if opts.colour {
-                if let Some(lang_colour) = &language_summary.colour {
-                    let language_summary_str = format!(
-                        "{:>6.2}%  {}",
-                        language_summary.prevalence_percentage, language.name
-                    )
-                    .truecolor(lang_colour.r, lang_colour.g, lang_colour.b);
-                    println!("{}", language_summary_str);
-                } else {
-                    println!(
-                        "{:>6.2}%  {}",
-                        language_summary.prevalence_percentage, language.name
-                    );
-                }
+    let languages_summary: Vec<&LanguageSummary> = languages_summary.iter().take(top_n).collect();
+
+    if opts.json {
+        let languages: Vec<JsonValue> = languages_summary.iter().map(|s| s.to_json()).collect();
+        println!("{}", JsonValue::Array(languages).pretty(2));
+        return;
+    }
+
+    // Compute column widths so that the bars line up
+    let mut name_width = languages_summary
+        .iter()
+        .map(|s| s.name().chars().count())
+        .max()
+        .unwrap_or(0);
+    if opts.detailed {
+        name_width = name_width.max("Language".len());
+    }
+    let file_count_width = languages_summary
+        .iter()
+        .map(|s| s.file_count.to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max("Files".len());
+    let line_count_width = languages_summary
+        .iter()
+        .map(|s| s.line_count.unwrap_or(0).to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max("Lines".len());
+
+    // The bar takes whatever horizontal space is left, within reason
+    let tsz = termsize::get().unwrap_or(termsize::Size { rows: 0, cols: 80 });
+    let mut used_width = 9 + name_width + 2;
+    if opts.detailed {
+        used_width += file_count_width + 2 + line_count_width + 2;
+    }
+    let bar_width = (tsz.cols as usize)
+        .saturating_sub(used_width)
+        .clamp(10, SETTINGS.language_bar_max_width);
+
+    if opts.detailed {
+        println!(
+            "{:>7}  {:<name_width$}  {:>file_count_width$}  {:>line_count_width$}",
+            "", "Language", "Files", "Lines",
+        );
+    }
+
+    for (rank, language_summary) in languages_summary.into_iter().enumerate() {
+        let mut summary_str = format!(
+            "{:>6.2}%  {:<name_width$}",
+            language_summary.prevalence_percentage,
+            language_summary.name(),
+        );
+        if opts.detailed {
+            summary_str.push_str(&format!(
+                "  {:>file_count_width$}  {:>line_count_width$}",
+                language_summary.file_count,
+                language_summary.line_count.unwrap_or(0),
+            ));
+        }
+        let bar = language_bar(language_summary.prevalence_percentage, bar_width);
+
+        // Check if the language was present in the database and has a colour
+        match (language_summary.display_colour(rank), opts.colour) {
+            (Some(lang_colour), true) => println!(
+                "{}  {}",
+                summary_str.truecolor(lang_colour.r, lang_colour.g, lang_colour.b),
+                bar.truecolor(lang_colour.r, lang_colour.g, lang_colour.b),
+            ),
+            _ => println!("{}  {}", summary_str, bar),
+        }
+    }
+}
+
+impl LanguageSummary {
+    fn to_json(&self) -> JsonValue {
+        json::object! {
+            language: self.name(),
+            percentage: self.prevalence_percentage,
+            files: self.file_count,
+            lines: self.line_count,
+            colour: self.colour.as_ref().map(|c| format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b)),
+        }
+    }
+
+    // The colour in which to show this language, which depends on the palette
+    fn display_colour(&self, rank: usize) -> Option<rgb::RGB8> {
+        let linguist_colour = self.colour.as_ref().map(|c| rgb::RGB8 {
+            r: c.r,
+            g: c.g,
+            b: c.b,
+        });
+        THEME.palette.language_colour(linguist_colour, rank)
+    }
+
+    fn name(&self) -> &str {
+        match self.language {
+            Some(language) => language.name,
+            None => "UNKNOWN LANGUAGE",
+        }
+    }
+}
+
+// A bar proportional to the language's share of the repository, using eighth-blocks for the
+// fractional remainder so that small languages still show up
+fn language_bar(percentage: f64, width: usize) -> String {
+    const PARTIAL_BLOCKS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+    let eighths = ((percentage / 100.0) * (width * 8) as f64).round() as usize;
+    let (whole, remainder) = (eighths / 8, eighths % 8);
+    let mut bar = "█".repeat(whole);
+    if remainder > 0 {
+        bar.push(PARTIAL_BLOCKS[remainder]);
+    }
+    if bar.is_empty() && percentage > 0.0 {
+        bar.push(PARTIAL_BLOCKS[1]);
+    }
+
+    bar
+}
+
+// Count the number of lines in a file (a final line without a trailing newline is still a line)
+fn count_lines(path: &PathBuf) -> usize {
+    match std::fs::read(path) {
+        Ok(bytes) => {
+            let newlines = bytes.iter().filter(|&&b| b == b'\n').count();
+            if bytes.last().is_some_and(|&b| b != b'\n') {
+                newlines + 1
             } else {
-                println!(
-                    "{:>6.2}%  {}",
-                    language_summary.prevalence_percentage, language.name
-                );
+                newlines
             }
-        } else {
-            println!(
-                "{:>6.2}%  UNKNOWN LANGUAGE",
-                language_summary.prevalence_percentage
-            );
         }
+        Err(_) => 0,
+    }
+}
+
+// Language history
+
+// How often to sample the repository: every n days, or at every tag
+#[derive(Clone)]
+pub enum LanguageHistorySampling {
+    Days(usize),
+    Tags,
+}
+
+impl LanguageHistorySampling {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        if input == "tags" {
+            return Ok(LanguageHistorySampling::Tags);
+        }
+        match input.parse::<usize>() {
+            Ok(days) => Ok(LanguageHistorySampling::Days(max(days, 1))),
+            Err(_) => Err(String::from("expected \"tags\" or a whole number of days")),
+        }
+    }
+}
+
+pub struct LanguageHistorySample {
+    date: NaiveDate,
+    rev: String,
+    summary: Vec<LanguageSummary>,
+}
+
+pub fn construct_language_history(
+    sampling: LanguageHistorySampling,
+    opts: &GitLogOptions,
+) -> Vec<LanguageHistorySample> {
+    let sample_revs = match sampling {
+        LanguageHistorySampling::Days(days) => sample_revs_by_days(days),
+        LanguageHistorySampling::Tags => sample_revs_by_tags(),
+    };
+
+    let mut history: Vec<LanguageHistorySample> = Vec::new();
+    let mut progress = Progress::new("Sampling languages", "samples", sample_revs.len());
+    for (date, rev) in sample_revs {
+        if interrupt::interrupted() {
+            break;
+        }
+        progress.inc(1);
+
+        // If nothing has been committed since the last sample, the tree will not have changed
+        if let Some(previous) = history.last() {
+            if previous.rev == rev {
+                continue;
+            }
+        }
+
+        // Extract the tree into a temporary directory rather than checking it out, so that we
+        // never touch the working tree (or index) of the repository
+        let tree_dir =
+            std::env::temp_dir().join(format!("gl-languages-{}-{}", std::process::id(), rev));
+        if extract_tree(&rev, &tree_dir) {
+            let language_breakdown = get_language_breakdown(&tree_dir);
+            history.push(LanguageHistorySample {
+                date,
+                rev,
+                summary: summarise_language_breakdown(language_breakdown, opts),
+            });
+        } else if !interrupt::interrupted() {
+            eprintln!("[WARN] Unable to extract tree for revision {}", rev);
+        }
+        let _ = std::fs::remove_dir_all(&tree_dir);
+    }
+    progress.finish();
+
+    history
+}
+
+pub fn print_language_history(history: Vec<LanguageHistorySample>, opts: &GitLogOptions) {
+    interrupt::display_partial_banner(opts.json);
+    if history.is_empty() {
+        println!("No history found to sample languages from.");
+        return;
+    }
+
+    // Only track the languages most prevalent in the latest sample, otherwise the chart is unreadable
+    let languages: Vec<&LanguageSummary> = history
+        .last()
+        .unwrap()
+        .summary
+        .iter()
+        .take(SETTINGS.language_history_top_n)
+        .collect();
+
+    // Tabulate the prevalence of each language at each sample
+    let mut table = Table::new(&format!(
+        "{{:<}}  {{:<}}{}",
+        "  {:>}".repeat(languages.len())
+    ));
+    let mut header = Row::new().with_cell("Date").with_cell("Commit");
+    for language in &languages {
+        header.add_cell(language.name());
+    }
+    table.add_row(header);
+    for sample in &history {
+        let mut row = Row::new()
+            .with_cell(sample.date)
+            .with_cell(sample.rev.short());
+        for language in &languages {
+            row.add_cell(format!("{:.2}%", sample.prevalence_of(language.name())));
+        }
+        table.add_row(row);
+    }
+    println!("{}", table);
+
+    // Chart the prevalence of each language over time (only meaningful with multiple samples)
+    if history.len() < 2 {
+        return;
+    }
+    let xstart = history[0].date;
+    let xmax = (history.last().unwrap().date - xstart).num_days();
+    let points: Vec<Vec<(f32, f32)>> = languages
+        .iter()
+        .map(|language| {
+            history
+                .iter()
+                .map(|sample| {
+                    (
+                        (sample.date - xstart).num_days() as f32,
+                        sample.prevalence_of(language.name()) as f32,
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    let shapes: Vec<Shape> = points.iter().map(|p| Shape::Lines(p)).collect();
+    let colours: Vec<rgb::RGB8> = languages
+        .iter()
+        .enumerate()
+        .map(
+            |(rank, language)| match (language.display_colour(rank), opts.colour) {
+                (Some(c), true) => c,
+                _ => rgb::RGB8 {
+                    r: 255,
+                    g: 255,
+                    b: 255,
+                },
+            },
+        )
+        .collect();
+
+    let tsz = termsize::get().unwrap_or(termsize::Size { rows: 0, cols: 0 });
+    let w: u32 = max(if tsz.cols == 0 { 40 } else { tsz.cols.into() }, 32);
+    let h: u32 = max(if tsz.rows == 0 { 60 } else { tsz.rows.into() }, 3);
+
+    let mut chart = Chart::new_with_y_range(w, h, 0.0, max(xmax, 1) as f32, 0.0, 100.0);
+    let mut chart_ref = &mut chart;
+    for (shape, colour) in shapes.iter().zip(colours) {
+        chart_ref = chart_ref.linecolorplot(shape, colour);
+    }
+    chart_ref
+        .x_label_format(LabelFormat::Custom(Box::new(move |val| {
+            format!("{}", xstart + Duration::days(val as i64))
+        })))
+        .y_label_format(LabelFormat::Custom(Box::new(move |val| {
+            format!("{}%", val as isize)
+        })))
+        .nice();
+}
+
+impl LanguageHistorySample {
+    fn prevalence_of(&self, name: &str) -> f64 {
+        self.summary
+            .iter()
+            .find(|s| s.name() == name)
+            .map(|s| s.prevalence_percentage)
+            .unwrap_or(0.0)
+    }
+}
+
+// Sample the latest commit (on or before each date) at regular intervals through history
+fn sample_revs_by_days(days: usize) -> Vec<(NaiveDate, String)> {
+    let mut cmd = Command::new("git");
+    cmd.arg("log");
+    cmd.arg("--first-parent");
+    cmd.arg("--format=%H %cs");
+    cmd.arg("HEAD");
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git log`");
+
+    if !output.status.success() {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return vec![];
+    }
+
+    // Commits are listed newest to oldest
+    let commits: Vec<(NaiveDate, String)> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (hash, date) = line.split_once(' ')?;
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+            Some((date, hash.to_string()))
+        })
+        .collect();
+    let (Some(first), Some(last)) = (commits.last(), commits.first()) else {
+        return vec![];
+    };
+
+    let mut samples = Vec::new();
+    let mut date = first.0;
+    loop {
+        let date_clamped = min(date, last.0);
+        if let Some((_, hash)) = commits.iter().find(|(d, _)| *d <= date_clamped) {
+            samples.push((date_clamped, hash.clone()));
+        }
+        if date >= last.0 {
+            break;
+        }
+        date += Duration::days(days as i64);
     }
+
+    samples
+}
+
+// Sample the repository at each tag, ordered by the date of the tagged commit
+fn sample_revs_by_tags() -> Vec<(NaiveDate, String)> {
+    let mut cmd = Command::new("git");
+    cmd.arg("for-each-ref");
+    cmd.arg("--format=%(objectname) %(committerdate:short) %(*objectname) %(*committerdate:short)");
+    cmd.arg("refs/tags");
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git for-each-ref`");
+
+    if !output.status.success() {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return vec![];
+    }
+
+    let mut samples: Vec<(NaiveDate, String)> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            // Annotated tags must be peeled to the commit they point to; lightweight tags
+            // already point to a commit, so their peeled fields are empty
+            let parts: Vec<&str> = line.split(' ').collect();
+            let (rev, date) = match parts.as_slice() {
+                [_, _, rev, date] if !rev.is_empty() => (rev, date),
+                [rev, date, _, _] => (rev, date),
+                _ => return None,
+            };
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+            Some((date, rev.to_string()))
+        })
+        .collect();
+    samples.sort();
+
+    samples
+}
+
+// Write the tree at the given revision into a directory using `git archive`
+fn extract_tree(rev: &str, dir: &Path) -> bool {
+    if std::fs::create_dir_all(dir).is_err() {
+        return false;
+    }
+
+    let mut archive = Command::new("git")
+        .arg("archive")
+        .arg("--format=tar")
+        .arg(rev)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to execute `git archive`");
+    let archive_stdout = archive.stdout.take().unwrap();
+
+    let extracted = Command::new("tar")
+        .arg("-x")
+        .arg("-C")
+        .arg(dir)
+        .stdin(archive_stdout)
+        .status()
+        .expect("Failed to execute `tar`");
+
+    let archived = archive.wait().expect("Failed to wait on `git archive`");
+
+    archived.success() && extracted.success()
 }
@@ -0,0 +1,192 @@
+use super::context::Context;
+use super::languages;
+use super::opts::GitLogOptions;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use tabular::{row, Table};
+
+// Types
+
+pub struct DirectoryLineCounts {
+    directory: String,
+    languages: Vec<LanguageLineCounts>,
+}
+
+struct LanguageLineCounts {
+    language: &'static str,
+    line_counts: LineCounts,
+}
+
+#[derive(Default, Clone, Copy)]
+struct LineCounts {
+    files: usize,
+    lines: usize,
+    blank: usize,
+}
+
+impl LineCounts {
+    fn code(&self) -> usize {
+        self.lines - self.blank
+    }
+
+    fn add(&mut self, other: &LineCounts) {
+        self.files += other.files;
+        self.lines += other.lines;
+        self.blank += other.blank;
+    }
+}
+
+impl DirectoryLineCounts {
+    fn total(&self) -> LineCounts {
+        let mut total = LineCounts::default();
+        for language in &self.languages {
+            total.add(&language.line_counts);
+        }
+        total
+    }
+}
+
+// Constructor methods
+
+// Count lines of code per top-level directory (relative to the given path), broken down by language
+pub fn construct_loc_summary(
+    path: &str,
+    ctx: &Context,
+    opts: &GitLogOptions,
+) -> Vec<DirectoryLineCounts> {
+    let Some(top_level_path) = ctx.top_level_path() else {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return vec![];
+    };
+    let root = match Path::new(path).canonicalize() {
+        Ok(root) => root,
+        Err(e) => {
+            eprintln!("[ERROR] Unable to resolve path {:?}: {e}", path);
+            return vec![];
+        }
+    };
+
+    // Re-use the language detection file walk, so that both modes agree on which files count
+    let language_breakdown = languages::language_breakdown(top_level_path, opts);
+    let mut counts: HashMap<String, HashMap<&'static str, LineCounts>> = HashMap::new();
+    for (language, files) in language_breakdown {
+        for (_, file) in files {
+            let Some(directory) = top_level_component(&file, &root) else {
+                continue;
+            };
+            counts
+                .entry(directory)
+                .or_default()
+                .entry(language)
+                .or_default()
+                .add(&line_counts(&file));
+        }
+    }
+
+    let mut summary: Vec<DirectoryLineCounts> = counts
+        .into_iter()
+        .map(|(directory, languages)| {
+            let mut languages: Vec<LanguageLineCounts> = languages
+                .into_iter()
+                .map(|(language, line_counts)| LanguageLineCounts {
+                    language,
+                    line_counts,
+                })
+                .collect();
+            languages.sort_by_key(|l| Reverse(l.line_counts.code()));
+            DirectoryLineCounts {
+                directory,
+                languages,
+            }
+        })
+        .collect();
+    summary.sort_by_key(|d| Reverse(d.total().code()));
+
+    summary
+}
+
+// The first path component of the file relative to the root, or "." for files directly in the root.
+// Returns None if the file is not inside the root
+fn top_level_component(file: &Path, root: &Path) -> Option<String> {
+    let file = file.canonicalize().ok()?;
+    let relative: PathBuf = file.strip_prefix(root).ok()?.to_path_buf();
+    let mut components = relative.components();
+    let first = components.next()?;
+    if components.next().is_none() {
+        return Some(String::from("."));
+    }
+    match first {
+        Component::Normal(dir) => Some(dir.to_string_lossy().into_owned()),
+        _ => None,
+    }
+}
+
+fn line_counts(path: &Path) -> LineCounts {
+    match std::fs::read(path) {
+        Ok(bytes) => {
+            let contents = String::from_utf8_lossy(&bytes);
+            let mut line_counts = LineCounts {
+                files: 1,
+                ..Default::default()
+            };
+            for line in contents.lines() {
+                line_counts.lines += 1;
+                if line.trim().is_empty() {
+                    line_counts.blank += 1;
+                }
+            }
+            line_counts
+        }
+        Err(_) => LineCounts::default(),
+    }
+}
+
+// Display methods
+
+pub fn print_loc_summary(summary: Vec<DirectoryLineCounts>) {
+    let mut table = Table::new("{:<}  {:<}  {:>}  {:>}  {:>}  {:>}").with_row(row!(
+        "Directory",
+        "Language",
+        "Files",
+        "Lines",
+        "Blank",
+        "Code"
+    ));
+
+    let mut total = LineCounts::default();
+    for directory in &summary {
+        let directory_total = directory.total();
+        total.add(&directory_total);
+
+        table.add_row(row!(
+            &directory.directory,
+            "",
+            directory_total.files,
+            directory_total.lines,
+            directory_total.blank,
+            directory_total.code(),
+        ));
+
+        for language in &directory.languages {
+            table.add_row(row!(
+                "",
+                language.language,
+                language.line_counts.files,
+                language.line_counts.lines,
+                language.line_counts.blank,
+                language.line_counts.code(),
+            ));
+        }
+    }
+    table.add_row(row!(
+        "Total",
+        "",
+        total.files,
+        total.lines,
+        total.blank,
+        total.code(),
+    ));
+
+    println!("{}", table);
+}
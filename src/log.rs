@@ -1,22 +1,115 @@
-use super::commit::{git_log, GitCommit};
-use super::config;
+use super::commit::{commit_bodies, git_log, GitCommit, HashFormat};
+use super::decoration::format_decorations;
+use super::diff;
+use super::identity;
+use super::notes;
 use super::opts::GitLogOptions;
+use super::repo;
+use super::theme::THEME;
+use super::timings;
+use chrono::{DateTime, Local};
 use colored::*;
+use lazy_static::lazy_static;
 use regex::Regex;
+use std::cmp::max;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
 
+// Don't bother fitting the message to the terminal if there is less room than this for it
+const MIN_MESSAGE_WIDTH: usize = 10;
+
+lazy_static! {
+    // Width of the terminal (or zero if we are not writing to a terminal)
+    static ref TERMINAL_WIDTH: usize = termsize::get().map(|s| s.cols as usize).unwrap_or(0);
+
+    // Patterns for the merged branch or pull request in common merge commit messages
+    static ref MERGE_SOURCE_RE: Regex = Regex::new(
+        r"(Merge (?:remote-tracking )?branch '(?P<branch>[^']+)'|Merge pull request (?P<pr>#\d+) from (?P<prbranch>\S+))"
+    )
+    .unwrap();
+
+    // The author at the end of a log line, e.g. "<Ada Lovelace>"
+    static ref AUTHOR_RE: Regex = Regex::new(r"<(?P<author>[^>]*)>").unwrap();
+}
+
+// What to show in the log: either the most recent n commits, or all commits in a revision range
+// (e.g. "v1.0..HEAD" or "main...feature")
+#[derive(Clone)]
+pub enum LogSelection {
+    Count(usize),
+    Range(String),
+}
+
+impl FromStr for LogSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains("..") {
+            Ok(LogSelection::Range(s.to_string()))
+        } else {
+            s.parse::<usize>().map(LogSelection::Count).map_err(|_| {
+                format!(
+                    "expected a number of commits or a revision range, but got {:?}",
+                    s
+                )
+            })
+        }
+    }
+}
+
+impl fmt::Display for LogSelection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogSelection::Count(n) => write!(f, "{}", n),
+            LogSelection::Range(range) => write!(f, "{}", range),
+        }
+    }
+}
+
+// Commits are formatted with the --grep patterns (see grep_regexes) to highlight in their messages,
+// which are compiled once for the whole log
 trait Format {
-    fn pretty(&self, opts: &GitLogOptions) -> String;
+    fn pretty(&self, grep_res: &[Regex], opts: &GitLogOptions) -> String;
+    fn pretty_aligned(
+        &self,
+        widths: &ColumnWidths,
+        grep_res: &[Regex],
+        opts: &GitLogOptions,
+    ) -> String;
+}
+
+// Widths of the fixed columns in the aligned log layout
+struct ColumnWidths {
+    hash: usize,
+    date: usize,
+    author: usize,
+}
+
+impl ColumnWidths {
+    fn new(logs: &[GitCommit]) -> Self {
+        let max_width = |f: &dyn Fn(&GitCommit) -> usize| logs.iter().map(f).max().unwrap_or(0);
+        ColumnWidths {
+            hash: max_width(&|log| log.hash.short().chars().count()),
+            date: max_width(&|log| log.date.repr.chars().count()),
+            author: max_width(&|log| log.author_name().chars().count()),
+        }
+    }
+}
+
+impl GitCommit {
+    fn author_name(&self) -> &str {
+        self.id.names.first().map(|s| s.as_str()).unwrap_or("")
+    }
 }
 
 impl Format for GitCommit {
-    fn pretty(&self, opts: &GitLogOptions) -> String {
-        let re_named = Regex::new(r"<(?P<author>[^>]*)>").unwrap();
-        let re = Regex::new(r"<([^>]*)>").unwrap();
+    fn pretty(&self, grep_res: &[Regex], opts: &GitLogOptions) -> String {
         // TODO: in future, instead of using raw, we can add colours ourself
         //   This would be extra beneficial as in some repos (for example, grafana), there are commits (for example, aba824a317) that have no author (%an), so we can use their name instead (at least, the first valid thing matching from identity---make an identity display function to find it)
         let log = &self.raw;
         let log: String = log.replace('\"', "");
-        let auth = re_named
+        let auth = AUTHOR_RE
             .captures(&log)
             .unwrap()
             .name("author") // using named groups
@@ -24,29 +117,495 @@ impl Format for GitCommit {
             .as_str()
             .to_string();
 
+        // Highlight the merged branch (or pull request) when viewing merges
+        let log = if opts.merges_only {
+            highlight_merge_source(&log, opts)
+        } else {
+            log
+        };
+
+        // Show the parents of the commit after its hash, marking merge commits
+        let log = if opts.parents {
+            insert_parents(&log, self, opts)
+        } else {
+            log
+        };
+
         // Need not colour author if colour not set
         // TODO: do I need to use more regex here?  Can I not replace the regex to just match with the author's name (which we already obtained)?
-        if opts.colour && config::ME_IDENTITY.contains(&auth.as_str()) {
-            re.replace(&log, |caps: &regex::Captures| {
-                format!(
-                    "{}{}{}{}",
-                    "".normal().white(), // need this to clear the current line of any colours
-                    "<".truecolor(192, 207, 227), // this is the light blue colour I have, defined by \e[0m\e[36m$&\e[39m\e[0m
-                    &caps[1].truecolor(192, 207, 227),
-                    ">".truecolor(192, 207, 227)
-                )
-            })
-            .to_string()
+        let group = identity::author_group(&auth, &self.id.email);
+        let log = match group {
+            Some(group) if opts.colour => AUTHOR_RE
+                .replace(&log, |caps: &regex::Captures| {
+                    format!(
+                        "{}{}{}{}",
+                        "".normal().white(), // need this to clear the current line of any colours
+                        group.colour.paint("<"),
+                        group.colour.paint(&caps["author"]),
+                        group.colour.paint(">")
+                    )
+                })
+                .to_string(),
+            _ => log.to_string(),
+        };
+
+        // Fit long messages to the width of the terminal
+        if opts.truncate || opts.wrap {
+            fit_message(&log, self, &auth, grep_res, opts)
+        } else {
+            highlight_message(&log, self, grep_res, opts)
+        }
+    }
+
+    // An alternative layout where the hash, date, and author are in fixed-width columns, followed
+    // by the refs and message, which fill the remaining width
+    fn pretty_aligned(
+        &self,
+        widths: &ColumnWidths,
+        grep_res: &[Regex],
+        opts: &GitLogOptions,
+    ) -> String {
+        let hash = format!("{:<w$}", self.hash.short(), w = widths.hash);
+        let date = format!("{:<w$}", self.date.repr, w = widths.date);
+        let author = format!("{:<w$}", self.author_name(), w = widths.author);
+        let refs = if self.decorations.is_empty() {
+            String::new()
+        } else {
+            format!("{} ", format_decorations(&self.decorations, false))
+        };
+        let message = self.message.trim_end();
+
+        // Fit the message in whatever is left of the terminal, if requested
+        let message_start = widths.hash + widths.date + widths.author + 6 + refs.chars().count();
+        let message_width = TERMINAL_WIDTH.saturating_sub(message_start);
+        let message = if *TERMINAL_WIDTH == 0 || message_width < MIN_MESSAGE_WIDTH {
+            message.to_string()
+        } else if opts.truncate {
+            ellipsize(message, message_width)
+        } else if opts.wrap {
+            wrap_words(message, message_width).join(&format!("\n{}", " ".repeat(message_start)))
+        } else {
+            message.to_string()
+        };
+
+        if opts.colour {
+            let message = highlight_matches(&message, grep_res, opts);
+            let author = match identity::author_group(self.author_name(), &self.id.email) {
+                Some(group) => group.colour.paint(&author),
+                None => THEME.author.paint(&author),
+            };
+            format!(
+                "{}  {}  {}  {}{}",
+                THEME.hash.paint(&hash),
+                THEME.date.paint(&date),
+                author,
+                if refs.is_empty() {
+                    refs
+                } else {
+                    format!("{} ", format_decorations(&self.decorations, true))
+                },
+                message
+            )
+        } else {
+            format!("{}  {}  {}  {}{}", hash, date, author, refs, message)
+        }
+    }
+}
+
+// Truncate or wrap the commit message so that the log line fits within the terminal, keeping the
+// hash, refs, date, and author intact
+fn fit_message(
+    log: &str,
+    commit: &GitCommit,
+    author: &str,
+    grep_res: &[Regex],
+    opts: &GitLogOptions,
+) -> String {
+    let width = *TERMINAL_WIDTH;
+    let visible_len = strip_ansi_escapes::strip_str(log).chars().count();
+    if width == 0 || visible_len <= width {
+        return log.to_string();
+    }
+
+    // The log line is always of the form "<hash> - <refs> <message> (<date>) <<author>>", so we can
+    // find where the message is from the length of what comes after it
+    let suffix_len = format!("({}) <{}>", commit.date.repr, author)
+        .chars()
+        .count();
+    let message = commit.message.trim_end();
+    let Some(message_start) = visible_len.checked_sub(suffix_len + commit.message.chars().count())
+    else {
+        return log.to_string();
+    };
+    let message_end = message_start + commit.message.chars().count();
+    let prefix = &log[..raw_byte_index(log, message_start)];
+    let suffix = &log[raw_byte_index(log, message_end)..];
+
+    // If the terminal is too narrow to fit anything sensible, leave the line as it is
+    let message_width = width.saturating_sub(message_start);
+    if message_width < MIN_MESSAGE_WIDTH {
+        return log.to_string();
+    }
+
+    if opts.truncate {
+        let available = message_width.saturating_sub(suffix_len + 1);
+        format!(
+            "{}{} {}",
+            prefix,
+            highlight_matches(&ellipsize(message, max(available, 1)), grep_res, opts),
+            suffix
+        )
+    } else {
+        // Hang-indent continuation lines so that they start in the same column as the message
+        let indent = " ".repeat(message_start);
+        let mut lines = wrap_words(message, message_width);
+        let last = lines.pop().unwrap_or_default();
+        let mut wrapped = String::from(prefix);
+        for line in lines {
+            wrapped.push_str(&highlight_matches(&line, grep_res, opts));
+            wrapped.push('\n');
+            wrapped.push_str(&indent);
+        }
+        wrapped.push_str(&highlight_matches(&last, grep_res, opts));
+        if last.chars().count() + 1 + suffix_len > message_width {
+            wrapped.push('\n');
+            wrapped.push_str(&indent);
+        } else {
+            wrapped.push(' ');
+        }
+        wrapped.push_str(suffix);
+        wrapped
+    }
+}
+
+// The byte index in a string (possibly containing ANSI escape sequences) immediately after the
+// first n visible characters
+fn raw_byte_index(raw: &str, n: usize) -> usize {
+    let mut visible = 0;
+    let mut chars = raw.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if visible == n {
+            return i;
+        }
+        if c == '\x1B' {
+            // Skip the escape sequence, up to and including its final byte
+            for (_, c) in chars.by_ref() {
+                if ('@'..='~').contains(&c) && c != '[' {
+                    break;
+                }
+            }
         } else {
-            log.to_string()
+            visible += 1;
         }
     }
+    raw.len()
+}
+
+fn ellipsize(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        s.to_string()
+    } else {
+        let mut truncated: String = s.chars().take(width - 1).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+// Greedily wrap text into lines no longer than the given width, splitting long words if necessary
+fn wrap_words(s: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in s.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        while word.len() > width {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            lines.push(word.drain(..width).collect());
+        }
+        let line_len = line.chars().count();
+        if !line.is_empty() && line_len + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.extend(word);
+    }
+    lines.push(line);
+    lines
+}
+
+fn insert_parents(log: &str, commit: &GitCommit, opts: &GitLogOptions) -> String {
+    let parents = commit.parents.join(" ");
+    let parents = if commit.parents.is_empty() {
+        // A root commit has no parents to list
+        String::from("[root]")
+    } else if commit.is_merge() {
+        let marker = if opts.colour {
+            "merge".bold().magenta().to_string()
+        } else {
+            String::from("merge")
+        };
+        format!("[{} {}]", marker, parents)
+    } else {
+        format!("[{}]", parents)
+    };
+    let parents = if opts.colour {
+        parents.dimmed().to_string()
+    } else {
+        parents
+    };
+
+    // The log line always starts with the (possibly coloured) hash, followed by a space
+    match log.split_once(' ') {
+        Some((hash, rest)) => format!("{} {} {}", hash, parents, rest),
+        None => format!("{} {}", log, parents),
+    }
+}
+
+fn highlight_merge_source(log: &str, opts: &GitLogOptions) -> String {
+    MERGE_SOURCE_RE
+        .replace(log, |caps: &regex::Captures| {
+            let whole = caps.get(0).unwrap().as_str();
+            let mut highlighted = whole.to_string();
+            for name in ["branch", "pr", "prbranch"] {
+                if let Some(m) = caps.name(name) {
+                    let coloured = if opts.colour {
+                        m.as_str().bold().magenta().to_string()
+                    } else {
+                        m.as_str().to_string()
+                    };
+                    highlighted = highlighted.replacen(m.as_str(), &coloured, 1);
+                }
+            }
+            highlighted
+        })
+        .to_string()
+}
+
+// The patterns given to --grep, for highlighting what they matched.  Git reads them as POSIX
+// regular expressions, which for everyday searches mean the same in Rust's syntax; any that are not
+// valid here are matched literally
+fn grep_regexes(opts: &GitLogOptions) -> Vec<Regex> {
+    opts.needles
+        .iter()
+        .filter_map(|needle| {
+            Regex::new(needle)
+                .or_else(|_| Regex::new(&regex::escape(needle)))
+                .ok()
+        })
+        .collect()
+}
+
+// Show the parts of the text matched by any of the patterns in reverse video
+fn highlight_matches(s: &str, res: &[Regex], opts: &GitLogOptions) -> String {
+    if !opts.colour || res.is_empty() {
+        return s.to_string();
+    }
+
+    let mut matches: Vec<(usize, usize)> = res
+        .iter()
+        .flat_map(|re| re.find_iter(s))
+        .map(|m| (m.start(), m.end()))
+        .collect();
+    matches.sort_unstable();
+
+    let mut highlighted = String::new();
+    let mut pos = 0;
+    for (start, end) in matches {
+        // Overlapping matches are highlighted as one
+        let start = max(start, pos);
+        if start >= end {
+            continue;
+        }
+        highlighted.push_str(&s[pos..start]);
+        highlighted.push_str(&s[start..end].reversed().to_string());
+        pos = end;
+    }
+    highlighted.push_str(&s[pos..]);
+    highlighted
+}
+
+// Highlight matches in the message of a log line, but not in its refs, date, or author.  The message
+// is the last occurrence of it before the date, as refs come before the message
+fn highlight_message(log: &str, commit: &GitCommit, res: &[Regex], opts: &GitLogOptions) -> String {
+    let message = commit.message.replace('"', "");
+    let date_start = log
+        .rfind(&format!("({})", commit.date.repr))
+        .unwrap_or(log.len());
+    match log[..date_start].rfind(&message) {
+        Some(i) if !message.is_empty() => format!(
+            "{}{}{}",
+            &log[..i],
+            highlight_matches(&message, res, opts),
+            &log[i + message.len()..]
+        ),
+        _ => log.to_string(),
+    }
+}
+
+// The lines of a commit's message body that matched --grep, beneath its log line
+fn display_grep_context(body: &str, res: &[Regex], opts: &GitLogOptions) {
+    for line in body
+        .lines()
+        .filter(|line| res.iter().any(|re| re.is_match(line)))
+    {
+        println!("    {}", highlight_matches(line.trim(), res, opts));
+    }
+}
+
+fn display_note(note: &str, opts: &GitLogOptions) {
+    for line in note.lines() {
+        let line = format!("    {}", line);
+        if opts.colour {
+            println!("{}", line.dimmed());
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+// Print the commit whose hash starts with the given prefix, or every such commit if the prefix is
+// ambiguous
+pub fn display_commits_by_prefix(prefix: &str, opts: &GitLogOptions) {
+    if prefix.len() < 4 || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        eprintln!(
+            "[ERROR] {:?} is not a hash prefix: expected at least four hexadecimal digits",
+            prefix
+        );
+        return;
+    }
+
+    let hashes = repo::commits_with_prefix(&prefix.to_lowercase());
+    if hashes.is_empty() {
+        println!("No commits found with a hash starting with {}.", prefix);
+        return;
+    }
+    if hashes.len() > 1 {
+        println!(
+            "{} is ambiguous; {} commits have a hash starting with it:",
+            prefix,
+            hashes.len()
+        );
+    }
+
+    // Show each commit as the log would, on its own
+    for hash in hashes {
+        let opts = GitLogOptions {
+            rev: Some(hash),
+            ..opts.clone()
+        };
+        display_git_log(1, &opts);
+    }
 }
 
 pub fn display_git_log(n: usize, opts: &GitLogOptions) {
     let logs: Vec<GitCommit> = git_log(Some(n), Some(opts));
 
-    for log in logs {
-        println!("{}", log.pretty(opts));
+    let notes = if opts.show_notes {
+        notes::git_notes()
+    } else {
+        HashMap::new()
+    };
+
+    let bodies = if opts.grep_context {
+        let hashes: Vec<&str> = logs.iter().map(|log| log.hash.as_str()).collect();
+        commit_bodies(&hashes)
+    } else {
+        HashMap::new()
+    };
+    let grep_res = grep_regexes(opts);
+
+    let widths = ColumnWidths::new(&logs);
+    let summary = opts.stat_summary.then(|| {
+        timings::time("line statistics (git log --numstat)", || {
+            StatSummary::new(&logs, opts)
+        })
+    });
+
+    timings::time("formatting", || {
+        for log in logs {
+            if opts.aligned {
+                println!("{}", log.pretty_aligned(&widths, &grep_res, opts));
+            } else {
+                println!("{}", log.pretty(&grep_res, opts));
+            }
+            if let Some(body) = bodies.get(&log.hash) {
+                display_grep_context(body, &grep_res, opts);
+            }
+            if let Some(note) = notes.get(&log.hash) {
+                display_note(note, opts);
+            }
+        }
+    });
+
+    if let Some(summary) = summary {
+        println!();
+        if opts.colour {
+            println!("{}", summary.to_string().bold());
+        } else {
+            println!("{}", summary);
+        }
+    }
+}
+
+// Totals for the commits shown in the log, so that a filtered log doubles as a report
+struct StatSummary {
+    commits: usize,
+    authors: usize,
+    added: usize,
+    deleted: usize,
+    // Oldest and newest commit dates
+    span: Option<(DateTime<Local>, DateTime<Local>)>,
+}
+
+impl StatSummary {
+    fn new(logs: &[GitCommit], opts: &GitLogOptions) -> Self {
+        let hashes: Vec<&str> = logs.iter().map(|log| log.hash.as_str()).collect();
+        let line_counts = diff::commit_line_counts(&hashes, opts);
+        let authors: HashSet<String> = logs
+            .iter()
+            .map(|log| match identity::normalise_email(&log.id.email) {
+                email if email.is_empty() => log.id.names.join(", "),
+                email => email,
+            })
+            .collect();
+        let dates = logs.iter().map(|log| log.date.abs);
+        StatSummary {
+            commits: logs.len(),
+            authors: authors.len(),
+            added: line_counts.values().map(|(added, _)| added).sum(),
+            deleted: line_counts.values().map(|(_, deleted)| deleted).sum(),
+            span: dates.clone().min().zip(dates.max()),
+        }
+    }
+}
+
+impl fmt::Display for StatSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        write!(
+            f,
+            "{} commit{} by {} author{}, +{} -{} lines",
+            self.commits,
+            plural(self.commits),
+            self.authors,
+            plural(self.authors),
+            self.added,
+            self.deleted
+        )?;
+        if let Some((first, last)) = self.span {
+            let days = (last.date_naive() - first.date_naive()).num_days() as usize;
+            write!(
+                f,
+                ", {} to {} ({} day{})",
+                first.format("%Y-%m-%d"),
+                last.format("%Y-%m-%d"),
+                days,
+                plural(days)
+            )?;
+        }
+        Ok(())
     }
 }
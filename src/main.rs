@@ -1,16 +1,57 @@
 use clap::{crate_version, ArgAction, Args, Parser};
+use identity::AuthorFilter;
 
+mod analysis;
+#[cfg(test)]
+mod arbitrary;
+mod audit;
+mod blame;
 mod branch;
+mod cache;
+mod chart;
 mod commit;
+mod commit_stats;
+mod compare;
 mod config;
+mod context;
 mod contributions;
 mod count;
+mod dates;
+mod decoration;
+mod diff;
+mod email_domains;
+mod file_stats;
+mod forge;
+mod goal;
+mod hooks;
 mod identity;
+mod interrupt;
 mod languages;
+mod loc;
 mod log;
+mod notes;
 mod opts;
+mod patch;
+mod patchid;
+mod pathspec;
+mod pool;
+mod progress;
+mod prompt;
+mod prs;
+mod recover;
+mod registry;
+mod remote;
 mod repo;
+mod review;
+mod settings;
+mod standup;
 mod status;
+mod syntax;
+mod tags;
+mod theme;
+mod timings;
+mod topics;
+mod trailers;
 
 // TODO list (delete help commands as I go)
 // -i | --issues        Prints currently open issues in present repository.
@@ -59,110 +100,821 @@ struct Cli {
     )]
     all: bool,
 
+    /// Follow only the first parent of merge commits (like `git log --first-parent`)
+    ///
+    /// Applies to the log, commit counts, and contribution statistics
+    #[arg(
+        long = "first-parent",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    first_parent: bool,
+
+    /// Walks the log from the tip of every branch and tag, not just HEAD (or the given revision), to
+    /// include side branches that have not been merged
+    ///
+    /// Applies to the log and commit counts
+    #[arg(
+        long = "all-refs",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    all_refs: bool,
+
+    /// Display only merge commits, highlighting the branch or pull request that was merged
+    #[arg(
+        long = "merges-only",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    merges_only: bool,
+
+    /// Display abbreviated parent hashes for each commit, marking merge commits
+    #[arg(
+        long = "parents",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    parents: bool,
+
+    /// Display any git notes attached to commits beneath each log line
+    #[arg(
+        long = "show-notes",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    show_notes: bool,
+
+    /// Truncate long commit messages to fit the terminal width
+    #[arg(
+        long = "truncate",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+        conflicts_with = "wrap",
+    )]
+    truncate: bool,
+
+    /// Wrap long commit messages to fit the terminal width, indenting continuation lines
+    #[arg(
+        long = "wrap",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+        conflicts_with = "truncate",
+    )]
+    wrap: bool,
+
+    /// Display the log with the hash, date, and author in aligned columns
+    #[arg(
+        long = "aligned",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    aligned: bool,
+
+    /// Display author commit counts in the same format as `git shortlog -sne`
+    ///
+    /// Use with -A/--author-commit-counts
+    #[arg(
+        long = "shortlog",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+        requires = "author_commit_counts",
+    )]
+    shortlog: bool,
+
+    /// Count commits by when they were authored rather than committed
+    ///
+    /// Use with -c/--commit-count or -C/--commit-count-at, so that rebased or amended commits are not counted as new
+    #[arg(
+        long = "author-date",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    author_date: bool,
+
+    /// Break down commit counts by author
+    ///
+    /// Use with -c/--commit-count, -C/--commit-count-at, or --count
+    #[arg(
+        long = "by-author",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    by_author: bool,
+
+    /// Fetch pull request titles, authors, merge dates, and review counts from the forge
+    ///
+    /// Use with --prs.  Requires GITHUB_TOKEN, GITLAB_TOKEN, or GITEA_TOKEN to be set (depending on
+    /// where the origin remote is hosted); responses are cached
+    #[arg(
+        long = "remote",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+        requires = "prs",
+    )]
+    remote: bool,
+
+    /// Display output as JSON (where supported)
+    #[arg(
+        long = "json",
+        visible_alias = "porcelain",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    json: bool,
+
+    /// Report how long each phase took to stderr
+    #[arg(
+        long = "timings",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    timings: bool,
+
+    /// Start the log from a branch, tag, or commit other than HEAD
+    ///
+    /// Also applies to commit counts and contribution statistics
+    #[arg(long = "ref", action = ArgAction::Set, num_args = 1, value_name = "rev")]
+    rev: Option<String>,
+
+    /// Only include commits made after a tag (e.g. "who contributed since v2.0?")
+    ///
+    /// Applies to the log, commit counts, and contribution statistics.  Combines with --ref, to
+    /// look at the commits on another branch since the tag
+    #[arg(long = "since-tag", action = ArgAction::Set, num_args = 1, value_name = "tag")]
+    since_tag: Option<String>,
+
     /// Filter log for specified commit author(s)
+    ///
+    /// Matches any part of the author's name or email; see also --author-exact, --author-name, and --author-email.  Also applies to contribution statistics
     #[arg(
         long = "author",
         action = ArgAction::Append,
         num_args = 1..=std::usize::MAX,
     )]
-    authors: Vec<String>,
+    authors: Vec<String>,
+
+    /// Filter log for commits whose author's whole name or whole email is exactly as given
+    #[arg(
+        long = "author-exact",
+        action = ArgAction::Append,
+        num_args = 1..=std::usize::MAX,
+    )]
+    authors_exact: Vec<String>,
+
+    /// Filter log for commits whose author's name is exactly as given
+    #[arg(
+        long = "author-name",
+        action = ArgAction::Append,
+        num_args = 1..=std::usize::MAX,
+    )]
+    author_names: Vec<String>,
+
+    /// Filter log for commits whose author's email is exactly as given
+    #[arg(
+        long = "author-email",
+        action = ArgAction::Append,
+        num_args = 1..=std::usize::MAX,
+    )]
+    author_emails: Vec<String>,
+
+    /// Filter log for your own commits, by any of your identities (see GL_IDENTITY).  Like
+    /// --author, this also applies to commit counts and contribution statistics
+    #[arg(
+        long = "me",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    me: bool,
+
+    /// Filter log for commit messages matching text
+    #[arg(
+        long = "grep",
+        action = ArgAction::Append,
+        num_args = 1..=std::usize::MAX,
+    )]
+    grep: Vec<String>,
+
+    /// Show the lines of each commit's message body that matched --grep beneath its log line, to
+    /// see why a commit matched
+    #[arg(
+        long = "only-matching-context",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        requires = "grep",
+        default_value_t = false,
+    )]
+    only_matching_context: bool,
+
+    /// After the log, print the number of commits shown, their authors, the lines they added and
+    /// deleted, and the dates they span
+    #[arg(
+        long = "stat-summary",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    stat_summary: bool,
+
+    /// Show progress towards a commit goal with -c, e.g. "--goal 5 week", overriding the goal in
+    /// settings.  The period is "day", "week" (the default), or "month"
+    ///
+    /// Counts your own commits (see GL_IDENTITY), or those of the authors given with --author etc.
+    #[arg(
+        long = "goal",
+        action = ArgAction::Set,
+        num_args = 1..=2,
+        value_names = ["n", "period"],
+        requires = "commit_count",
+    )]
+    goal: Vec<String>,
+
+    /// Runs in the given repository under your base directory (see --list-repos), from anywhere.
+    /// Without a name, prints the name of the current repository
+    #[arg(
+        short = 'r',
+        long = "repo",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "name",
+    )]
+    repo: Option<Option<String>>,
+
+    /// Work across every repository under your base directory (see GL_BASE_DIR), rather than the
+    /// current one
+    ///
+    /// Applies to --standup, -A, and -S
+    #[arg(
+        long = "global",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    global: bool,
+
+    /// Print the patch of the commit after the statistics from --stats-of
+    #[arg(
+        long = "patch",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        requires = "stats_of",
+        default_value_t = false,
+    )]
+    patch: bool,
+
+    /// Show changes in patches word by word, marking removed and added words inline, rather than as
+    /// removed and added lines
+    ///
+    /// Applies to --show and --patch
+    #[arg(
+        long = "word-diff",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    word_diff: bool,
+
+    /// Exclude commits by bots and automation accounts (e.g. dependabot, renovate)
+    ///
+    /// Applies to the log and contribution statistics
+    #[arg(
+        long = "no-bots",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    no_bots: bool,
+
+    /// Only consider commits more recent than a specific date
+    ///
+    /// Accepts any date understood by git (e.g. "2023-01-01" or "1 year ago"), as well as periods such as "2w" or "3m", and "this week"/"this month"/"this year"
+    #[arg(long = "since", action = ArgAction::Set, num_args = 1, value_name = "date")]
+    since: Option<String>,
+
+    /// Only consider commits older than a specific date
+    ///
+    /// Accepts the same formats as --since
+    #[arg(long = "until", action = ArgAction::Set, num_args = 1, value_name = "date")]
+    until: Option<String>,
+
+    /// Include files ignored by git in the language breakdown
+    #[arg(
+        long = "include-ignored",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    include_ignored: bool,
+
+    /// Include file and line counts in the language breakdown
+    #[arg(
+        long = "detailed",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    detailed: bool,
+
+    /// Use a logarithmic y-axis for the contribution graph
+    #[arg(
+        long = "log-scale",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    log_scale: bool,
+
+    /// Plot the total number of commits to date in the contribution graph, rather than daily counts
+    #[arg(
+        long = "cumulative",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    cumulative: bool,
+
+    /// Plot a moving average over the given number of days in the contribution graph
+    #[arg(
+        long = "smooth",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "window",
+    )]
+    smooth: Option<usize>,
+
+    /// Mark tags, and milestones given in settings, with vertical lines on the contribution graph
+    #[arg(
+        long = "markers",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    markers: bool,
+
+    /// Show the refs pointing at each commit in the log with "short" names, "full" names, or "no"
+    /// refs at all
+    #[arg(
+        long = "decorate",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "style",
+        value_enum,
+        default_value = "short",
+    )]
+    decorate: decoration::DecorateStyle,
+
+    /// Hide refs matching a glob from the log, e.g. "remotes" or "tags/dependabot-*"
+    ///
+    /// Globs are matched against full ref names, with "refs/" added if missing.  Can be given
+    /// multiple times
+    #[arg(
+        long = "exclude-refs",
+        action = ArgAction::Append,
+        num_args = 1,
+        value_name = "glob",
+    )]
+    exclude_refs: Vec<String>,
+
+    /// Colour palette: "default", "colourblind", or "monochrome"
+    ///
+    /// The colourblind palette is used for the log, charts, languages, and status
+    #[arg(
+        long = "palette",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "name",
+        value_enum,
+    )]
+    palette: Option<theme::Palette>,
+
+    /// Draw charts with "braille", "block", or "ascii" characters
+    ///
+    /// Use "block" or "ascii" if your font renders Braille poorly
+    #[arg(
+        long = "chart-style",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "style",
+        value_enum,
+        default_value = "braille",
+    )]
+    chart_style: chart::ChartStyle,
+
+    /// Width of charts in characters (defaults to half the width of the terminal)
+    #[arg(
+        long = "width",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "columns",
+    )]
+    chart_width: Option<usize>,
+
+    /// Height of charts in characters (defaults to a quarter of the height of the terminal)
+    #[arg(
+        long = "height",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "rows",
+    )]
+    chart_height: Option<usize>,
+
+    /// Add a sparkline beneath tables of counts
+    ///
+    /// Use with --seasonality
+    #[arg(
+        long = "sparkline",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    sparkline: bool,
+
+    /// Continuously refresh the git status, checking for changes every n seconds
+    ///
+    /// Use with -s/--status.  Defaults to checking every 2 seconds
+    #[arg(
+        long = "watch",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "seconds",
+        default_missing_value = "2",
+        requires = "status",
+    )]
+    watch: Option<u64>,
+
+    /// Sort the tag listing by "date", "version", or "name"
+    ///
+    /// Use with --tags.  Version sorting handles prefixes such as "v" and orders pre-releases
+    /// (e.g. "1.0.0-rc.1") before their release
+    #[arg(
+        long = "sort",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "key",
+        value_enum,
+        default_value = "date",
+        requires = "tags",
+    )]
+    sort: tags::TagSort,
+
+    /// Only list tags matching a glob, e.g. "v2.*"
+    ///
+    /// Use with --tags
+    #[arg(
+        long = "match",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "glob",
+        requires = "tags",
+    )]
+    tag_match: Option<String>,
+
+    /// Exclude paths matching a glob from the log and contribution statistics
+    ///
+    /// Can be given multiple times, e.g. --exclude "*.lock" --exclude "vendor/**"
+    #[arg(
+        long = "exclude",
+        action = ArgAction::Append,
+        num_args = 1,
+        value_name = "glob",
+    )]
+    excludes: Vec<String>,
+
+    /// Ignore changes in whitespace when counting lines added and deleted, so that reformatting
+    /// does not dwarf other work in the statistics
+    #[arg(
+        long = "ignore-whitespace",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    ignore_whitespace: bool,
+
+    /// Count generated files, such as lockfiles and minified code, in line statistics
+    ///
+    /// These are left out by default; the globs used to recognise them can be set in the config
+    #[arg(
+        long = "include-generated",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    include_generated: bool,
+
+    /// Only show commits that add and delete at least n lines in total, e.g. to find big landings
+    #[arg(
+        long = "min-changes",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "n lines",
+    )]
+    min_changes: Option<usize>,
+
+    /// Only show commits that add and delete at most n lines in total, e.g. to find small fixups
+    #[arg(
+        long = "max-changes",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "n lines",
+    )]
+    max_changes: Option<usize>,
+
+    /// In a partial clone, don't fetch missing objects from the remote.  Git stops at the first
+    /// commit whose line statistics would need one, so statistics leave out it and every older
+    /// commit
+    ///
+    /// Requires git 2.45 or later
+    #[arg(
+        long = "no-fetch",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    no_fetch: bool,
+
+    /// Fetches from the default remote first, so that how far ahead or behind branches are is up to
+    /// date.  If the remote can't be reached in time (see GL_FETCH_TIMEOUT), the last fetched state
+    /// is shown
+    ///
+    /// Applies to -s, --branch-sync, and --prompt
+    #[arg(
+        long = "fetch",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+        conflicts_with = "no_fetch",
+    )]
+    fetch: bool,
+
+    #[clap(flatten)]
+    group: Group,
+}
+
+// We only want to allow one functional check at a time.  The following group,
+// which is flattened in the main Cli struct, should provide such functionality
+//
+//   https://stackoverflow.com/a/76315811
+#[derive(Args)]
+#[group(multiple = false)]
+pub struct Group {
+    /// Given a number, will print the last n commits nicely; given a revision range, will print all commits in that range
+    ///
+    /// By default, the programme will print the last 10 commits.  Can use with --rev to show least recent logs first.  Can also use --all to show all logs.  Ranges may be two-dot (e.g. v1.0..HEAD, for commits in HEAD but not v1.0) or three-dot (e.g. main...feature, for commits in either but not both)
+    #[arg(
+        // TODO: as well as -n, we should also be able to do -10, -100, -3, etc
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "n commits|range",
+        default_value_t = log::LogSelection::Count(settings::SETTINGS.default_n),
+    )]
+    log_number: log::LogSelection,
+
+    /// Prints language breakdown in present repository
+    ///
+    /// Will print only top n languages if given value (optional).  Defaults to displaying all languages (you can also specify n = 0 for this behaviour)
+    #[arg(
+        short = 'l',
+        long = "languages",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "n languages",
+        default_missing_value = "0",
+    )]
+    languages: Option<usize>,
+
+    /// Prints how the language breakdown has changed over the history of the repository
+    ///
+    /// Samples the repository every n days (defaults to 90), or at every tag if given "tags"
+    #[arg(
+        long = "languages-history",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "days|tags",
+        value_parser = languages::LanguageHistorySampling::parse,
+        default_missing_value = "90",
+    )]
+    languages_history: Option<languages::LanguageHistorySampling>,
+
+    /// Prints a summary of lines of code per top-level directory and language
+    ///
+    /// Defaults to the current directory, but you can specify a directory
+    #[arg(
+        long = "loc",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "path",
+        default_missing_value = ".",
+    )]
+    loc: Option<String>,
+
+    /// Prints current git status minimally
+    ///
+    /// Defaults to the current directory, but you can specify a directory
+    #[arg(
+        short = 's',
+        long = "status",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "dir",
+        default_missing_value = ".",
+    )]
+    status: Option<String>,
+
+    /// Prints the current branch name
+    #[arg(
+        short = 'b',
+        long = "branch",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    branch: bool,
+
+    /// Prints all local branches in the current repository
+    #[arg(
+        short = 'B',
+        long = "branches",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    local_branches: bool,
+
+    /// Print all remote branches of the current repository
+    #[arg(
+        short = 'R',
+        long = "remotes",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    remote_branches: bool,
+
+    /// Prints every local branch with its upstream and how far ahead or behind it is
+    ///
+    /// Branches whose upstream has been deleted are marked as "gone"
+    #[arg(
+        long = "branch-sync",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    branch_sync: bool,
+
+    /// Prints when each local branch diverged from the default branch, who started it, and how
+    /// many commits it has that the default branch does not
+    #[arg(
+        long = "branch-age",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    branch_age: bool,
+
+    /// Checks whether HEAD has already been pushed, exiting with an error if so
+    ///
+    /// Intended for use before amending a commit, e.g. in an alias: `gl --amend-check && git commit
+    /// --amend`
+    #[arg(
+        long = "amend-check",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    amend_check: bool,
 
-    /// Filter log for commit messages matching text
+    /// Compares protected branches (by default main, master, trunk, and release/*; see the config)
+    /// with their remotes, warning about any that have diverged, which would need a force push
+    ///
+    /// Give a glob to check other branches.  Exits with an error if any branch has diverged, so
+    /// that it can be used before pushing.  Fetch first to compare with the latest remote
     #[arg(
-        long = "grep",
-        action = ArgAction::Append,
-        num_args = 1..=std::usize::MAX,
+        long = "protected",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "glob",
     )]
-    grep: Vec<String>,
-
-    #[clap(flatten)]
-    group: Group,
-}
+    protected: Option<Option<String>>,
 
-// We only want to allow one functional check at a time.  The following group,
-// which is flattened in the main Cli struct, should provide such functionality
-//
-//   https://stackoverflow.com/a/76315811
-#[derive(Args)]
-#[group(multiple = false)]
-pub struct Group {
-    /// Given a number, will print the last n commits nicely
+    /// Installs a git hook in the current repository: "commit-count" (keeps today's commit count
+    /// in .git/gl-commit-count, for shell prompts) or "amend-check" (stops amending pushed
+    /// commits)
     ///
-    /// By default, the programme will print the last 10 commits.  Can use with --rev to show least recent logs first.  Can also use --all to show all logs
+    /// Hooks written by gl can be removed with --uninstall-hook
     #[arg(
-        // TODO: as well as -n, we should also be able to do -10, -100, -3, etc
+        long = "install-hook",
         action = ArgAction::Set,
         num_args = 1,
-        value_name = "n commits",
-        default_value_t = config::DEFAULT_TOP_N_LOG,
+        value_name = "kind",
+        value_enum,
     )]
-    log_number: usize,
+    install_hook: Option<hooks::HookKind>,
 
-    /// Prints language breakdown in present repository
+    /// Removes a git hook installed with --install-hook
+    #[arg(
+        long = "uninstall-hook",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "kind",
+        value_enum,
+    )]
+    uninstall_hook: Option<hooks::HookKind>,
+
+    /// Prints a one-line summary of the repository for shell prompts: the branch, how far ahead
+    /// (⇡) or behind (⇣) its upstream it is, and counts of conflicted (=), staged (+), modified
+    /// (!), and untracked (?) files and stashes ($)
     ///
-    /// Will print only top n languages if given value (optional).  Defaults to displaying all languages (you can also specify n = 0 for this behaviour)
+    /// Give "bash" or "zsh" to escape the output for the shell's prompt; the default, "plain", suits
+    /// starship custom commands and the like.  In bash, set PS1 from PROMPT_COMMAND, e.g.
+    /// PROMPT_COMMAND='PS1="$(gl --prompt bash) \$ "', and in zsh, use PROMPT_SUBST, e.g.
+    /// setopt PROMPT_SUBST; PROMPT='$(gl --prompt zsh) %# '.  This never walks the history, so it is
+    /// fast even in large repositories
     #[arg(
-        short = 'l',
-        long = "languages",
+        long = "prompt",
         action = ArgAction::Set,
         num_args = 0..=1,
-        value_name = "n languages",
-        default_missing_value = "0",
+        value_name = "shell",
+        value_enum,
+        default_missing_value = "plain",
     )]
-    languages: Option<usize>,
+    prompt: Option<prompt::PromptShell>,
 
-    /// Prints current git status minimally
+    /// Prints the commit whose hash starts with the given prefix, or lists every matching commit if
+    /// the prefix is ambiguous
     ///
-    /// Defaults to the current directory, but you can specify a directory
+    /// Unreachable commits (e.g. from before a rebase) are found too
     #[arg(
-        short = 's',
-        long = "status",
+        long = "find-hash",
         action = ArgAction::Set,
-        num_args = 0..=1,
-        value_name = "dir",
-        default_missing_value = ".",
+        num_args = 1,
+        value_name = "prefix",
     )]
-    status: Option<String>,
+    find_hash: Option<String>,
 
-    /// Prints the current branch name
+    /// Searches the reflog and unreferenced commits for commits whose message contains the given
+    /// text, to help recover lost work
     #[arg(
-        short = 'b',
-        long = "branch",
+        long = "find-lost",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "text",
+    )]
+    find_lost: Option<String>,
+
+    /// Lists commits that no branch or tag can reach (e.g. left behind by a rebase, an amend, or
+    /// deleting a branch), with their age, so that they can be recovered before git deletes them
+    #[arg(
+        long = "dangling",
         action = ArgAction::SetTrue,
         num_args = 0,
         default_value_t = false,
     )]
-    branch: bool,
+    dangling: bool,
 
-    /// Prints all local branches in the current repository
+    /// Prints all tags in the current repository, with the date and commit they point to
+    ///
+    /// Annotated tags also show their tagger, the date they were tagged, and whether they are signed
     #[arg(
-        short = 'B',
-        long = "branches",
+        long = "tags",
         action = ArgAction::SetTrue,
         num_args = 0,
         default_value_t = false,
     )]
-    local_branches: bool,
+    tags: bool,
 
-    /// Print all remote branches of the current repository
+    /// Writes the commit-graph file to speed up history traversal, if it does not already exist
     #[arg(
-        short = 'R',
-        long = "remotes",
+        long = "write-commit-graph",
         action = ArgAction::SetTrue,
         num_args = 0,
         default_value_t = false,
     )]
-    remote_branches: bool,
+    write_commit_graph: bool,
 
-    /// Prints the name of the current repository
+    /// Lists the repositories under your base directory (see GL_BASE_DIR) and their current
+    /// branches, for use with --repo
     #[arg(
-        short = 'r',
-        long = "repo",
+        long = "list-repos",
         action = ArgAction::SetTrue,
         num_args = 0,
         default_value_t = false,
     )]
-    repo_name: bool,
+    list_repos: bool,
 
     /// Counts the current number of commits on working branch on the current day
     #[arg(
@@ -175,9 +927,9 @@ pub struct Group {
     )]
     commit_count: bool,
 
-    /// Counts the number of commits for a specified day, or all time
+    /// Counts the number of commits for a specified day or period, or all time
     ///
-    /// Given value "today" (see also -c), "yesterday", or some number of days ago.  If no value is given, it will default to all time (you can also specify C = total for this behaviour)
+    /// Given value "today" (see also -c), "yesterday", some number of days ago, a period with units (e.g. "2w", "3m", "1.5y"), or "this week"/"this month"/"this year".  If no value is given, it will default to all time (you can also specify C = total for this behaviour)
     #[arg(
         // TODO:
         //   If you give it 2 numbers, it will show the number of commits since the first number but before the second number (days ago).
@@ -225,6 +977,273 @@ pub struct Group {
     )]
     contrib_graph: bool,
 
+    /// Display the number of distinct authors who committed in each month as a graph, to show the
+    /// size of the community over time (rather than how much it committed)
+    ///
+    /// Use --markers to mark tags and milestones
+    #[arg(
+        long = "authors-graph",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    authors_graph: bool,
+
+    /// Prints a contributors list (e.g. for CONTRIBUTORS.md) in "markdown" or "text" format
+    ///
+    /// Defaults to Markdown.  The commit threshold and line templates are set in the config
+    #[arg(
+        long = "generate-contributors",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "format",
+        value_enum,
+        default_missing_value = "markdown",
+    )]
+    generate_contributors: Option<contributions::ContributorsFormat>,
+
+    /// Displays two authors' contribution statistics side by side
+    ///
+    /// Authors are matched as with --author (i.e., against either name or email)
+    #[arg(
+        long = "compare",
+        action = ArgAction::Set,
+        num_args = 2,
+        value_names = ["author1", "author2"],
+    )]
+    compare: Option<Vec<String>>,
+
+    /// Compares activity between two time windows, e.g. "6 months ago..3 months ago" "3 months ago.."
+    ///
+    /// Each range is of the form <since>..<until>, where either end may be omitted, and dates may
+    /// be given in any format understood by git
+    #[arg(
+        long = "compare-periods",
+        action = ArgAction::Set,
+        num_args = 2,
+        value_names = ["range1", "range2"],
+        value_parser = compare::DateRange::parse,
+    )]
+    compare_periods: Option<Vec<compare::DateRange>>,
+
+    /// Lists changes that were applied to both of two refs as separate commits, e.g. fixes
+    /// cherry-picked from main to a release branch
+    ///
+    /// Commits are matched by patch ID, so the same change matches even if it was applied to
+    /// different lines or with different whitespace
+    #[arg(
+        long = "duplicates",
+        action = ArgAction::Set,
+        num_args = 2,
+        value_names = ["ref1", "ref2"],
+    )]
+    duplicates: Option<Vec<String>>,
+
+    /// Prints the n most common terms in commit subjects (defaults to 20), as a rough view of what
+    /// has been worked on
+    ///
+    /// Conventional commit prefixes and common words are ignored.  Use --since and --until to
+    /// choose the window
+    #[arg(
+        long = "topics",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "n",
+        default_missing_value = "20",
+    )]
+    topics: Option<usize>,
+
+    /// Lists the pull requests that landed, from merge commits and squashed commits ending in
+    /// "(#1234)" (or "(!1234)" if the origin remote is on GitLab)
+    ///
+    /// Use --ref to choose a range (e.g. --ref v1.0..HEAD), or --since and --until.  Links to the
+    /// pull requests are shown if the origin remote is on GitHub, GitLab, or Gitea
+    #[arg(
+        long = "prs",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    prs: bool,
+
+    /// Prints a draft of release notes in Markdown for the pull requests that landed in a range
+    /// (e.g. v1.0..v1.1), grouped into features, fixes, and other changes
+    ///
+    /// Pull requests are grouped by their conventional commit prefix, or with --remote, by their
+    /// labels on the forge (which also gives authors' usernames)
+    #[arg(
+        long = "release-notes",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "from..to",
+    )]
+    release_notes: Option<String>,
+
+    /// Prints the web address of the current branch on GitHub, GitLab, or Gitea
+    ///
+    /// Use --ref to link to another branch.  Self-hosted forges can be set in the config
+    #[arg(
+        long = "browse",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    browse: bool,
+
+    /// Displays how many distinct authors have committed in the last n days, and in total
+    #[arg(
+        long = "count-authors",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "n days",
+    )]
+    count_authors: Option<usize>,
+
+    /// Lists authors who have committed under more than one email domain (e.g. moving from a
+    /// personal address to a work one), with when they used each, to help maintain a .mailmap
+    ///
+    /// Authors are grouped by shared names and emails
+    #[arg(
+        long = "email-domains",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    email_domains: bool,
+
+    /// Prints your commits since the last working day (Friday, on a Monday), grouped by repository
+    /// and branch, to paste into standup notes
+    ///
+    /// Given an author, prints their commits instead.  Use --global for all of your repositories
+    #[arg(
+        long = "standup",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "author",
+    )]
+    standup: Option<Option<String>>,
+
+    /// Displays every commit that changed a file, with lines added and deleted
+    #[arg(
+        long = "file-stats",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "path",
+    )]
+    file_stats: Option<String>,
+
+    /// Summarises who last changed the lines of a file: each author's share of the lines, and the
+    /// dates of their oldest and newest lines
+    ///
+    /// Use --ref to look at the file as of another revision
+    #[arg(
+        long = "blame-summary",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "path",
+    )]
+    blame_summary: Option<String>,
+
+    /// Prints a histogram of how old the lines of a file are (by the year they were last changed),
+    /// and the commits that wrote the most surviving lines, to judge how stable the file is
+    ///
+    /// Use --ref to look at the file as of another revision
+    #[arg(
+        long = "blame-age",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "path",
+    )]
+    blame_age: Option<String>,
+
+    /// Prints how long ago the code in each top-level directory was last changed, to find stale
+    /// areas of the repository
+    ///
+    /// Lines are dated by the last commit to touch their file
+    #[arg(
+        long = "code-age",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    code_age: bool,
+
+    /// Prints a tree of directories (down to n levels, defaulting to 2) with the number of commits
+    /// that touched each, to show where development is concentrated
+    ///
+    /// Use --since and --until to choose the window
+    #[arg(
+        long = "dir-activity",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "depth",
+        default_missing_value = "2",
+    )]
+    dir_activity: Option<usize>,
+
+    /// Prints, for each month, how long commits took to land after they were authored (median,
+    /// 75th, and 90th percentiles), as a proxy for review turnaround
+    ///
+    /// Commits count if they were brought in by a merge on the first-parent history, or have a
+    /// Reviewed-by or Approved-by trailer
+    #[arg(
+        long = "review-latency",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    review_latency: bool,
+
+    /// Prints the number of commits in each month of the year and on each day of the month,
+    /// summed over all years, to show release rhythms and holiday slumps
+    ///
+    /// Use --author-date to count by when commits were written, and --sparkline for a compact view
+    #[arg(
+        long = "seasonality",
+        action = ArgAction::SetTrue,
+        num_args = 0,
+        default_value_t = false,
+    )]
+    seasonality: bool,
+
+    /// Flags commits whose committer date is more than n days (defaults to 30) from their author
+    /// date, and first-parent commits committed before their parent
+    ///
+    /// Useful when auditing imported or rewritten history.  Use --ref to audit another branch or
+    /// range
+    #[arg(
+        long = "audit-dates",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        value_name = "n days",
+        default_missing_value = "30",
+    )]
+    audit_dates: Option<i64>,
+
+    /// Prints statistics for a single commit: lines changed, grouped by directory, its
+    /// conventional commit type and trailers, and how many branches and tags contain it
+    ///
+    /// Merges are compared with their first parent
+    #[arg(
+        long = "stats-of",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "rev",
+    )]
+    stats_of: Option<String>,
+
+    /// Prints a commit's message and patch, highlighting the part of each changed line that
+    /// changed
+    ///
+    /// Merges are compared with their first parent
+    #[arg(
+        long = "show",
+        action = ArgAction::Set,
+        num_args = 1,
+        value_name = "rev",
+    )]
+    show: Option<String>,
+
     /// Display count of commits
     ///
     /// See also -C/--commit-count-at
@@ -238,40 +1257,244 @@ pub struct Group {
     count: bool,
 }
 
+// Exit with an error if a revision, or either end of a range, does not exist.  Each end of a range
+// may be omitted, in which case git uses HEAD
+fn check_revisions(rev: &str) {
+    for endpoint in rev.split("..").map(|s| s.trim_start_matches('.')) {
+        if !endpoint.is_empty() && !repo::rev_exists(endpoint) {
+            eprintln!("[ERROR] Unknown revision {:?}", endpoint);
+            std::process::exit(1);
+        }
+    }
+}
+
 fn main() {
+    let start = std::time::Instant::now();
     let cli = Cli::parse();
-    let opts = opts::GitLogOptions {
+
+    // Move to the repository given with --repo before anything (including settings) reads from the
+    // current one
+    if let Some(Some(name)) = &cli.repo {
+        let Some(path) = registry::find(name) else {
+            eprintln!(
+                "[ERROR] No repository named {:?} under {}; see --list-repos",
+                name,
+                settings::base_dir().display()
+            );
+            std::process::exit(1);
+        };
+        if let Err(e) = std::env::set_current_dir(&path) {
+            eprintln!("[ERROR] Unable to change to {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+
+    // The palette must be chosen before anything is coloured
+    if let Some(palette) = cli.palette {
+        theme::set_palette(palette);
+    }
+    if cli.me && settings::SETTINGS.identity.is_empty() {
+        eprintln!(
+            "[WARN] --me has no effect as you have no identities; set GL_IDENTITY or user.email"
+        );
+    }
+    let report_timings = cli.timings;
+    let mut opts = opts::GitLogOptions {
         relative: !cli.absolute,
+        stat_summary: cli.stat_summary,
 
         // https://no-color.org
-        colour: !(std::env::var("NO_COLOR").is_ok() || std::env::var("NO_COLOUR").is_ok()),
+        colour: !settings::SETTINGS.no_colour,
         reverse: cli.reverse,
         all: cli.all,
+        json: cli.json,
+        first_parent: cli.first_parent,
+        all_refs: cli.all_refs,
+        merges_only: cli.merges_only,
+        parents: cli.parents,
+        show_notes: cli.show_notes,
+        truncate: cli.truncate,
+        wrap: cli.wrap,
+        aligned: cli.aligned,
+        shortlog: cli.shortlog,
+        author_date: cli.author_date,
+        by_author: cli.by_author,
+        remote: cli.remote,
+
+        // Language detection
+        include_ignored: cli.include_ignored,
+        detailed: cli.detailed,
+
+        // Contribution graph
+        log_scale: cli.log_scale,
+        markers: cli.markers,
+        cumulative: cli.cumulative,
+        smooth: cli.smooth,
+
+        // Refs shown alongside commits in the log
+        decorate: cli.decorate,
+        exclude_refs: cli.exclude_refs,
+
+        // Charts
+        chart_style: cli.chart_style,
+        chart_width: cli.chart_width,
+        chart_height: cli.chart_height,
+        sparkline: cli.sparkline,
+
+        // Patches
+        word_diff: cli.word_diff,
 
         // Filters
-        authors: cli.authors,
+        rev: cli.rev,
+        authors: cli
+            .authors
+            .into_iter()
+            .map(AuthorFilter::Pattern)
+            .chain(cli.authors_exact.into_iter().map(AuthorFilter::Exact))
+            .chain(cli.author_names.into_iter().map(AuthorFilter::Name))
+            .chain(cli.author_emails.into_iter().map(AuthorFilter::Email))
+            .chain(if cli.me {
+                identity::own_identity_filters()
+            } else {
+                Vec::new()
+            })
+            .collect(),
         needles: cli.grep,
+        grep_context: cli.only_matching_context,
+        no_bots: cli.no_bots,
+        since: cli.since,
+        until: cli.until,
+        excludes: settings::SETTINGS
+            .excludes
+            .iter()
+            .cloned()
+            .chain(cli.excludes)
+            .collect(),
+        ignore_whitespace: cli.ignore_whitespace,
+        include_generated: cli.include_generated,
+        min_changes: cli.min_changes,
+        max_changes: cli.max_changes,
+        no_fetch: cli.no_fetch,
     };
 
+    // Every git command we run inherits this, so that none of them fetch objects lazily
+    if opts.no_fetch {
+        std::env::set_var("GIT_NO_LAZY_FETCH", "1");
+    }
+
+    // A revision range given in place of the number of commits replaces the starting revision, and
+    // shows every commit in the range
+    if let log::LogSelection::Range(range) = &cli.group.log_number {
+        opts.rev = Some(range.clone());
+        opts.all = true;
+    }
+
+    // Commits since a tag are the range from the tag to the starting revision
+    if let Some(tag) = &cli.since_tag {
+        if !repo::rev_exists(&format!("refs/tags/{}", tag)) {
+            eprintln!("[ERROR] Unknown tag {:?}", tag);
+            std::process::exit(1);
+        }
+        if opts.rev.as_deref().is_some_and(|rev| rev.contains("..")) {
+            eprintln!("[ERROR] --since-tag cannot be combined with a range");
+            std::process::exit(1);
+        }
+        let rev = opts.rev.as_deref().unwrap_or("HEAD");
+        opts.rev = Some(format!("{}..{}", tag, rev));
+        opts.all = true;
+    }
+
+    if let Some(rev) = &opts.rev {
+        check_revisions(rev);
+    }
+
+    // Repository details are looked up at most once, by whichever mode needs them
+    let ctx = context::Context::default();
+
+    // Line statistics need the contents of every changed file, which a partial clone may have to
+    // fetch one at a time, and the working tree of a sparse checkout is missing some files
+    let needs_diffs = cli.group.author_contrib_stats
+        || cli.group.compare.is_some()
+        || cli.group.compare_periods.is_some()
+        || cli.group.duplicates.is_some()
+        || cli.group.file_stats.is_some()
+        || cli.group.blame_summary.is_some()
+        || cli.group.blame_age.is_some()
+        || cli.group.stats_of.is_some()
+        || cli.group.show.is_some()
+        || opts.stat_summary
+        || opts.min_changes.is_some()
+        || opts.max_changes.is_some();
+    if needs_diffs && !opts.no_fetch {
+        if let Some(partial_clone) = ctx.partial_clone() {
+            eprintln!(
+                "[WARN] This repository is a partial clone{}, so this may fetch many objects from {:?}.  Use --no-fetch to stop at the first commit whose objects are missing instead",
+                partial_clone
+                    .filter
+                    .as_ref()
+                    .map(|filter| format!(" (filter {:?})", filter))
+                    .unwrap_or_default(),
+                partial_clone.remote
+            );
+        }
+    }
+    // Long analyses stop cleanly on Ctrl-C and show what they have so far; anything else is quick
+    // enough to be killed as usual
+    if cli.group.author_commit_counts
+        || cli.group.author_contrib_stats
+        || cli.group.contrib_graph
+        || cli.group.authors_graph
+        || cli.group.languages_history.is_some()
+    {
+        interrupt::install_handler();
+    }
+
+    // Only reports on upstreams are worth the wait for the network
+    if cli.fetch {
+        if cli.group.status.is_some() || cli.group.branch_sync || cli.group.prompt.is_some() {
+            remote::fetch(std::time::Duration::from_secs(
+                settings::SETTINGS.fetch_timeout,
+            ));
+        } else {
+            eprintln!("[WARN] --fetch only applies to -s, --branch-sync, and --prompt");
+        }
+    }
+
+    let reads_working_tree = cli.group.languages.is_some() || cli.group.loc.is_some();
+    if reads_working_tree && repo::is_sparse_checkout() {
+        eprintln!("[WARN] This is a sparse checkout, so only the files checked out are counted");
+    }
+
     // Because all of these options are in a group, at most one branch should
     // ever be matched, so it is safe to put this in an if-else chain
     if let Some(n) = cli.group.languages {
         // This parses _and_ prints the language output
-        let language_summary = languages::construct_language_summary();
+        let language_summary = languages::construct_language_summary(&ctx, &opts);
         // If no argument was provided, it will print all languages
         let top_n = if n == 0 { language_summary.len() } else { n };
         languages::print_language_summary(top_n, language_summary, &opts);
+    } else if let Some(sampling) = cli.group.languages_history {
+        // Sample the language breakdown over time
+        let language_history = languages::construct_language_history(sampling, &opts);
+        languages::print_language_history(language_history, &opts);
+    } else if let Some(path) = cli.group.loc {
+        // Count lines of code per directory
+        let loc_summary = loc::construct_loc_summary(&path, &ctx, &opts);
+        loc::print_loc_summary(loc_summary);
     } else if cli.group.status.is_some() {
         // Show status of git repo
-        status::get_git_status(&cli.group.status, &opts);
+        if let Some(interval) = cli.watch {
+            status::watch_git_status(&cli.group.status, interval, &opts);
+        } else {
+            status::get_git_status(&cli.group.status, &opts);
+        }
     // } else if cli.group.global_status {
     //     // Show statuses of predefined git repos (not yet implemented)
     //     todo!()
     //     // status::global_status(&opts);
     } else if cli.group.branch {
         // Show current branch name
-        let current_branch = branch::current_branch();
-        if let Some(current_branch) = current_branch {
+        if let Some(current_branch) = ctx.current_branch() {
             println!("{}", current_branch);
         }
     } else if cli.group.local_branches {
@@ -280,41 +1503,241 @@ fn main() {
     } else if cli.group.remote_branches {
         // Show remote branches
         branch::get_branch_names(branch::BranchListings::Remotes, &opts);
-    } else if cli.group.repo_name {
+    } else if cli.group.branch_sync {
+        // Show upstream sync state of local branches
+        branch::display_branch_sync(&opts);
+    } else if cli.group.branch_age {
+        // Show how old each branch is
+        branch::display_branch_ages(&ctx, &opts);
+    } else if cli.group.amend_check {
+        // Refuse to amend published commits
+        if !branch::amend_check() {
+            std::process::exit(1);
+        }
+    } else if let Some(glob) = cli.group.protected {
+        // Check for rewritten history on protected branches
+        let patterns = match glob {
+            Some(glob) => vec![glob],
+            None => settings::SETTINGS.protected_branches.clone(),
+        };
+        if !branch::display_protected_branches(&patterns, &opts) {
+            std::process::exit(1);
+        }
+    } else if let Some(shell) = cli.group.prompt {
+        // Summarise the repository for a shell prompt
+        prompt::display_prompt(shell, &opts);
+    } else if let Some(kind) = cli.group.install_hook {
+        // Install a git hook
+        hooks::install_hook(kind);
+    } else if let Some(kind) = cli.group.uninstall_hook {
+        // Remove a git hook
+        hooks::uninstall_hook(kind);
+    } else if let Some(prefix) = cli.group.find_hash {
+        // Resolve an abbreviated hash
+        log::display_commits_by_prefix(&prefix, &opts);
+    } else if let Some(text) = cli.group.find_lost {
+        // Search for lost commits
+        let commits = recover::find_lost_commits(&text);
+        recover::display_lost_commits(commits, &opts);
+    } else if cli.group.dangling {
+        // Show commits that no ref can reach
+        let commits = recover::dangling_commits();
+        recover::display_dangling_commits(commits, &opts);
+    } else if cli.group.tags {
+        // Show tags
+        let tags = tags::tags(cli.tag_match.as_deref(), &cli.sort);
+        tags::display_tags(tags, &opts);
+    } else if cli.group.write_commit_graph {
+        // Generate the commit-graph for faster walks
+        repo::write_commit_graph();
+    } else if cli.group.list_repos {
+        // Show the repositories under the base directory, updating the registry
+        let repos = registry::scan();
+        registry::display_registered_repos(repos, &opts);
+    } else if cli.repo == Some(None) {
         // Show the current repository
-        let current_repo = repo::current_repository();
-        if let Some(current_repo) = current_repo {
+        if let Some(current_repo) = ctx.repo_name() {
             println!("{}", current_repo);
         }
     } else if cli.group.commit_count {
         // Show commit count
-        count::get_commit_count("today", &opts);
+        let goal = if cli.goal.is_empty() {
+            goal::configured_goal()
+        } else {
+            let goal =
+                goal::CommitGoal::from_parts(&cli.goal[0], cli.goal.get(1).map(String::as_str));
+            if goal.is_none() {
+                eprintln!("[ERROR] Invalid goal {:?}; expected a number of commits, and optionally \"day\", \"week\", or \"month\"", cli.goal.join(" "));
+                std::process::exit(1);
+            }
+            goal
+        };
+        count::get_commit_count("today", &ctx, &opts);
+        if let Some(goal) = goal {
+            goal::display_goal_progress(&goal, &opts);
+        }
     } else if cli.group.count {
         // Equivalent to -C without arguments (i.e., commit_count_at = total)
-        count::get_commit_count_total(&opts);
+        count::get_commit_count_total(&ctx, &opts);
     } else if let Some(commit_count_at) = cli.group.commit_count_at {
         // Show commit count for a  specific time
         if commit_count_at == "total" {
-            count::get_commit_count_total(&opts);
+            count::get_commit_count_total(&ctx, &opts);
         } else {
-            count::get_commit_count(&commit_count_at, &opts);
+            count::get_commit_count(&commit_count_at, &ctx, &opts);
         }
     } else if cli.group.author_commit_counts
         || cli.group.author_contrib_stats
         || cli.group.contrib_graph
     {
         // Handle different contributor stats options
-        let contributors = contributions::git_contributors();
-        if cli.group.author_commit_counts {
-            contributions::display_git_author_frequency(contributors.clone());
-        } else if cli.group.author_contrib_stats {
-            // Show contribution stats per author, sorted by lines added + deleted
-            contributions::display_git_contributions_per_author(contributors.clone());
-        } else if cli.group.contrib_graph {
-            // Show contributions graph
-            contributions::display_git_contributions_graph(contributors.clone());
+        if cli.global && !cli.group.contrib_graph {
+            // Aggregate contributor stats across all of your repositories
+            let global = contributions::global_contributors(&opts, cli.group.author_contrib_stats);
+            contributions::display_global_contributors(
+                &global,
+                cli.group.author_contrib_stats,
+                &opts,
+            );
+        } else if cli.group.author_commit_counts && opts.shortlog {
+            // Shortlog counts each name and email separately, so doesn't need the contributors
+            contributions::display_git_shortlog(&opts);
+        } else {
+            let contributors =
+                contributions::git_contributors(&opts, cli.group.author_contrib_stats);
+            if cli.group.author_commit_counts {
+                contributions::display_git_author_frequency(&contributors, &opts);
+            } else if cli.group.author_contrib_stats {
+                // Show contribution stats per author, sorted by lines added + deleted
+                contributions::display_git_contributions_per_author(&contributors);
+            } else if cli.group.contrib_graph {
+                // Show contributions graph
+                contributions::display_git_contributions_graph(&contributors, &opts);
+            }
+        }
+    } else if cli.group.authors_graph {
+        // Show the number of authors over time
+        contributions::display_authors_graph(&opts);
+    } else if let Some(format) = cli.group.generate_contributors {
+        // Generate a list of contributors
+        contributions::generate_contributors_file(format, &opts);
+    } else if let Some(authors) = cli.group.compare {
+        // Compare two authors' contributions
+        compare::compare_authors(&authors[0], &authors[1], &opts);
+    } else if let Some(ranges) = cli.group.compare_periods {
+        // Compare activity between two time windows
+        compare::compare_periods(&ranges[0], &ranges[1], &opts);
+    } else if let Some(refs) = cli.group.duplicates {
+        // Find cherry-picked changes
+        patchid::display_duplicate_commits(&refs[0], &refs[1], &opts);
+    } else if let Some(top_n) = cli.group.topics {
+        // Show the most common terms in commit subjects
+        topics::display_topics(top_n, &opts);
+    } else if cli.group.prs {
+        // List landed pull requests
+        let mut prs = prs::pull_requests(&opts);
+        if opts.remote {
+            prs::fetch_pull_request_details(&mut prs);
+        }
+        prs::display_pull_requests(prs, &opts);
+    } else if let Some(range) = cli.group.release_notes {
+        // Draft release notes from the pull requests in a range
+        if !range.contains("..") {
+            eprintln!(
+                "[ERROR] Expected a range (e.g. v1.0..v1.1), but got {:?}",
+                range
+            );
+            std::process::exit(1);
+        }
+        check_revisions(&range);
+        let opts = opts::GitLogOptions {
+            rev: Some(range.clone()),
+            ..opts
+        };
+        let mut prs = prs::pull_requests(&opts);
+        if opts.remote {
+            prs::fetch_pull_request_details(&mut prs);
+        }
+        prs::display_release_notes(&range, prs, &opts);
+    } else if cli.group.browse {
+        // Link to the repository on the web
+        forge::display_browse_url(&ctx, &opts);
+    } else if let Some(days) = cli.group.count_authors {
+        // Show the number of active authors
+        contributions::display_active_author_count(days, &opts);
+    } else if let Some(author) = cli.group.standup {
+        // Summarise recent work
+        let standup = standup::standup(author.as_deref(), cli.global, ctx.repo_name(), &opts);
+        standup::display_standup(standup, &opts);
+    } else if cli.group.email_domains {
+        // Show authors whose email domain has changed
+        let people = email_domains::domain_changes(&opts);
+        email_domains::display_domain_changes(people, &opts);
+    } else if let Some(path) = cli.group.file_stats {
+        // Show the history of changes to a file
+        let stats = file_stats::file_commit_stats(&path, &opts);
+        file_stats::display_file_commit_stats(&path, stats, &opts);
+    } else if let Some(path) = cli.group.blame_summary {
+        // Show line ownership of a file
+        blame::display_blame_summary(&path, &opts);
+    } else if let Some(path) = cli.group.blame_age {
+        // Show how old the lines of a file are
+        blame::display_blame_age(&path, &opts);
+    } else if cli.group.code_age {
+        // Show how stale each part of the repository is
+        let directories = analysis::code_age::code_age(&ctx, &opts);
+        analysis::code_age::display_code_age(directories, &opts);
+    } else if let Some(depth) = cli.group.dir_activity {
+        // Show where development has been concentrated
+        let root = analysis::dir_activity::dir_activity(depth, &opts);
+        analysis::dir_activity::display_dir_activity(root, &opts);
+    } else if cli.group.review_latency {
+        // Show how long changes took to land
+        review::display_review_latency(&opts);
+    } else if cli.group.seasonality {
+        // Show when in the year work happens
+        let seasonality = analysis::seasonality::seasonality(&opts);
+        analysis::seasonality::display_seasonality(seasonality, &opts);
+    } else if let Some(days) = cli.group.audit_dates {
+        // Look for signs of rewritten history
+        audit::audit_dates(days, &opts);
+    } else if let Some(rev) = cli.group.stats_of {
+        // Summarise one commit
+        if !repo::rev_exists(&rev) {
+            eprintln!("[ERROR] Unknown revision {:?}", rev);
+            std::process::exit(1);
+        }
+        if let Some(stats) = commit_stats::commit_stats(&rev, &opts) {
+            commit_stats::display_commit_stats(stats, &opts);
+        }
+        if cli.patch && !opts.json {
+            if let Some(patch) = patch::commit_patch(&rev, &opts) {
+                println!();
+                patch::display_patch(&patch.files, &opts);
+            }
+        }
+    } else if let Some(rev) = cli.group.show {
+        // Show one commit's patch
+        if !repo::rev_exists(&rev) {
+            eprintln!("[ERROR] Unknown revision {:?}", rev);
+            std::process::exit(1);
+        }
+        if let Some(patch) = patch::commit_patch(&rev, &opts) {
+            patch::display_commit_patch(patch, &opts);
         }
     } else {
-        log::display_git_log(cli.group.log_number, &opts);
+        let n = match cli.group.log_number {
+            log::LogSelection::Count(n) => n,
+            log::LogSelection::Range(_) => settings::SETTINGS.default_n,
+        };
+        log::display_git_log(n, &opts);
+    }
+
+    if report_timings {
+        timings::report(start.elapsed());
+    }
+
+    if interrupt::interrupted() {
+        std::process::exit(interrupt::EXIT_CODE);
     }
 }
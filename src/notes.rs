@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// Returns a map of (full) commit hash -> note, for all commits with a note under refs/notes/commits
+pub fn git_notes() -> HashMap<String, String> {
+    // git notes list prints "<note blob> <annotated commit>" per line
+    let mut cmd = Command::new("git");
+    cmd.arg("notes");
+    cmd.arg("list");
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git notes`");
+
+    // If there are no notes, the notes ref may not exist, in which case git errors silently
+    if !output.status.success() {
+        return HashMap::new();
+    }
+
+    let note_objects: Vec<(String, String)> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (blob, commit) = line.split_once(' ')?;
+            Some((blob.to_string(), commit.to_string()))
+        })
+        .collect();
+    if note_objects.is_empty() {
+        return HashMap::new();
+    }
+
+    // Read all of the note contents in one process
+    let blobs: Vec<&str> = note_objects.iter().map(|(b, _)| b.as_str()).collect();
+    let contents = cat_file_batch(&blobs);
+
+    note_objects
+        .into_iter()
+        .zip(contents)
+        .map(|((_, commit), note)| (commit, note.trim_end().to_string()))
+        .collect()
+}
+
+// Read the contents of the given objects using `git cat-file --batch`
+fn cat_file_batch(objects: &[&str]) -> Vec<String> {
+    let mut child = Command::new("git")
+        .arg("cat-file")
+        .arg("--batch")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute `git cat-file`");
+
+    // Write the object names on a separate thread so that a full stdout pipe can't deadlock us
+    let mut stdin = child.stdin.take().unwrap();
+    let input = objects.join("\n") + "\n";
+    let writer = std::thread::spawn(move || {
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let output = child
+        .wait_with_output()
+        .expect("Failed to wait on `git cat-file`");
+    let _ = writer.join();
+
+    // Each object is printed as "<name> <type> <size>\n<contents>\n"
+    let stdout = output.stdout;
+    let mut contents = Vec::new();
+    let mut i = 0;
+    while i < stdout.len() {
+        let Some(header_len) = stdout[i..].iter().position(|&b| b == b'\n') else {
+            break;
+        };
+        let header = String::from_utf8_lossy(&stdout[i..i + header_len]);
+        i += header_len + 1;
+
+        let size: usize = header
+            .split(' ')
+            .nth(2)
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        let end = (i + size).min(stdout.len());
+        contents.push(String::from_utf8_lossy(&stdout[i..end]).into_owned());
+        i = end + 1;
+    }
+
+    contents
+}
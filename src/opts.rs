@@ -1,24 +1,130 @@
+use super::chart::ChartStyle;
+use super::decoration::DecorateStyle;
+use super::identity::AuthorFilter;
+
 #[derive(Clone)]
 pub struct GitLogOptions {
-    pub relative: bool, // relative commit dates
+    pub relative: bool,     // relative commit dates
+    pub stat_summary: bool, // print totals for the commits shown after the log
     pub colour: bool,
     pub reverse: bool,
     pub all: bool,
+    pub json: bool,
+    pub first_parent: bool,
+    pub merges_only: bool,
+    pub parents: bool,
+    pub show_notes: bool,
+    pub truncate: bool,
+    pub wrap: bool,
+    pub aligned: bool,
+    pub shortlog: bool,
+    pub author_date: bool, // count commits by author date rather than commit date
+    pub by_author: bool,   // break down commit counts by author
+    pub remote: bool,      // fetch details from the forge's API
+
+    // Language detection
+    pub include_ignored: bool,
+    pub detailed: bool,
+
+    // Contribution graph
+    pub log_scale: bool,
+    pub cumulative: bool,
+    pub smooth: Option<usize>,
+    pub markers: bool, // mark tags and milestones
+
+    // Refs shown alongside commits in the log
+    pub decorate: DecorateStyle,
+    pub exclude_refs: Vec<String>,
+
+    // Charts
+    pub chart_style: ChartStyle,
+    pub chart_width: Option<usize>,
+    pub chart_height: Option<usize>,
+    pub sparkline: bool, // add sparklines to tables
+
+    // Patches
+    pub word_diff: bool, // mark changed words inline rather than whole lines
+
+    // Revision to start the log from (defaults to HEAD)
+    pub rev: Option<String>,
+    pub all_refs: bool, // also start from every branch and tag
 
     // Filter commits by author or grep
-    pub authors: Vec<String>,
+    pub authors: Vec<AuthorFilter>,
     pub needles: Vec<String>,
+    pub grep_context: bool, // show the lines of the message body that matched --grep
+
+    // Exclude commits by automation accounts (see config::BOT_PATTERNS)
+    pub no_bots: bool,
+
+    // Filter commits by date (any format understood by git, e.g. "2023-01-01" or "1 year ago")
+    pub since: Option<String>,
+    pub until: Option<String>,
+
+    // Exclude paths matching these globs from the log and statistics
+    pub excludes: Vec<String>,
+
+    // Ignore whitespace-only changes in line statistics
+    pub ignore_whitespace: bool,
+
+    // Count generated files (see config::GENERATED_FILES) in line statistics
+    pub include_generated: bool,
+
+    // Filter commits by the number of lines they add plus delete
+    pub min_changes: Option<usize>,
+    pub max_changes: Option<usize>,
+
+    // Don't fetch objects missing from a partial clone
+    pub no_fetch: bool,
 }
 
 impl Default for GitLogOptions {
     fn default() -> Self {
         Self {
             relative: true,
+            stat_summary: false,
             colour: true,
             reverse: false,
             all: false,
+            all_refs: false,
+            json: false,
+            first_parent: false,
+            merges_only: false,
+            parents: false,
+            show_notes: false,
+            truncate: false,
+            wrap: false,
+            aligned: false,
+            shortlog: false,
+            author_date: false,
+            by_author: false,
+            remote: false,
+            include_ignored: false,
+            detailed: false,
+            log_scale: false,
+            cumulative: false,
+            smooth: None,
+            markers: false,
+            decorate: DecorateStyle::Short,
+            exclude_refs: vec![],
+            chart_style: ChartStyle::Braille,
+            chart_width: None,
+            chart_height: None,
+            sparkline: false,
+            word_diff: false,
+            rev: None,
             authors: Vec::new(),
             needles: Vec::new(),
+            grep_context: false,
+            no_bots: false,
+            since: None,
+            until: None,
+            excludes: Vec::new(),
+            ignore_whitespace: false,
+            include_generated: false,
+            min_changes: None,
+            max_changes: None,
+            no_fetch: false,
         }
     }
 }
@@ -0,0 +1,726 @@
+use super::commit::HashFormat;
+use super::diff::diff_stat_args;
+use super::opts::GitLogOptions;
+use super::syntax::{FileHighlighter, Highlighted};
+use super::theme::{Palette, THEME};
+use colored::*;
+use json::JsonValue;
+use std::process::{Command, Stdio};
+
+// One line of a hunk, without its leading " ", "-", or "+"
+pub enum HunkLine {
+    Context(String),
+    Removed(String),
+    Added(String),
+    // "\ No newline at end of file", which applies to the line before it
+    NoNewline(String),
+}
+
+pub struct Hunk {
+    // The "@@ -1,2 +1,3 @@ fn context" line
+    pub header: String,
+    pub lines: Vec<HunkLine>,
+}
+
+pub struct FilePatch {
+    pub path: String,
+    // Anything git says about the file other than its name, e.g. "new file mode 100644",
+    // "rename from old.rs", or "Binary files a/x and b/x differ"
+    pub notes: Vec<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+// A commit and the changes it made
+pub struct CommitPatch {
+    pub hash: String,
+    pub author: String,
+    pub date: String,
+    pub message: String,
+    pub files: Vec<FilePatch>,
+}
+
+// The patch of a single commit.  As with --stats-of, a merge is compared with its first parent
+pub fn commit_patch(rev: &str, opts: &GitLogOptions) -> Option<CommitPatch> {
+    let mut cmd = Command::new("git");
+    cmd.arg("show");
+    cmd.arg("--patch");
+    cmd.arg("--no-color");
+    cmd.arg("--no-ext-diff");
+    cmd.arg("--diff-merges=first-parent");
+    cmd.args(diff_stat_args(opts));
+    cmd.arg("--date=format:%Y-%m-%d %H:%M");
+    cmd.arg("--format=%H%x00%an <%ae>%x00%ad%x00%B%x00");
+    cmd.arg(format!("{}^{{commit}}", rev));
+    cmd.arg("--");
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git show`");
+
+    if !output.status.success() {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut parts = stdout.splitn(5, '\0');
+    Some(CommitPatch {
+        hash: parts.next().unwrap_or("").to_string(),
+        author: parts.next().unwrap_or("").to_string(),
+        date: parts.next().unwrap_or("").to_string(),
+        message: parts.next().unwrap_or("").trim_end().to_string(),
+        files: parse_patch(parts.next().unwrap_or("")),
+    })
+}
+
+// Parse the output of `git diff` (or the patch part of `git show`) into files and hunks
+pub fn parse_patch(patch: &str) -> Vec<FilePatch> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    for line in patch.lines() {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            // "a/old b/new"; the +++ or "rename to" lines give the new path more reliably, as
+            // paths may contain spaces
+            let path = paths.rsplit_once(" b/").map_or(paths, |(_, new)| new);
+            files.push(FilePatch {
+                path: path.to_string(),
+                notes: Vec::new(),
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if line.starts_with("@@") {
+            file.hunks.push(Hunk {
+                header: line.to_string(),
+                lines: Vec::new(),
+            });
+            continue;
+        }
+        match file.hunks.last_mut() {
+            Some(hunk) => {
+                let (marker, text) = line.split_at(line.chars().next().map_or(0, char::len_utf8));
+                hunk.lines.push(match marker {
+                    "-" => HunkLine::Removed(text.to_string()),
+                    "+" => HunkLine::Added(text.to_string()),
+                    "\\" => HunkLine::NoNewline(line.to_string()),
+                    _ => HunkLine::Context(text.to_string()),
+                });
+            }
+            None => {
+                if let Some(path) = line
+                    .strip_prefix("+++ b/")
+                    .or_else(|| line.strip_prefix("rename to "))
+                {
+                    file.path = path.to_string();
+                }
+                if !line.starts_with("index ")
+                    && !line.starts_with("--- ")
+                    && !line.starts_with("+++ ")
+                {
+                    file.notes.push(line.to_string());
+                }
+            }
+        }
+    }
+    files
+}
+
+pub fn display_commit_patch(patch: CommitPatch, opts: &GitLogOptions) {
+    if opts.json {
+        let files: Vec<JsonValue> = patch.files.iter().map(file_patch_json).collect();
+        let value = json::object! {
+            hash: patch.hash.clone(),
+            author: patch.author.clone(),
+            date: patch.date.clone(),
+            message: patch.message.clone(),
+            files: JsonValue::Array(files),
+        };
+        println!("{}", value.pretty(2));
+        return;
+    }
+
+    let hash = patch.hash.short();
+    let mut message = patch.message.lines();
+    let subject = message.next().unwrap_or("");
+    if opts.colour {
+        println!("{}  {}", THEME.hash.paint(&hash), subject.bold());
+        println!("Author:   {}", THEME.author.paint(&patch.author));
+        println!("Date:     {}", THEME.date.paint(&patch.date));
+    } else {
+        println!("{}  {}", hash, subject);
+        println!("Author:   {}", patch.author);
+        println!("Date:     {}", patch.date);
+    }
+    for line in message {
+        if line.is_empty() {
+            println!();
+        } else {
+            println!("    {}", line);
+        }
+    }
+
+    println!();
+    display_patch(&patch.files, opts);
+}
+
+fn file_patch_json(file: &FilePatch) -> JsonValue {
+    let hunks: Vec<JsonValue> = file
+        .hunks
+        .iter()
+        .map(|hunk| {
+            let lines: Vec<String> = hunk
+                .lines
+                .iter()
+                .map(|line| match line {
+                    HunkLine::Context(s) => format!(" {}", s),
+                    HunkLine::Removed(s) => format!("-{}", s),
+                    HunkLine::Added(s) => format!("+{}", s),
+                    HunkLine::NoNewline(s) => s.clone(),
+                })
+                .collect();
+            json::object! { header: hunk.header.clone(), lines: lines }
+        })
+        .collect();
+    json::object! {
+        path: file.path.clone(),
+        notes: file.notes.clone(),
+        hunks: JsonValue::Array(hunks),
+    }
+}
+
+// Print a patch, highlighting within each changed line the part that actually changed.  In colour,
+// the code is also highlighted by its language (from the file's path), with removed and added
+// lines tinted in the colours for removed and added text
+pub fn display_patch(files: &[FilePatch], opts: &GitLogOptions) {
+    if files.is_empty() {
+        println!("No files changed.");
+        return;
+    }
+
+    for (i, file) in files.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        if opts.colour {
+            println!("{}", file.path.bold());
+        } else {
+            println!("{}", file.path);
+        }
+        for note in &file.notes {
+            if opts.colour {
+                println!("{}", note.dimmed());
+            } else {
+                println!("{}", note);
+            }
+        }
+        // A word diff is already coloured by what changed, and in monochrome there are no colours
+        // to tint lines with
+        let mut highlighter =
+            if opts.colour && !opts.word_diff && THEME.palette != Palette::Monochrome {
+                FileHighlighter::for_path(&file.path)
+            } else {
+                None
+            };
+        for hunk in &file.hunks {
+            if opts.colour {
+                println!("{}", THEME.diff_hunk.paint(&hunk.header));
+            } else {
+                println!("{}", hunk.header);
+            }
+            display_hunk_lines(&hunk.lines, highlighter.as_mut(), opts);
+        }
+    }
+}
+
+// A hunk in the pieces that renderers deal with: unchanged lines, and changes, where a run of
+// removed lines followed by a run of added lines is a change from the one to the other
+enum HunkSegment<'a> {
+    Line(&'a HunkLine),
+    Change(Vec<&'a str>, Vec<&'a str>),
+}
+
+fn hunk_segments(lines: &[HunkLine]) -> Vec<HunkSegment<'_>> {
+    let mut segments = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let removed: Vec<&str> = lines[i..]
+            .iter()
+            .map_while(|line| match line {
+                HunkLine::Removed(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect();
+        let added: Vec<&str> = lines[i + removed.len()..]
+            .iter()
+            .map_while(|line| match line {
+                HunkLine::Added(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        if removed.is_empty() && added.is_empty() {
+            segments.push(HunkSegment::Line(&lines[i]));
+            i += 1;
+        } else {
+            i += removed.len() + added.len();
+            segments.push(HunkSegment::Change(removed, added));
+        }
+    }
+    segments
+}
+
+fn display_hunk_lines(
+    lines: &[HunkLine],
+    mut highlighter: Option<&mut FileHighlighter>,
+    opts: &GitLogOptions,
+) {
+    for segment in hunk_segments(lines) {
+        match segment {
+            HunkSegment::Line(HunkLine::Context(s)) if highlighter.is_some() => {
+                let highlighter = highlighter.as_mut().unwrap();
+                println!(" {}", paint_syntax(&highlighter.context(s), None));
+            }
+            HunkSegment::Line(line) => {
+                display_unchanged_line(line, if opts.word_diff { "" } else { " " }, opts)
+            }
+            HunkSegment::Change(removed, added) if opts.word_diff => {
+                for line in word_diff_lines(&removed, &added, opts) {
+                    println!("{}", line);
+                }
+            }
+            HunkSegment::Change(removed, added) => {
+                // When the runs are the same length, we pair them up line by line to highlight
+                // what changed
+                let paired = opts.colour && removed.len() == added.len();
+                for (j, old) in removed.iter().enumerate() {
+                    let changed = if paired {
+                        changed_range(old, added[j]).map(|(start, old_end, _)| (start, old_end))
+                    } else {
+                        None
+                    };
+                    match highlighter.as_mut() {
+                        Some(highlighter) => {
+                            let syntax = highlighter.removed(old);
+                            println!("{}", paint_syntax_line('-', &syntax, changed, highlighter));
+                        }
+                        None => println!("{}", paint_line('-', old, changed, opts)),
+                    }
+                }
+                for (j, new) in added.iter().enumerate() {
+                    let changed = if paired {
+                        changed_range(removed[j], new).map(|(start, _, new_end)| (start, new_end))
+                    } else {
+                        None
+                    };
+                    match highlighter.as_mut() {
+                        Some(highlighter) => {
+                            let syntax = highlighter.added(new);
+                            println!("{}", paint_syntax_line('+', &syntax, changed, highlighter));
+                        }
+                        None => println!("{}", paint_line('+', new, changed, opts)),
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Context lines are prefixed with a space in a patch, but not in a word diff, where no lines are
+// marked as removed or added
+fn display_unchanged_line(line: &HunkLine, context_prefix: &str, opts: &GitLogOptions) {
+    match line {
+        HunkLine::NoNewline(s) if opts.colour => println!("{}", s.dimmed()),
+        HunkLine::NoNewline(s) => println!("{}", s),
+        HunkLine::Context(s) => println!("{}{}", context_prefix, s),
+        HunkLine::Removed(_) | HunkLine::Added(_) => unreachable!(),
+    }
+}
+
+// A piece of a word diff
+#[derive(Debug, PartialEq)]
+enum WordChange {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+// Split text into words (runs of letters, digits, and underscores), runs of spaces, line breaks,
+// and single punctuation characters, which are the units a word diff compares
+fn word_tokens(s: &str) -> Vec<&str> {
+    #[derive(PartialEq)]
+    enum Class {
+        Word,
+        Space,
+        Other,
+    }
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            Class::Word
+        } else if c.is_whitespace() && c != '\n' {
+            Class::Space
+        } else {
+            Class::Other
+        }
+    };
+
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut chars = s.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        let c_class = class(c);
+        if c_class != Class::Other {
+            while let Some(&(_, next)) = chars.peek() {
+                if class(next) != c_class {
+                    break;
+                }
+                chars.next();
+            }
+        }
+        let end = chars.peek().map_or(s.len(), |&(i, _)| i);
+        tokens.push(&s[start..end]);
+        start = end;
+    }
+    tokens
+}
+
+// Above this many token comparisons, a change is shown as all of the old text removed and all of
+// the new text added, rather than spending a long time (and a lot of memory) on a word diff
+const MAX_WORD_DIFF_CELLS: usize = 4_000_000;
+
+// The changes from the old to the new text, from their longest common subsequence of tokens.  Each
+// run of changes between unchanged text is given as the text removed and then the text added, and
+// whitespace alone between two changes counts as part of the change, as it is more readable to see
+// "[-quick-]{+slow brown+}" than "[-quick-]{+slow+} {+brown +}"
+fn word_diff(old: &str, new: &str) -> Vec<WordChange> {
+    let (old, new) = (word_tokens(old), word_tokens(new));
+    let (n, m) = (old.len(), new.len());
+    let mut tokens: Vec<WordChange> = Vec::new();
+    if n.saturating_mul(m) > MAX_WORD_DIFF_CELLS {
+        tokens.push(WordChange::Removed(old.concat()));
+        tokens.push(WordChange::Added(new.concat()));
+    } else {
+        // lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+        let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+        let at = |i: usize, j: usize| i * (m + 1) + j;
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[at(i, j)] = if old[i] == new[j] {
+                    lcs[at(i + 1, j + 1)] + 1
+                } else {
+                    lcs[at(i + 1, j)].max(lcs[at(i, j + 1)])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old[i] == new[j] {
+                tokens.push(WordChange::Same(old[i].to_string()));
+                i += 1;
+                j += 1;
+            } else if j == m || (i < n && lcs[at(i + 1, j)] >= lcs[at(i, j + 1)]) {
+                tokens.push(WordChange::Removed(old[i].to_string()));
+                i += 1;
+            } else {
+                tokens.push(WordChange::Added(new[j].to_string()));
+                j += 1;
+            }
+        }
+    }
+
+    let is_change = |change: Option<&WordChange>| {
+        matches!(change, Some(WordChange::Removed(_) | WordChange::Added(_)))
+    };
+    let mut changes = Vec::new();
+    let (mut removed, mut added) = (String::new(), String::new());
+    for (k, token) in tokens.iter().enumerate() {
+        match token {
+            WordChange::Same(text)
+                if text.trim().is_empty()
+                    && k > 0
+                    && is_change(tokens.get(k - 1))
+                    && is_change(tokens.get(k + 1)) =>
+            {
+                removed.push_str(text);
+                added.push_str(text);
+            }
+            WordChange::Same(text) => {
+                flush_word_changes(&mut changes, &mut removed, &mut added);
+                match changes.last_mut() {
+                    Some(WordChange::Same(same)) => same.push_str(text),
+                    _ => changes.push(WordChange::Same(text.clone())),
+                }
+            }
+            WordChange::Removed(text) => removed.push_str(text),
+            WordChange::Added(text) => added.push_str(text),
+        }
+    }
+    flush_word_changes(&mut changes, &mut removed, &mut added);
+    changes
+}
+
+// Add a run of changes to the word diff.  Whitespace at the end of both the removed and the added
+// text is unchanged, so it is moved out of the change
+fn flush_word_changes(changes: &mut Vec<WordChange>, removed: &mut String, added: &mut String) {
+    let unchanged: usize = removed
+        .chars()
+        .rev()
+        .zip(added.chars().rev())
+        .take_while(|(a, b)| a == b && a.is_whitespace())
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+    let unchanged = removed.split_off(removed.len() - unchanged);
+    added.truncate(added.len() - unchanged.len());
+
+    if !removed.is_empty() {
+        changes.push(WordChange::Removed(std::mem::take(removed)));
+    }
+    if !added.is_empty() {
+        changes.push(WordChange::Added(std::mem::take(added)));
+    }
+    if !unchanged.is_empty() {
+        changes.push(WordChange::Same(unchanged));
+    }
+}
+
+// Render a change from some lines to others as a word diff: the new lines, with the words that
+// were removed and added marked inline, as [-removed-]{+added+}, or in colour.  Line breaks that
+// were removed are dropped, so the lines are laid out as in the new text
+fn word_diff_lines(removed: &[&str], added: &[&str], opts: &GitLogOptions) -> Vec<String> {
+    let (old, new) = (removed.join("\n"), added.join("\n"));
+    let mut lines = vec![String::new()];
+    for change in word_diff(&old, &new) {
+        let text = match &change {
+            WordChange::Same(text) | WordChange::Removed(text) | WordChange::Added(text) => text,
+        };
+        for (k, part) in text.split('\n').enumerate() {
+            if k > 0 && !matches!(change, WordChange::Removed(_)) {
+                lines.push(String::new());
+            }
+            if part.is_empty() {
+                continue;
+            }
+            let line = lines.last_mut().unwrap();
+            match (&change, opts.colour) {
+                (WordChange::Same(_), _) => line.push_str(part),
+                (WordChange::Removed(_), true) => {
+                    line.push_str(&THEME.diff_removed.paint(part).to_string())
+                }
+                (WordChange::Added(_), true) => {
+                    line.push_str(&THEME.diff_added.paint(part).to_string())
+                }
+                (WordChange::Removed(_), false) => line.push_str(&format!("[-{}-]", part)),
+                (WordChange::Added(_), false) => line.push_str(&format!("{{+{}+}}", part)),
+            }
+        }
+    }
+    lines
+}
+
+// Where two versions of a line differ, after their common prefix and suffix: the byte offset at
+// which the difference starts, and where it ends in the old and the new line.  None if the lines
+// have nothing in common, as then highlighting the whole line says nothing
+fn changed_range(old: &str, new: &str) -> Option<(usize, usize, usize)> {
+    let prefix: usize = old
+        .chars()
+        .zip(new.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+    let suffix: usize = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+    if prefix + suffix == 0 {
+        return None;
+    }
+    Some((prefix, old.len() - suffix, new.len() - suffix))
+}
+
+// A removed or added line, with the given byte range of it in reverse video
+fn paint_line(
+    marker: char,
+    line: &str,
+    changed: Option<(usize, usize)>,
+    opts: &GitLogOptions,
+) -> String {
+    let line = format!("{}{}", marker, line);
+    if !opts.colour {
+        return line;
+    }
+    let colour = if marker == '-' {
+        &THEME.diff_removed
+    } else {
+        &THEME.diff_added
+    };
+    match changed {
+        // Offsets are into the line without its marker
+        Some((start, end)) => format!(
+            "{}{}{}",
+            colour.paint(&line[..start + 1]),
+            colour.paint(&line[start + 1..end + 1]).reversed(),
+            colour.paint(&line[end + 1..])
+        ),
+        None => colour.paint(&line).to_string(),
+    }
+}
+
+// How strongly removed and added lines are tinted, and the part of them that changed, out of 255
+const LINE_TINT: u16 = 64;
+const CHANGE_TINT: u16 = 128;
+
+// A removed or added line highlighted by its syntax, on a background of the theme's colour for
+// removed or added text.  The given byte range of the line is tinted more strongly
+fn paint_syntax_line(
+    marker: char,
+    syntax: &[Highlighted],
+    changed: Option<(usize, usize)>,
+    highlighter: &FileHighlighter,
+) -> String {
+    let colour = if marker == '-' {
+        &THEME.diff_removed
+    } else {
+        &THEME.diff_added
+    };
+    let tint = |amount| blend(highlighter.background, colour.rgb(), amount);
+    let (line_tint, change_tint) = (tint(LINE_TINT), tint(CHANGE_TINT));
+    let marker =
+        colour
+            .paint(&marker.to_string())
+            .on_truecolor(line_tint.0, line_tint.1, line_tint.2);
+    format!(
+        "{}{}",
+        marker,
+        paint_syntax(syntax, Some((line_tint, change_tint, changed)))
+    )
+}
+
+type Tints = ((u8, u8, u8), (u8, u8, u8), Option<(usize, usize)>);
+
+// Text highlighted by its syntax, optionally on a background colour, with a different background
+// for the given byte range of the text
+fn paint_syntax(syntax: &[Highlighted], tints: Option<Tints>) -> String {
+    let mut painted = String::new();
+    let mut offset = 0;
+    for region in syntax {
+        let end = offset + region.text.len();
+        // Split the region where the changed range starts and ends
+        let mut cuts = vec![offset, end];
+        if let Some((_, _, Some((start, stop)))) = tints {
+            cuts.extend(
+                [start, stop]
+                    .iter()
+                    .copied()
+                    .filter(|&i| offset < i && i < end),
+            );
+        }
+        cuts.sort_unstable();
+        for piece in cuts.windows(2) {
+            let text = &region.text[piece[0] - offset..piece[1] - offset];
+            let mut text = match region.colour {
+                Some((r, g, b)) => text.truecolor(r, g, b),
+                None => text.normal(),
+            };
+            if let Some((line, change, changed)) = tints {
+                let in_change =
+                    changed.is_some_and(|(start, stop)| start <= piece[0] && piece[1] <= stop);
+                let (r, g, b) = if in_change { change } else { line };
+                text = text.on_truecolor(r, g, b);
+            }
+            painted.push_str(&text.to_string());
+        }
+        offset = end;
+    }
+    painted
+}
+
+// The colour the given amount (out of 255) of the way from one colour to another
+fn blend(from: (u8, u8, u8), to: rgb::RGB8, amount: u16) -> (u8, u8, u8) {
+    let mix = |a: u8, b: u8| ((a as u16 * (255 - amount) + b as u16 * amount) / 255) as u8;
+    (mix(from.0, to.r), mix(from.1, to.g), mix(from.2, to.b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_patches() {
+        let patch = "diff --git a/old name.rs b/new name.rs\n\
+                     similarity index 90%\n\
+                     rename from old name.rs\n\
+                     rename to new name.rs\n\
+                     index 1111111..2222222 100644\n\
+                     --- a/old name.rs\n\
+                     +++ b/new name.rs\n\
+                     @@ -1,2 +1,2 @@\n\
+                     \x20same\n\
+                     -old\n\
+                     +new\n\
+                     \\ No newline at end of file\n\
+                     diff --git a/bin b/bin\n\
+                     Binary files a/bin and b/bin differ\n";
+        let files = parse_patch(patch);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "new name.rs");
+        assert_eq!(
+            files[0].notes,
+            [
+                "similarity index 90%",
+                "rename from old name.rs",
+                "rename to new name.rs"
+            ]
+        );
+        assert_eq!(files[0].hunks.len(), 1);
+        assert!(matches!(
+            files[0].hunks[0].lines.as_slice(),
+            [
+                HunkLine::Context(same),
+                HunkLine::Removed(old),
+                HunkLine::Added(new),
+                HunkLine::NoNewline(_),
+            ] if same == "same" && old == "old" && new == "new"
+        ));
+        assert_eq!(files[1].path, "bin");
+        assert_eq!(files[1].notes, ["Binary files a/bin and b/bin differ"]);
+        assert!(files[1].hunks.is_empty());
+    }
+
+    #[test]
+    fn finds_the_changed_part_of_a_line() {
+        assert_eq!(changed_range("let x = 1;", "let x = 42;"), Some((8, 9, 10)));
+        assert_eq!(changed_range("abc", "abXc"), Some((2, 2, 3)));
+        assert_eq!(changed_range("Zoë", "Zoé"), Some((2, 4, 4)));
+        assert_eq!(changed_range("old", "new"), None);
+    }
+
+    #[test]
+    fn splits_words() {
+        assert_eq!(
+            word_tokens("let  x_1 = f(ü);\n"),
+            ["let", "  ", "x_1", " ", "=", " ", "f", "(", "ü", ")", ";", "\n"]
+        );
+        assert!(word_tokens("").is_empty());
+    }
+
+    #[test]
+    fn diffs_words() {
+        use WordChange::*;
+        assert_eq!(
+            word_diff("the quick fox", "the slow brown fox"),
+            [
+                Same("the ".to_string()),
+                Removed("quick".to_string()),
+                Added("slow brown".to_string()),
+                Same(" fox".to_string()),
+            ]
+        );
+        assert_eq!(word_diff("same", "same"), [Same("same".to_string())]);
+        assert_eq!(word_diff("", "new"), [Added("new".to_string())]);
+    }
+}
@@ -0,0 +1,190 @@
+use super::commit::HashFormat;
+use super::opts::GitLogOptions;
+use super::repo;
+use json::JsonValue;
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+use tabular::{row, Table};
+
+// A commit on one side of a symmetric difference (A...B)
+pub struct SideCommit {
+    pub hash: String,
+    pub subject: String,
+    // Whether the commit is only reachable from the left-hand ref
+    pub left: bool,
+}
+
+// The same change, applied on both refs (e.g. a cherry-pick from main to a release branch)
+pub struct Duplicate {
+    pub patch_id: String,
+    pub left: Vec<SideCommit>,
+    pub right: Vec<SideCommit>,
+}
+
+// Map each commit in the given revision range to its patch ID: a hash of the commit's diff that
+// ignores line numbers and whitespace, so a change has the same patch ID wherever it was applied.
+// Merge commits have no patch ID
+pub fn patch_ids(range: &str) -> Option<HashMap<String, String>> {
+    let mut log = Command::new("git")
+        .arg("log")
+        .arg("--no-merges")
+        .arg("--no-color")
+        .arg("--patch")
+        .arg("--format=commit %H")
+        .arg(range)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to execute `git log`");
+
+    let log_stdout = log.stdout.take().expect("Failed to read `git log` output");
+    let output = Command::new("git")
+        .arg("patch-id")
+        .arg("--stable")
+        .stdin(log_stdout)
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git patch-id`");
+
+    let log_status = log.wait().expect("Failed to wait for `git log`");
+    if !log_status.success() || !output.status.success() {
+        return None;
+    }
+
+    // Each line is "<patch ID> <commit hash>"
+    let ids = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(patch_id, hash)| (hash.to_string(), patch_id.to_string()))
+        .collect();
+    Some(ids)
+}
+
+// The commits that are on only one of two refs, marked with the side they are on
+fn side_commits(range: &str) -> Option<Vec<SideCommit>> {
+    let output = Command::new("git")
+        .arg("log")
+        .arg("--no-merges")
+        .arg("--left-right")
+        .arg("--format=%m%x00%H%x00%s")
+        .arg(range)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git log`");
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let commits = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\0');
+            let side = fields.next()?;
+            Some(SideCommit {
+                left: side == "<",
+                hash: fields.next()?.to_string(),
+                subject: fields.next().unwrap_or("").to_string(),
+            })
+        })
+        .collect();
+    Some(commits)
+}
+
+// Find changes that were applied to both refs as separate commits, newest first
+pub fn duplicate_commits(left_ref: &str, right_ref: &str) -> Option<Vec<Duplicate>> {
+    // Commits reachable from both refs are shared history, not duplicates, so we only need to
+    // consider the symmetric difference
+    let range = format!("{}...{}", left_ref, right_ref);
+    let ids = patch_ids(&range)?;
+    let commits = side_commits(&range)?;
+
+    let mut duplicates: Vec<Duplicate> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for commit in commits {
+        // Empty commits have no patch ID
+        let Some(patch_id) = ids.get(&commit.hash) else {
+            continue;
+        };
+        let i = *index.entry(patch_id.clone()).or_insert_with(|| {
+            duplicates.push(Duplicate {
+                patch_id: patch_id.clone(),
+                left: vec![],
+                right: vec![],
+            });
+            duplicates.len() - 1
+        });
+        if commit.left {
+            duplicates[i].left.push(commit);
+        } else {
+            duplicates[i].right.push(commit);
+        }
+    }
+
+    duplicates.retain(|d| !d.left.is_empty() && !d.right.is_empty());
+    Some(duplicates)
+}
+
+pub fn display_duplicate_commits(left_ref: &str, right_ref: &str, opts: &GitLogOptions) {
+    for rev in [left_ref, right_ref] {
+        if !repo::rev_exists(rev) {
+            eprintln!("[ERROR] Unknown revision {:?}", rev);
+            return;
+        }
+    }
+    let Some(duplicates) = duplicate_commits(left_ref, right_ref) else {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return;
+    };
+
+    let hashes = |commits: &[SideCommit]| -> Vec<String> {
+        commits.iter().map(|c| c.hash.clone()).collect()
+    };
+    if opts.json {
+        let duplicates: Vec<JsonValue> = duplicates
+            .iter()
+            .map(|d| {
+                json::object! {
+                    patch_id: d.patch_id.clone(),
+                    subject: d.left[0].subject.clone(),
+                    left: hashes(&d.left),
+                    right: hashes(&d.right),
+                }
+            })
+            .collect();
+        println!("{}", JsonValue::Array(duplicates).pretty(2));
+        return;
+    }
+
+    if duplicates.is_empty() {
+        println!(
+            "No commits on {} were also applied to {}.",
+            left_ref, right_ref
+        );
+        return;
+    }
+
+    let short_hashes = |commits: &[SideCommit]| -> String {
+        commits
+            .iter()
+            .map(|c| c.hash.short())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut table = Table::new("{:<}  {:<}  {:<}").with_row(row!(left_ref, right_ref, "Subject"));
+    for d in &duplicates {
+        table.add_row(row!(
+            short_hashes(&d.left),
+            short_hashes(&d.right),
+            &d.left[0].subject
+        ));
+    }
+    print!("{}", table);
+    println!(
+        "\n{} changes were applied to both {} and {}.",
+        duplicates.len(),
+        left_ref,
+        right_ref
+    );
+}
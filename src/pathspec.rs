@@ -0,0 +1,119 @@
+use super::opts::GitLogOptions;
+use super::settings::SETTINGS;
+
+// Pathspec arguments (to be placed at the end of a git command) that exclude any paths matching the
+// globs given in the options.  Git applies these the same way for log filtering and for diff
+// statistics, so every subsystem that walks history should use this to stay consistent
+//
+// We use the default pathspec magic (rather than `glob`) so that a wildcard can match across
+// directories (e.g. "*.lock" excludes lockfiles at any depth), and `top` so that patterns are
+// relative to the repository root regardless of the current directory
+pub fn exclude_pathspec_args(opts: &GitLogOptions) -> Vec<String> {
+    pathspec_args(&opts.excludes)
+}
+
+// Whether a path (relative to the repository root) is a generated file (see
+// config::GENERATED_FILES), which line statistics leave out unless asked to include them.  Unlike
+// --exclude, we match these ourselves rather than giving them to git, as a pathspec would also drop
+// commits that only touch generated files from the revision walk, changing commit counts too
+pub fn is_generated(path: &str, opts: &GitLogOptions) -> bool {
+    !opts.include_generated
+        && SETTINGS
+            .generated_files
+            .iter()
+            .any(|pattern| pathspec_matches(pattern, path))
+}
+
+// Match a path as git matches a pathspec with the default magic: a pattern without wildcards
+// matches that path or anything under it, and otherwise "*" may match across directories.  As in
+// .gitignore, a leading "**/" matches any number of directories, including none
+fn pathspec_matches(pattern: &str, path: &str) -> bool {
+    if let Some(rest) = pattern.strip_prefix("**/") {
+        return pathspec_matches(rest, path)
+            || path
+                .match_indices('/')
+                .any(|(i, _)| pathspec_matches(rest, &path[i + 1..]));
+    }
+    if !pattern.contains(['*', '?', '[']) {
+        let pattern = pattern.trim_end_matches('/');
+        return path == pattern
+            || path
+                .strip_prefix(pattern)
+                .is_some_and(|rest| rest.starts_with('/'));
+    }
+    wildcard_matches(pattern.as_bytes(), path.as_bytes())
+}
+
+fn wildcard_matches(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((b'*', rest)) => (0..=path.len()).any(|i| wildcard_matches(rest, &path[i..])),
+        Some((b'?', rest)) => !path.is_empty() && wildcard_matches(rest, &path[1..]),
+        Some((b'[', rest)) => {
+            let Some(close) = rest.iter().skip(1).position(|&c| c == b']').map(|i| i + 1) else {
+                return path.first() == Some(&b'[') && wildcard_matches(rest, &path[1..]);
+            };
+            let Some((&c, path_rest)) = path.split_first() else {
+                return false;
+            };
+            let (negated, class) = match rest[..close].split_first() {
+                Some((b'!' | b'^', class)) => (true, class),
+                _ => (false, &rest[..close]),
+            };
+            let mut in_class = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == b'-' {
+                    in_class |= class[i] <= c && c <= class[i + 2];
+                    i += 3;
+                } else {
+                    in_class |= class[i] == c;
+                    i += 1;
+                }
+            }
+            in_class != negated && wildcard_matches(&rest[close + 1..], path_rest)
+        }
+        Some((&c, rest)) => path.first() == Some(&c) && wildcard_matches(rest, &path[1..]),
+    }
+}
+
+fn pathspec_args(excludes: &[String]) -> Vec<String> {
+    if excludes.is_empty() {
+        return vec![];
+    }
+
+    let mut args = vec![String::from("--"), String::from(":/")];
+    for exclude in excludes {
+        args.push(format!(":(top,exclude){}", exclude));
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_like_git_pathspecs() {
+        assert!(pathspec_matches("*.lock", "Cargo.lock"));
+        assert!(pathspec_matches("*.lock", "vendor/deep/yarn.lock"));
+        assert!(pathspec_matches(
+            "*__snapshots__/*",
+            "src/__snapshots__/a.snap"
+        ));
+        assert!(pathspec_matches("go.sum", "go.sum"));
+        assert!(!pathspec_matches("go.sum", "sub/go.sum"));
+        assert!(pathspec_matches("**/go.sum", "go.sum"));
+        assert!(pathspec_matches("**/go.sum", "sub/go.sum"));
+        assert!(pathspec_matches(
+            "**/package-lock.json",
+            "web/app/package-lock.json"
+        ));
+        assert!(!pathspec_matches("**/go.sum", "notgo.sum"));
+        assert!(pathspec_matches("**/*.min.js", "a/b.min.js"));
+        assert!(pathspec_matches("vendor", "vendor/lib.js"));
+        assert!(!pathspec_matches("vendor", "vendored.js"));
+        assert!(pathspec_matches("*.pb.[ch]", "proto/a.pb.h"));
+        assert!(!pathspec_matches("*.min.js", "src/main.js"));
+    }
+}
@@ -0,0 +1,38 @@
+use super::interrupt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+// Apply a function to each item on a pool of worker threads (one per CPU), returning the results in
+// the same order as the items, so that output doesn't depend on scheduling.  This is for work that
+// is mostly spent waiting on git (e.g. a `git log --numstat` per author), where running several
+// git processes at once makes good use of the machine.  Items not yet started when we are
+// interrupted are skipped, and their result is None
+pub fn parallel_map<T, R, F>(items: &[T], f: F) -> Vec<Option<R>>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let n_workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(items.len());
+    let results: Mutex<Vec<Option<R>>> = Mutex::new((0..items.len()).map(|_| None).collect());
+    let next = AtomicUsize::new(0);
+
+    thread::scope(|scope| {
+        for _ in 0..n_workers {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= items.len() || interrupt::interrupted() {
+                    break;
+                }
+                let result = f(&items[i]);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+
+    results.into_inner().unwrap()
+}
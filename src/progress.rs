@@ -0,0 +1,82 @@
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+const BAR_WIDTH: usize = 20;
+
+// Don't draw anything for operations that finish quickly, so that fast runs don't flicker
+const DELAY: Duration = Duration::from_millis(500);
+
+// How often to redraw, at most
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+// A progress bar for expensive operations, drawn on stderr so that it never mixes with the output
+// (which may be piped or redirected).  It is only drawn when stderr is a terminal
+pub struct Progress {
+    label: &'static str,
+    unit: &'static str,
+    total: usize,
+    done: usize,
+    start: Instant,
+    last_draw: Option<Instant>,
+    enabled: bool,
+}
+
+impl Progress {
+    pub fn new(label: &'static str, unit: &'static str, total: usize) -> Self {
+        Self {
+            label,
+            unit,
+            total,
+            done: 0,
+            start: Instant::now(),
+            last_draw: None,
+            enabled: io::stderr().is_terminal(),
+        }
+    }
+
+    pub fn inc(&mut self, n: usize) {
+        self.done += n;
+        if !self.enabled || self.start.elapsed() < DELAY {
+            return;
+        }
+        if self
+            .last_draw
+            .is_some_and(|last| last.elapsed() < REDRAW_INTERVAL)
+        {
+            return;
+        }
+        self.draw();
+        self.last_draw = Some(Instant::now());
+    }
+
+    fn draw(&self) {
+        let fraction = if self.total == 0 {
+            0.0
+        } else {
+            (self.done as f64 / self.total as f64).min(1.0)
+        };
+        let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+        let rate = self.done as f64 / self.start.elapsed().as_secs_f64();
+        eprint!(
+            "\r{} [{}{}] {:>3}% {}/{} {} ({:.0} {}/s)\x1b[K",
+            self.label,
+            "#".repeat(filled),
+            "-".repeat(BAR_WIDTH - filled),
+            (fraction * 100.0).round(),
+            self.done,
+            self.total,
+            self.unit,
+            rate,
+            self.unit
+        );
+        let _ = io::stderr().flush();
+    }
+
+    // Clear the bar, so that the output starts on a clean line
+    pub fn finish(&self) {
+        if self.last_draw.is_some() {
+            eprint!("\r\x1b[K");
+            let _ = io::stderr().flush();
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use super::opts::GitLogOptions;
+use super::theme::{ThemeColour, THEME};
+use clap::ValueEnum;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::process::{Command, Stdio};
+
+lazy_static! {
+    static ref ANSI_ESCAPE_RE: Regex = Regex::new(r"\x1b\[[0-9;]*m").unwrap();
+}
+
+// How the prompt segment will be embedded
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum PromptShell {
+    // e.g. starship custom commands or tmux, which take the output as-is
+    Plain,
+    Bash,
+    Zsh,
+}
+
+impl PromptShell {
+    // Shells need to be told which parts of the prompt are invisible (so that they can work out
+    // its width), and some characters in branch names have special meaning in prompts
+    fn escape(&self, segment: &str) -> String {
+        match self {
+            PromptShell::Plain => segment.to_string(),
+            // Bash only decodes \[ and \] in PS1 itself, not in the output of a command substitution
+            // in it, so this is for setting PS1 from PROMPT_COMMAND.  PS1 then has its backslash
+            // escapes decoded, and is expanded as if in double quotes, so "\\$" is what comes out as
+            // a plain "$" (whereas "\$" would be "#" for root)
+            PromptShell::Bash => {
+                let segment = segment
+                    .replace('\\', r"\\\\")
+                    .replace('$', r"\\$")
+                    .replace('`', r"\\`");
+                ANSI_ESCAPE_RE.replace_all(&segment, r"\[$0\]").into_owned()
+            }
+            PromptShell::Zsh => {
+                let segment = segment.replace('%', "%%");
+                ANSI_ESCAPE_RE.replace_all(&segment, "%{$0%}").into_owned()
+            }
+        }
+    }
+}
+
+// The state of the repository, as needed for a prompt
+#[derive(Default)]
+struct PromptStatus {
+    branch: String,
+    ahead: usize,
+    behind: usize,
+    staged: usize,
+    unstaged: usize,
+    untracked: usize,
+    conflicted: usize,
+    stashes: usize,
+}
+
+// Everything comes from a single `git status` call, which never walks the history, so that the
+// prompt stays fast in large repositories
+fn prompt_status() -> Option<PromptStatus> {
+    let output = Command::new("git")
+        // Don't take the index lock, which could get in the way of git commands run at the same time
+        .arg("--no-optional-locks")
+        .arg("status")
+        .arg("--porcelain=v2")
+        .arg("--branch")
+        .arg("--show-stash")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git status`");
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let mut status = PromptStatus::default();
+    let mut oid = "";
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in stdout.lines() {
+        if let Some(head) = line.strip_prefix("# branch.head ") {
+            status.branch = head.to_string();
+        } else if let Some(o) = line.strip_prefix("# branch.oid ") {
+            oid = o;
+        } else if let Some(ab) = line.strip_prefix("# branch.ab ") {
+            for count in ab.split_whitespace() {
+                if let Some(n) = count.strip_prefix('+') {
+                    status.ahead = n.parse().unwrap_or(0);
+                } else if let Some(n) = count.strip_prefix('-') {
+                    status.behind = n.parse().unwrap_or(0);
+                }
+            }
+        } else if let Some(n) = line.strip_prefix("# stash ") {
+            status.stashes = n.parse().unwrap_or(0);
+        } else if line.starts_with("? ") {
+            status.untracked += 1;
+        } else if line.starts_with("u ") {
+            status.conflicted += 1;
+        } else if let Some(xy) = line
+            .strip_prefix("1 ")
+            .or_else(|| line.strip_prefix("2 "))
+            .and_then(|l| l.get(..2))
+        {
+            let mut xy = xy.chars();
+            if xy.next() != Some('.') {
+                status.staged += 1;
+            }
+            if xy.next() != Some('.') {
+                status.unstaged += 1;
+            }
+        }
+    }
+
+    // Show the commit when HEAD is detached, as git does
+    if status.branch == "(detached)" {
+        status.branch = format!("({})", oid.get(..7).unwrap_or(oid));
+    }
+
+    Some(status)
+}
+
+// Print a single line describing the branch, how far ahead or behind its upstream it is, changes in
+// the working tree, and stashes, e.g. "main ⇡2 +1 !3 ?1 $1".  Nothing is printed outside of a
+// repository, so that the prompt stays clean
+pub fn display_prompt(shell: PromptShell, opts: &GitLogOptions) {
+    let Some(status) = prompt_status() else {
+        return;
+    };
+
+    // The prompt is always read through a pipe by the shell, which would otherwise turn colour off
+    colored::control::set_override(opts.colour);
+
+    let paint = |s: String, colour: &ThemeColour| {
+        if opts.colour {
+            colour.paint(&s).to_string()
+        } else {
+            s
+        }
+    };
+    let mut segments = vec![paint(status.branch.clone(), &THEME.ref_branch)];
+    let counts = [
+        ("⇡", status.ahead, &THEME.ref_head),
+        ("⇣", status.behind, &THEME.ref_head),
+        ("=", status.conflicted, &THEME.ref_remote),
+        ("+", status.staged, &THEME.ref_branch),
+        ("!", status.unstaged, &THEME.ref_remote),
+        ("?", status.untracked, &THEME.ref_remote),
+        ("$", status.stashes, &THEME.ref_tag),
+    ];
+    for (marker, n, colour) in counts {
+        if n > 0 {
+            segments.push(paint(format!("{}{}", marker, n), colour));
+        }
+    }
+
+    println!("{}", shell.escape(&segments.join(" ")));
+}
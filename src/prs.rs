@@ -0,0 +1,411 @@
+use super::commit::{date_filter_args, HashFormat};
+use super::forge::{self, Forge, PullRequestDetails};
+use super::identity::author_filter_args;
+use super::opts::GitLogOptions;
+use super::pathspec::exclude_pathspec_args;
+use super::topics::conventional_commit;
+use json::JsonValue;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashSet;
+use std::process::{Command, Stdio};
+use tabular::{row, Table};
+
+lazy_static! {
+    // GitHub's merge commit subject, "Merge pull request #1234 from owner/branch"
+    static ref MERGE_PR_RE: Regex = Regex::new(r"^Merge pull request #(?P<number>\d+) from (?P<branch>\S+)").unwrap();
+
+    // GitLab's merge commit body, which ends "See merge request group/project!1234"
+    static ref GITLAB_MR_RE: Regex = Regex::new(r"See merge request \S+!(?P<number>\d+)").unwrap();
+
+    // Squashed or rebased pull requests, whose subject ends in a reference to the pull request, e.g.
+    // "Fix parser bug (#1234)", or on GitLab "Fix parser bug (!1234)"
+    static ref SQUASH_PR_RE: Regex = Regex::new(r"^(?P<title>.*?)\s*\((?P<reference>[#!](?P<number>\d+))\)$").unwrap();
+}
+
+// A pull request that landed, as recorded in the commit history
+pub struct PullRequest {
+    pub number: u64,
+    pub title: String,
+    pub hash: String,
+    pub date: String,
+    // The author of the commit, or for a merge, the distinct authors of the commits it merged (those
+    // in merge^1..merge^2), in the order that they first committed
+    pub authors: Vec<String>,
+    // Fetched from the forge, if requested
+    pub details: Option<PullRequestDetails>,
+}
+
+impl PullRequest {
+    fn to_json(&self, forge: Option<&dyn Forge>) -> JsonValue {
+        json::object! {
+            number: self.number,
+            title: self.title.clone(),
+            hash: self.hash.clone(),
+            date: self.date.clone(),
+            url: forge.map(|forge| forge.pull_request_url(self.number)),
+            // The username on the forge if we have it, or else the names from the commits
+            author: self
+                .details
+                .as_ref()
+                .map_or(self.authors.join(", "), |d| d.author.clone()),
+            authors: self.authors.clone(),
+            merged_at: self.details.as_ref().and_then(|d| d.merged_at.clone()),
+            reviews: self.details.as_ref().map(|d| d.reviews),
+        }
+    }
+}
+
+// Find the pull requests that landed in the history (or range) given in the options, newest first
+pub fn pull_requests(opts: &GitLogOptions) -> Vec<PullRequest> {
+    let forge = forge::forge("origin");
+
+    // Pull requests may be merged with a merge commit or squashed into a single commit, so we look
+    // at both
+    let mut cmd = Command::new("git");
+    cmd.arg("log");
+    cmd.arg("--format=%H%x00%cs%x00%an%x00%P%x00%s%x00%b%x00");
+    if opts.first_parent {
+        cmd.arg("--first-parent");
+    }
+    cmd.args(author_filter_args(&opts.authors));
+    cmd.args(date_filter_args(opts));
+    if let Some(rev) = &opts.rev {
+        cmd.arg(rev);
+    }
+    cmd.args(exclude_pathspec_args(opts));
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git log`");
+
+    if !output.status.success() {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return vec![];
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let fields: Vec<&str> = stdout.split('\0').collect();
+    let mut seen = HashSet::new();
+    let mut prs = Vec::new();
+    let mut merges = Vec::new();
+    for entry in fields.chunks_exact(6) {
+        let hash = entry[0].trim_start_matches('\n');
+        let (date, author, parents, subject, body) =
+            (entry[1], entry[2], entry[3], entry[4], entry[5]);
+        let Some((number, title)) = parse_pull_request(subject, body, forge.as_deref()) else {
+            continue;
+        };
+
+        // A pull request may be referenced more than once (e.g. if it was reverted and relanded),
+        // in which case we keep the most recent
+        if seen.insert(number) {
+            prs.push(PullRequest {
+                number,
+                title,
+                hash: hash.to_string(),
+                date: date.to_string(),
+                authors: vec![author.to_string()],
+                details: None,
+            });
+            let mut parents = parents.split_whitespace();
+            merges.push(parents.next().zip(parents.next()));
+        }
+    }
+
+    // Whoever merged a pull request is often not who wrote it, so for merges we use the authors of
+    // the commits it merged
+    for (pr, merge) in prs.iter_mut().zip(merges) {
+        if let Some((base, tip)) = merge {
+            let authors = merged_authors(base, tip);
+            if !authors.is_empty() {
+                pr.authors = authors;
+            }
+        }
+    }
+
+    prs
+}
+
+// The distinct authors of the commits in base..tip, in the order that they first committed
+fn merged_authors(base: &str, tip: &str) -> Vec<String> {
+    let output = Command::new("git")
+        .arg("log")
+        .arg("--reverse")
+        .arg("--format=%an")
+        .arg(format!("{}..{}", base, tip))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git log`");
+
+    let mut seen = HashSet::new();
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|author| seen.insert(author.to_string()))
+        .map(String::from)
+        .collect()
+}
+
+// The number and title of the pull request that a commit landed, if any.  For merge commits the
+// title is the first non-empty line of the body, as GitHub and GitLab write it, or if the body is
+// empty, the name of the merged branch.  A squashed commit's subject must refer to the pull request
+// as the forge does (see Forge::pull_request_ref), or as GitHub does if we don't know the forge, so
+// that e.g. an issue number on GitLab isn't taken for a merge request
+fn parse_pull_request(
+    subject: &str,
+    body: &str,
+    forge: Option<&dyn Forge>,
+) -> Option<(u64, String)> {
+    let first_line = body
+        .lines()
+        .map(|l| l.trim())
+        .find(|l| !l.is_empty() && !GITLAB_MR_RE.is_match(l));
+    if let Some(caps) = MERGE_PR_RE.captures(subject) {
+        // GitHub names the branch as "owner/branch", but the branch name may itself contain slashes
+        let branch = caps["branch"]
+            .split_once('/')
+            .map_or(&caps["branch"], |(_, branch)| branch);
+        let title = first_line.unwrap_or(branch);
+        return Some((caps["number"].parse().ok()?, title.to_string()));
+    }
+    if let Some(caps) = GITLAB_MR_RE.captures(body) {
+        let title = first_line.unwrap_or(subject);
+        return Some((caps["number"].parse().ok()?, title.to_string()));
+    }
+    let caps = SQUASH_PR_RE.captures(subject.trim())?;
+    let number = caps["number"].parse().ok()?;
+    (caps["reference"] == pull_request_ref(forge, number))
+        .then(|| (number, caps["title"].to_string()))
+}
+
+fn pull_request_ref(forge: Option<&dyn Forge>, number: u64) -> String {
+    forge.map_or(format!("#{}", number), |forge| {
+        forge.pull_request_ref(number)
+    })
+}
+
+// Fetch the title, author, merge date, and number of reviews of each pull request from the forge.
+// This needs a token (see forge.rs), and results are cached between runs
+pub fn fetch_pull_request_details(prs: &mut [PullRequest]) {
+    let Some(forge) = forge::forge("origin") else {
+        eprintln!("[WARN] The origin remote is not on a known forge, so pull request details are unavailable");
+        return;
+    };
+    let Some(token) = forge.token() else {
+        eprintln!(
+            "[WARN] Set {} to fetch pull request details from {}",
+            forge.token_vars().join(" or "),
+            forge.name()
+        );
+        return;
+    };
+
+    for pr in prs.iter_mut() {
+        pr.details = forge.pull_request_details(pr.number, &token);
+        if let Some(details) = &pr.details {
+            pr.title = details.title.clone();
+        }
+    }
+}
+
+pub fn display_pull_requests(prs: Vec<PullRequest>, opts: &GitLogOptions) {
+    // Only link to pull requests if we know where they are
+    let forge = forge::forge("origin");
+
+    if opts.json {
+        let prs: Vec<JsonValue> = prs.iter().map(|pr| pr.to_json(forge.as_deref())).collect();
+        println!("{}", JsonValue::Array(prs).pretty(2));
+        return;
+    }
+
+    if prs.is_empty() {
+        println!("No pull requests found.");
+        return;
+    }
+
+    // Columns from the forge are only shown if we fetched them
+    let has_details = prs.iter().any(|pr| pr.details.is_some());
+    let mut header = row!("PR", "Commit", "Date", "Title");
+    let mut spec = String::from("{:>}  {:<}  {:<}  {:<}");
+    if has_details {
+        header
+            .add_cell("Author")
+            .add_cell("Merged")
+            .add_cell("Reviews");
+        spec.push_str("  {:<}  {:<}  {:>}");
+    }
+    if forge.is_some() {
+        header.add_cell("URL");
+        spec.push_str("  {:<}");
+    }
+
+    let mut table = Table::new(&spec).with_row(header);
+    for pr in &prs {
+        let mut row = row!(
+            format!("#{}", pr.number),
+            pr.hash.short(),
+            &pr.date,
+            &pr.title
+        );
+        if has_details {
+            match &pr.details {
+                Some(details) => row
+                    .add_cell(&details.author)
+                    // The date of the API's timestamp, e.g. "2024-01-02" of "2024-01-02T03:04:05Z"
+                    .add_cell(
+                        details
+                            .merged_at
+                            .as_deref()
+                            .map_or("", |d| d.get(..10).unwrap_or(d)),
+                    )
+                    .add_cell(details.reviews),
+                None => row.add_cell("").add_cell("").add_cell(""),
+            };
+        }
+        if let Some(forge) = &forge {
+            row.add_cell(forge.pull_request_url(pr.number));
+        }
+        table.add_row(row);
+    }
+    print!("{}", table);
+}
+
+// The sections of release notes, in the order they are written
+#[derive(Clone, Copy, PartialEq)]
+enum ReleaseNoteSection {
+    Features,
+    Fixes,
+    Other,
+}
+
+impl ReleaseNoteSection {
+    const ALL: [ReleaseNoteSection; 3] = [
+        ReleaseNoteSection::Features,
+        ReleaseNoteSection::Fixes,
+        ReleaseNoteSection::Other,
+    ];
+
+    fn heading(&self) -> &'static str {
+        match self {
+            ReleaseNoteSection::Features => "Features",
+            ReleaseNoteSection::Fixes => "Fixes",
+            ReleaseNoteSection::Other => "Other",
+        }
+    }
+
+    // Labels from the forge take precedence over the conventional commit type of the title, as
+    // they are easier to correct after the fact
+    fn of(pr: &PullRequest) -> Self {
+        let labels = pr.details.iter().flat_map(|details| &details.labels);
+        for label in labels.map(|label| label.to_lowercase()) {
+            if ["feature", "enhancement"].iter().any(|l| label.contains(l)) {
+                return ReleaseNoteSection::Features;
+            }
+            if ["bug", "fix"].iter().any(|l| label.contains(l)) {
+                return ReleaseNoteSection::Fixes;
+            }
+        }
+        match conventional_commit(&pr.title).map(|c| c.kind) {
+            Some(kind) if kind == "feat" || kind == "feature" => ReleaseNoteSection::Features,
+            Some(kind) if kind == "fix" => ReleaseNoteSection::Fixes,
+            _ => ReleaseNoteSection::Other,
+        }
+    }
+}
+
+// A pull request as a line of release notes: its title without any conventional commit prefix, a
+// reference to it (which forges link), and who wrote it.  That is whoever opened it on the forge, if we
+// fetched the details, or else everyone who authored its commits
+fn release_note(pr: &PullRequest, forge: Option<&dyn Forge>) -> String {
+    let title = conventional_commit(&pr.title).map_or(pr.title.clone(), |c| c.description);
+    let author = match &pr.details {
+        Some(details) if !details.author.is_empty() => format!("@{}", details.author),
+        _ => pr.authors.join(", "),
+    };
+    format!(
+        "- {} ({}) by {}",
+        title,
+        pull_request_ref(forge, pr.number),
+        author
+    )
+}
+
+// Print a draft of release notes in Markdown: the pull requests that landed in the range, oldest
+// first, grouped into features, fixes, and everything else
+pub fn display_release_notes(range: &str, prs: Vec<PullRequest>, opts: &GitLogOptions) {
+    let forge = forge::forge("origin");
+    let mut prs = prs;
+    prs.reverse();
+
+    if opts.json {
+        let mut value = JsonValue::new_object();
+        for section in ReleaseNoteSection::ALL {
+            let prs: Vec<JsonValue> = prs
+                .iter()
+                .filter(|pr| ReleaseNoteSection::of(pr) == section)
+                .map(|pr| pr.to_json(forge.as_deref()))
+                .collect();
+            value[section.heading().to_lowercase()] = JsonValue::Array(prs);
+        }
+        println!("{}", value.pretty(2));
+        return;
+    }
+
+    if prs.is_empty() {
+        println!("No pull requests found in {}.", range);
+        return;
+    }
+
+    println!("## What's Changed");
+    for section in ReleaseNoteSection::ALL {
+        let notes: Vec<String> = prs
+            .iter()
+            .filter(|pr| ReleaseNoteSection::of(pr) == section)
+            .map(|pr| release_note(pr, forge.as_deref()))
+            .collect();
+        if notes.is_empty() {
+            continue;
+        }
+        println!();
+        println!("### {}", section.heading());
+        println!();
+        for note in notes {
+            println!("{}", note);
+        }
+    }
+
+    // The forge can only compare refs it knows about, so we don't link to a comparison with HEAD
+    if let (Some(forge), Some((from, to))) = (&forge, range.split_once("..")) {
+        let (from, to) = (from.trim_end_matches('.'), to.trim_start_matches('.'));
+        if !from.is_empty() && !to.is_empty() && to != "HEAD" {
+            println!();
+            println!("**Full Changelog**: {}", forge.compare_url(from, to));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn titles_merged_pull_requests_from_the_body() {
+        let subject = "Merge pull request #12 from jake/fix/parser";
+        assert_eq!(
+            parse_pull_request(subject, "\n  Fix the parser  \n\nMore detail\n", None),
+            Some((12, String::from("Fix the parser")))
+        );
+        assert_eq!(
+            parse_pull_request(subject, "\n", None),
+            Some((12, String::from("fix/parser")))
+        );
+        assert_eq!(
+            parse_pull_request("Fix the parser (#13)", "", None),
+            Some((13, String::from("Fix the parser")))
+        );
+        // Only GitLab refers to merge requests with "!"
+        assert_eq!(parse_pull_request("Fix the parser (!13)", "", None), None);
+    }
+}
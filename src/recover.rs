@@ -0,0 +1,328 @@
+use super::commit::HashFormat;
+use super::opts::GitLogOptions;
+use super::theme::THEME;
+use json::JsonValue;
+use std::collections::HashSet;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tabular::{row, Table};
+
+// A commit whose message matches the search, and where we found it
+pub struct LostCommit {
+    pub hash: String,
+    pub date: String,
+    pub subject: String,
+    // The reflog entry that refers to the commit (e.g. "HEAD@{3}"), or None if the commit is only
+    // dangling in the object store
+    pub reflog: Option<String>,
+    // Branches that still contain the commit; if there are none, the commit is lost
+    pub branches: Vec<String>,
+}
+
+impl LostCommit {
+    fn is_lost(&self) -> bool {
+        self.branches.is_empty()
+    }
+
+    fn to_json(&self) -> JsonValue {
+        json::object! {
+            hash: self.hash.clone(),
+            date: self.date.clone(),
+            subject: self.subject.clone(),
+            reflog: self.reflog.clone(),
+            branches: self.branches.clone(),
+            lost: self.is_lost(),
+        }
+    }
+}
+
+// Search the reflogs of all refs, then commits that are no longer referenced by anything (e.g.
+// after a branch was deleted and its reflog expired, or a stash was dropped), for commits whose
+// message contains the given text (ignoring case)
+pub fn find_lost_commits(text: &str) -> Vec<LostCommit> {
+    let needle = text.to_lowercase();
+    let mut seen = HashSet::new();
+    let mut commits = Vec::new();
+
+    // git log --walk-reflogs --all --format=...
+    let reflog_entries = git_log_entries(
+        &["--walk-reflogs", "--all"],
+        None,
+        "%H%x00%gd%x00%cs%x00%s%x00%B",
+    );
+    let unreachable = fsck_commits("--unreachable");
+    let dangling_entries = if unreachable.is_empty() {
+        vec![]
+    } else {
+        git_log_entries(
+            &["--no-walk"],
+            Some(&unreachable),
+            "%H%x00%x00%cs%x00%s%x00%B",
+        )
+    };
+
+    for [hash, selector, date, subject, body] in reflog_entries.into_iter().chain(dangling_entries)
+    {
+        if !body.to_lowercase().contains(&needle) || !seen.insert(hash.clone()) {
+            continue;
+        }
+        commits.push(LostCommit {
+            branches: branches_containing(&hash),
+            hash,
+            date,
+            subject,
+            reflog: if selector.is_empty() {
+                None
+            } else {
+                Some(selector)
+            },
+        });
+    }
+
+    commits
+}
+
+pub fn display_lost_commits(commits: Vec<LostCommit>, opts: &GitLogOptions) {
+    if opts.json {
+        let commits: Vec<JsonValue> = commits.iter().map(|c| c.to_json()).collect();
+        println!("{}", JsonValue::Array(commits).pretty(2));
+        return;
+    }
+
+    if commits.is_empty() {
+        println!("No matching commits found in the reflog or object store.");
+        return;
+    }
+
+    for commit in &commits {
+        let hash = commit.hash.short();
+        let found = match (&commit.reflog, commit.is_lost()) {
+            (_, false) => format!("on {}", commit.branches.join(", ")),
+            (Some(selector), true) => format!("lost; last seen at {}", selector),
+            (None, true) => String::from("lost; dangling"),
+        };
+        if opts.colour {
+            println!(
+                "{} {} {} {}",
+                THEME.hash.paint(&hash),
+                THEME.date.paint(&commit.date),
+                commit.subject,
+                THEME.refs.paint(&format!("({})", found))
+            );
+        } else {
+            println!("{} {} {} ({})", hash, commit.date, commit.subject, found);
+        }
+    }
+
+    if let Some(lost) = commits.iter().find(|c| c.is_lost()) {
+        println!(
+            "\nTo recover a lost commit, create a branch at it, e.g.:\n  git branch rescue {}",
+            lost.hash.short()
+        );
+    }
+}
+
+// The tip of a chain of commits that no branch, tag, or other ref points to, e.g. left behind by a
+// rebase, an amend, or deleting an unmerged branch
+pub struct DanglingCommit {
+    pub hash: String,
+    pub timestamp: i64,
+    pub age: String,
+    pub author: String,
+    pub subject: String,
+    // The commits reachable from this one but from no ref, including itself
+    pub unreachable: usize,
+    // Whether a reflog still refers to it.  If so, gc keeps it until the reflog entry expires (by
+    // default after 30 days); otherwise the next gc may delete it
+    pub in_reflog: bool,
+}
+
+// The dangling commits in the object store, newest first.  As in find_lost_commits, reflogs are
+// not counted as refs, so commits only a reflog refers to are included
+pub fn dangling_commits() -> Vec<DanglingCommit> {
+    let dangling = fsck_commits("--dangling");
+    if dangling.is_empty() {
+        return vec![];
+    }
+
+    let in_reflog: HashSet<String> =
+        git_log_entries(&["--walk-reflogs", "--all"], None, "%H%x00%x00%x00%x00")
+            .into_iter()
+            .map(|[hash, ..]| hash)
+            .collect();
+    let mut commits: Vec<DanglingCommit> = git_log_entries(
+        &["--no-walk"],
+        Some(&dangling),
+        "%H%x00%ct%x00%cr%x00%an%x00%s",
+    )
+    .into_iter()
+    .map(|[hash, timestamp, age, author, subject]| DanglingCommit {
+        unreachable: unreachable_count(&hash),
+        in_reflog: in_reflog.contains(&hash),
+        timestamp: timestamp.parse().unwrap_or(0),
+        hash,
+        age,
+        author,
+        subject,
+    })
+    .collect();
+    commits.sort_by_key(|commit| std::cmp::Reverse(commit.timestamp));
+    commits
+}
+
+pub fn display_dangling_commits(commits: Vec<DanglingCommit>, opts: &GitLogOptions) {
+    if opts.json {
+        let commits: Vec<JsonValue> = commits
+            .iter()
+            .map(|commit| {
+                json::object! {
+                    hash: commit.hash.clone(),
+                    timestamp: commit.timestamp,
+                    author: commit.author.clone(),
+                    subject: commit.subject.clone(),
+                    unreachable: commit.unreachable,
+                    in_reflog: commit.in_reflog,
+                }
+            })
+            .collect();
+        println!("{}", JsonValue::Array(commits).pretty(2));
+        return;
+    }
+
+    if commits.is_empty() {
+        println!("No dangling commits.");
+        return;
+    }
+
+    let mut table = Table::new("{:<}  {:<}  {:<}  {:>}  {:<}  {:<}").with_row(row!(
+        "Commit", "Age", "Author", "Commits", "Reflog", "Subject"
+    ));
+    for commit in &commits {
+        let hash = commit.hash.short();
+        let (hash, age, author) = if opts.colour {
+            (
+                THEME.hash.paint(&hash).to_string(),
+                THEME.date.paint(&commit.age).to_string(),
+                THEME.author.paint(&commit.author).to_string(),
+            )
+        } else {
+            (hash, commit.age.clone(), commit.author.clone())
+        };
+        table.add_row(row!(
+            hash,
+            age,
+            author,
+            commit.unreachable,
+            if commit.in_reflog { "yes" } else { "no" },
+            &commit.subject
+        ));
+    }
+    print!("{}", table);
+
+    if commits.iter().any(|c| !c.in_reflog) {
+        println!("\nCommits in no reflog may be deleted by the next `git gc`.");
+    }
+    println!(
+        "To recover a commit, create a branch at it, e.g.:\n  git branch rescue {}",
+        commits[0].hash.short()
+    );
+}
+
+// The number of commits reachable from the given one that no ref can reach
+fn unreachable_count(hash: &str) -> usize {
+    let output = Command::new("git")
+        .args(["rev-list", "--count", hash, "--not", "--all"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git rev-list`");
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .unwrap_or(1)
+}
+
+// Run `git log` with the given arguments and a format of five NUL-separated fields, where the last
+// field is the commit's full message.  Entries are separated by a NUL after the message.  Commits to
+// start from are given on stdin rather than as arguments, as there may be too many for the command
+// line (e.g. every unreachable commit after a large rebase)
+fn git_log_entries(args: &[&str], hashes: Option<&[String]>, format: &str) -> Vec<[String; 5]> {
+    let mut cmd = Command::new("git");
+    cmd.arg("log");
+    cmd.args(args);
+    if hashes.is_some() {
+        cmd.arg("--stdin");
+    }
+    cmd.arg(format!("--format={}%x00", format));
+
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to execute `git log`");
+    // Dropping stdin closes it, so that git doesn't wait for more
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(hashes.unwrap_or_default().join("\n").as_bytes());
+    }
+    let output = child
+        .wait_with_output()
+        .expect("Failed to wait for `git log`");
+
+    if !output.status.success() {
+        return vec![];
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let fields: Vec<&str> = stdout.split('\0').collect();
+    fields
+        .chunks_exact(5)
+        .map(|entry| {
+            let field = |i: usize| entry[i].trim_start_matches('\n').to_string();
+            [field(0), field(1), field(2), field(3), field(4)]
+        })
+        .collect()
+}
+
+// Commits in the object store that no ref can reach (whether or not a reflog still refers to them):
+// all of them with "--unreachable", or only those that no other such commit has as a parent with
+// "--dangling"
+fn fsck_commits(kind: &str) -> Vec<String> {
+    let mut cmd = Command::new("git");
+    cmd.arg("fsck");
+    cmd.arg(kind);
+    cmd.arg("--no-reflogs");
+    cmd.arg("--no-progress");
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git fsck`");
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.strip_prefix(&format!("{} commit ", &kind[2..])))
+        .map(|hash| hash.trim().to_string())
+        .collect()
+}
+
+fn branches_containing(hash: &str) -> Vec<String> {
+    let mut cmd = Command::new("git");
+    cmd.arg("branch");
+    cmd.arg("--all");
+    cmd.arg("--format=%(refname:short)");
+    cmd.arg("--contains");
+    cmd.arg(hash);
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git branch`");
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(String::from)
+        .collect()
+}
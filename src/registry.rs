@@ -0,0 +1,200 @@
+use super::opts::GitLogOptions;
+use super::repo;
+use super::settings;
+use super::theme::THEME;
+use json::JsonValue;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tabular::{row, Table};
+
+// The repositories under your base directory are registered by short name, so that gl can be run
+// on any of them from anywhere with --repo <name>.  The registry lives in $XDG_CONFIG_HOME/gl/repos
+// (or ~/.config/gl/repos), with a name and path on each line, and is rebuilt whenever a name is not
+// found in it or --list-repos is run
+fn registry_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("gl").join("repos"))
+}
+
+pub struct RegisteredRepo {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+// Repositories are named by their directory, unless several share a directory name, in which case
+// those are named by their path relative to the base directory (e.g. "work/api" and "home/api")
+fn repo_names(base: &Path, paths: Vec<PathBuf>) -> Vec<RegisteredRepo> {
+    let dir_name = |path: &Path| {
+        path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        )
+    };
+    let mut dir_names: HashMap<String, usize> = HashMap::new();
+    for path in &paths {
+        *dir_names.entry(dir_name(path)).or_insert(0) += 1;
+    }
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let name = dir_name(&path);
+            let name = if dir_names[&name] > 1 {
+                path.strip_prefix(base)
+                    .map_or(name, |rel| rel.to_string_lossy().into_owned())
+            } else {
+                name
+            };
+            RegisteredRepo { name, path }
+        })
+        .collect()
+}
+
+fn parse_registry(contents: &str) -> Vec<RegisteredRepo> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (name, path) = line.split_once('\t')?;
+            Some(RegisteredRepo {
+                name: name.to_string(),
+                path: PathBuf::from(path),
+            })
+        })
+        .collect()
+}
+
+fn read_registry() -> Option<Vec<RegisteredRepo>> {
+    Some(parse_registry(&fs::read_to_string(registry_path()?).ok()?))
+}
+
+// Scan the base directory for repositories and save them to the registry.  As with the cache,
+// failing to save is not an error
+pub fn scan() -> Vec<RegisteredRepo> {
+    let base = settings::base_dir();
+    let repos = repo_names(&base, repo::repos_under(&base));
+
+    if let Some(path) = registry_path() {
+        let contents: String = repos
+            .iter()
+            .map(|repo| format!("{}\t{}\n", repo.name, repo.path.display()))
+            .collect();
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, contents));
+        if let Err(e) = written {
+            eprintln!(
+                "[WARN] Unable to write repository registry at {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+    repos
+}
+
+// The path of the registered repository with the given name.  If it isn't registered (or has moved
+// since), the base directory is scanned again
+pub fn find(name: &str) -> Option<PathBuf> {
+    let lookup = |repos: Vec<RegisteredRepo>| {
+        repos
+            .into_iter()
+            .find(|repo| repo.name == name && repo.path.exists())
+            .map(|repo| repo.path)
+    };
+    read_registry().and_then(lookup).or_else(|| lookup(scan()))
+}
+
+// The current branch of the repository at the given path, if it has any commits
+fn repo_branch(path: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .arg("rev-parse")
+        .arg("--abbrev-ref")
+        .arg("HEAD")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git rev-parse`");
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+pub fn display_registered_repos(repos: Vec<RegisteredRepo>, opts: &GitLogOptions) {
+    let repos: Vec<(RegisteredRepo, Option<String>)> = repos
+        .into_iter()
+        .map(|repo| {
+            let branch = repo_branch(&repo.path);
+            (repo, branch)
+        })
+        .collect();
+
+    if opts.json {
+        let repos: Vec<JsonValue> = repos
+            .iter()
+            .map(|(repo, branch)| {
+                json::object! {
+                    name: repo.name.clone(),
+                    path: repo.path.display().to_string(),
+                    branch: branch.clone(),
+                }
+            })
+            .collect();
+        println!("{}", JsonValue::Array(repos).pretty(2));
+        return;
+    }
+
+    if repos.is_empty() {
+        println!(
+            "No repositories found under {}.",
+            settings::base_dir().display()
+        );
+        return;
+    }
+
+    // The branch is last so that colouring it doesn't throw out the alignment
+    let mut table = Table::new("{:<}  {:<}  {:<}");
+    for (repo, branch) in &repos {
+        // A detached HEAD has no branch name
+        let branch = match branch.as_deref() {
+            Some("HEAD") => String::from("(detached)"),
+            Some(branch) => branch.to_string(),
+            None => String::from("(no commits)"),
+        };
+        let branch = if opts.colour {
+            THEME.ref_branch.paint(&branch).to_string()
+        } else {
+            branch
+        };
+        table.add_row(row!(&repo.name, repo.path.display(), branch));
+    }
+    print!("{}", table);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_repos_by_directory() {
+        let base = Path::new("/p");
+        let paths = ["/p/gl", "/p/home/api", "/p/work/api"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        let names: Vec<String> = repo_names(base, paths)
+            .into_iter()
+            .map(|repo| repo.name)
+            .collect();
+        assert_eq!(names, ["gl", "home/api", "work/api"]);
+    }
+}
@@ -0,0 +1,164 @@
+use super::interrupt;
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long git is given to clean up after being asked to stop a fetch, before it is killed
+const FETCH_STOP_GRACE: Duration = Duration::from_secs(2);
+
+// The web address of a repository hosted on a forge, derived from its remote URL
+#[derive(Clone, PartialEq)]
+pub struct RemoteRepo {
+    pub host: String,
+    pub path: String,
+}
+
+impl RemoteRepo {
+    // Parse a remote URL in any of the forms git accepts for a hosted repository, e.g.
+    // "git@github.com:owner/repo.git", "ssh://git@github.com/owner/repo", or
+    // "https://github.com/owner/repo.git"
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = url.trim();
+        let (host, path) = if let Some((_scheme, rest)) = url.split_once("://") {
+            let rest = rest.rsplit_once('@').map_or(rest, |(_user, rest)| rest);
+            rest.split_once('/')?
+        } else {
+            // scp-like syntax, "[user@]host:path"
+            let (host, path) = url.split_once(':')?;
+            (
+                host.rsplit_once('@').map_or(host, |(_user, host)| host),
+                path,
+            )
+        };
+
+        // Ignore any port in the host, as the web interface is unlikely to be served on it
+        let host = host.split(':').next().unwrap_or(host);
+        let path = path.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        if host.is_empty() || !path.contains('/') {
+            return None;
+        }
+
+        Some(RemoteRepo {
+            host: host.to_string(),
+            path: path.to_string(),
+        })
+    }
+
+    pub fn web_url(&self) -> String {
+        format!("https://{}/{}", self.host, self.path)
+    }
+}
+
+// The hosted repository that the given remote points to, if any
+pub fn remote_repo(remote: &str) -> Option<RemoteRepo> {
+    let output = Command::new("git")
+        .arg("remote")
+        .arg("get-url")
+        .arg(remote)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git remote`");
+
+    if output.status.success() {
+        RemoteRepo::parse(&String::from_utf8_lossy(&output.stdout))
+    } else {
+        None
+    }
+}
+
+// The remote of the current branch's upstream, or "origin" if it has none
+fn default_remote() -> String {
+    let output = Command::new("git")
+        .arg("rev-parse")
+        .arg("--abbrev-ref")
+        .arg("--symbolic-full-name")
+        .arg("@{upstream}")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git rev-parse`");
+    let upstream = String::from_utf8_lossy(&output.stdout);
+    match upstream.trim().split_once('/') {
+        Some((remote, _)) if output.status.success() => remote.to_string(),
+        _ => String::from("origin"),
+    }
+}
+
+// Fetch from the default remote, so that reports of how far ahead or behind branches are reflect
+// the remote as it is now.  If the fetch fails or takes longer than the timeout (e.g. when offline),
+// we carry on with what was last fetched.  Returns whether the fetch succeeded
+pub fn fetch(timeout: Duration) -> bool {
+    let remote = default_remote();
+    // Never prompt for credentials, which would hang until the timeout
+    let mut cmd = Command::new("git");
+    cmd.arg("fetch")
+        .arg("--quiet")
+        .arg("--prune")
+        .arg(&remote)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // In its own process group, so that stopping the fetch also stops the ssh or
+    // git-remote-https that git started for it (see stop_fetch)
+    #[cfg(unix)]
+    cmd.process_group(0);
+    let Ok(mut child) = cmd.spawn() else {
+        eprintln!("[WARN] Failed to execute `git fetch`; showing the last fetched state");
+        return false;
+    };
+
+    let start = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return true,
+            Ok(Some(_)) | Err(_) => {
+                eprintln!(
+                    "[WARN] Unable to fetch from {:?} (are you offline?); showing the last fetched state",
+                    remote
+                );
+                return false;
+            }
+            // Being in its own process group, git doesn't see Ctrl-C, so we stop it ourselves
+            Ok(None) if interrupt::interrupted() => {
+                stop_fetch(&mut child);
+                return false;
+            }
+            Ok(None) if start.elapsed() >= timeout => {
+                stop_fetch(&mut child);
+                eprintln!(
+                    "[WARN] Fetching from {:?} timed out after {}s; showing the last fetched state",
+                    remote,
+                    timeout.as_secs()
+                );
+                return false;
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(50)),
+        }
+    }
+}
+
+// Ask git to stop fetching, so that it can remove its lock files (e.g. refs/remotes/*.lock, which
+// would otherwise break the next fetch), and only kill it if it hasn't stopped shortly after
+fn stop_fetch(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        // The negative ID signals the whole process group
+        unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGTERM) };
+        let start = Instant::now();
+        while start.elapsed() < FETCH_STOP_GRACE {
+            if let Ok(Some(_)) = child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
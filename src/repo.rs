@@ -1,3 +1,5 @@
+use super::timings;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
@@ -5,10 +7,11 @@ pub fn top_level_repo_path() -> Option<String> {
     let mut cmd = Command::new("git");
     cmd.arg("rev-parse");
     cmd.arg("--show-toplevel");
-    let output = cmd
-        .stdout(Stdio::piped())
-        .output()
-        .expect("Failed to execute `git rev-parse`");
+    let output = timings::time("repo discovery", || {
+        cmd.stdout(Stdio::piped())
+            .output()
+            .expect("Failed to execute `git rev-parse`")
+    });
 
     if output.status.success() {
         let mut current_repo_path = String::from_utf8_lossy(&output.stdout).into_owned();
@@ -26,17 +29,229 @@ pub fn top_level_repo_path() -> Option<String> {
     }
 }
 
-pub fn current_repository() -> Option<String> {
-    let current_repo_path = top_level_repo_path();
+// Returns the set of files that git tracks or could track (i.e., untracked files that are not
+// ignored), respecting nested .gitignore files, .git/info/exclude, and core.excludesFile
+pub fn trackable_files(top_level_path: &str) -> Option<HashSet<PathBuf>> {
+    let mut cmd = Command::new("git");
+    cmd.arg("-C");
+    cmd.arg(top_level_path);
+    cmd.arg("ls-files");
+    cmd.arg("--cached");
+    cmd.arg("--others");
+    cmd.arg("--exclude-standard");
+    cmd.arg("-z");
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git ls-files`");
+
+    if output.status.success() {
+        let top_level_path = Path::new(top_level_path);
+        let files = String::from_utf8_lossy(&output.stdout)
+            .split_terminator('\0')
+            .map(|f| top_level_path.join(f))
+            .collect();
+        Some(files)
+    } else {
+        None
+    }
+}
+
+// Git uses the commit-graph file (when present) to speed up revision walks, which all of our `git
+// log`/`git rev-list` calls benefit from automatically (as core.commitGraph is enabled by default)
+pub fn commit_graph_exists() -> bool {
+    [
+        "objects/info/commit-graph",
+        "objects/info/commit-graphs/commit-graph-chain",
+    ]
+    .iter()
+    .filter_map(|p| git_path(p))
+    .any(|p| p.exists())
+}
+
+// Write the commit-graph for all reachable commits if it doesn't already exist
+pub fn write_commit_graph() {
+    if commit_graph_exists() {
+        println!("Commit-graph already exists; nothing to do.");
+        return;
+    }
+
+    let mut cmd = Command::new("git");
+    cmd.arg("commit-graph");
+    cmd.arg("write");
+    cmd.arg("--reachable");
+    cmd.arg("--changed-paths");
+
+    let status = cmd.status().expect("Failed to execute `git commit-graph`");
+
+    if status.success() {
+        println!("Commit-graph written.");
+    } else {
+        println!("An error has occured while writing the commit-graph.  It is likely that you aren't in a git repository, or your version of `git` is too old.");
+    }
+}
+
+// A partial clone leaves some objects (e.g. all blobs, with --filter=blob:none) on the remote, and
+// git fetches them lazily whenever they are needed
+pub struct PartialClone {
+    pub remote: String,
+    pub filter: Option<String>,
+}
+
+// The promisor remote, if the repository is a partial clone
+pub fn partial_clone() -> Option<PartialClone> {
+    let output = Command::new("git")
+        .arg("config")
+        .arg("--null")
+        .arg("--get-regexp")
+        .arg(r"^remote\..*\.(promisor|partialclonefilter)$")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git config`");
+
+    // Each entry is "remote.<name>.<key>\n<value>\0"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut partial_clone: Option<PartialClone> = None;
+    let mut filters: Vec<(&str, &str)> = Vec::new();
+    for entry in stdout.split_terminator('\0') {
+        let Some((key, value)) = entry.split_once('\n') else {
+            continue;
+        };
+        let Some(remote) = key.strip_prefix("remote.") else {
+            continue;
+        };
+        if let Some(remote) = remote.strip_suffix(".promisor") {
+            if value == "true" && partial_clone.is_none() {
+                partial_clone = Some(PartialClone {
+                    remote: remote.to_string(),
+                    filter: None,
+                });
+            }
+        } else if let Some(remote) = remote.strip_suffix(".partialclonefilter") {
+            filters.push((remote, value));
+        }
+    }
+
+    let mut partial_clone = partial_clone?;
+    partial_clone.filter = filters
+        .into_iter()
+        .find(|(remote, _)| *remote == partial_clone.remote)
+        .map(|(_, filter)| filter.to_string());
+    Some(partial_clone)
+}
 
-    if let Some(current_repo_path) = current_repo_path {
-        // get the basename of the path
-        let repo_path = Path::new(&current_repo_path);
-        let repo_path_basename = PathBuf::from(repo_path.file_name().unwrap());
+// Whether only some of the tracked files are checked out in the working tree
+pub fn is_sparse_checkout() -> bool {
+    let output = Command::new("git")
+        .arg("config")
+        .arg("--bool")
+        .arg("core.sparseCheckout")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git config`");
+    String::from_utf8_lossy(&output.stdout).trim() == "true"
+}
 
-        let repo_basename_str: String = repo_path_basename.into_os_string().into_string().unwrap();
+// How deep below the base directory to look for repositories (e.g. ~/projects/work/client/repo)
+const MAX_REPO_DEPTH: usize = 3;
 
-        Some(repo_basename_str)
+// The repositories under the given directory, sorted by path.  We don't look inside repositories
+// (for submodules or nested clones) or hidden directories
+pub fn repos_under(base: &Path) -> Vec<PathBuf> {
+    fn walk(dir: &Path, depth: usize, repos: &mut Vec<PathBuf>) {
+        if dir.join(".git").exists() {
+            repos.push(dir.to_path_buf());
+            return;
+        }
+        if depth == MAX_REPO_DEPTH {
+            return;
+        }
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if !hidden && entry.file_type().is_ok_and(|t| t.is_dir()) {
+                walk(&entry.path(), depth + 1, repos);
+            }
+        }
+    }
+
+    let mut repos = Vec::new();
+    walk(base, 0, &mut repos);
+    repos.sort();
+    repos
+}
+
+// Whether a revision (branch, tag, hash, etc.) resolves to a commit
+pub fn rev_exists(rev: &str) -> bool {
+    let mut cmd = Command::new("git");
+    cmd.arg("rev-parse");
+    cmd.arg("--verify");
+    cmd.arg("--quiet");
+    cmd.arg(format!("{}^{{commit}}", rev));
+    cmd.stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+// The full hashes of the commits whose hash starts with the given prefix (of at least four hex
+// digits, as for git), including commits that are no longer reachable
+pub fn commits_with_prefix(prefix: &str) -> Vec<String> {
+    let mut cmd = Command::new("git");
+    cmd.arg("rev-parse");
+    cmd.arg(format!("--disambiguate={}", prefix));
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git rev-parse`");
+
+    // Any kind of object may match the prefix, so we keep only the commits
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|hash| object_type(hash).as_deref() == Some("commit"))
+        .map(String::from)
+        .collect()
+}
+
+fn object_type(hash: &str) -> Option<String> {
+    let mut cmd = Command::new("git");
+    cmd.arg("cat-file");
+    cmd.arg("-t");
+    cmd.arg(hash);
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git cat-file`");
+
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        None
+    }
+}
+
+// Resolve a path inside the git directory (e.g., "objects/info/commit-graph")
+pub fn git_path(path: &str) -> Option<PathBuf> {
+    let mut cmd = Command::new("git");
+    cmd.arg("rev-parse");
+    cmd.arg("--git-path");
+    cmd.arg(path);
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git rev-parse`");
+
+    if output.status.success() {
+        Some(PathBuf::from(
+            String::from_utf8_lossy(&output.stdout).trim_end(),
+        ))
     } else {
         None
     }
@@ -0,0 +1,229 @@
+use super::commit::date_filter_args;
+use super::opts::GitLogOptions;
+use super::trailers::parse_trailers;
+use chrono::{DateTime, Local};
+use json::JsonValue;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::process::{Command, Stdio};
+use tabular::{row, Table};
+
+const HOUR: i64 = 60 * 60;
+const DAY: i64 = 24 * HOUR;
+
+// Commit message trailers that show a commit was reviewed before it landed
+const REVIEW_TRAILERS: [&str; 2] = ["Reviewed-by", "Approved-by"];
+
+struct LogCommit {
+    hash: String,
+    parents: Vec<String>,
+    author_time: i64,
+    commit_time: i64,
+    reviewed: bool,
+}
+
+// How long a commit took to land on the main line of history, from when it was authored
+struct Landed {
+    landed_time: i64,
+    latency: i64,
+}
+
+// Review latency for the commits that landed in one month
+pub struct MonthLatency {
+    pub month: String,
+    pub commits: usize,
+    pub median: i64,
+    pub p75: i64,
+    pub p90: i64,
+}
+
+fn log_commits(opts: &GitLogOptions) -> Option<Vec<LogCommit>> {
+    let mut cmd = Command::new("git");
+    cmd.arg("log");
+    cmd.arg("--format=%H%x00%P%x00%at%x00%ct%x00%B%x00");
+    cmd.args(date_filter_args(opts));
+    if let Some(rev) = &opts.rev {
+        cmd.arg(rev);
+    }
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .output()
+        .expect("Failed to execute `git log`");
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let fields: Vec<&str> = stdout.split('\0').collect();
+    let commits = fields
+        .chunks_exact(5)
+        .map(|entry| LogCommit {
+            hash: entry[0].trim_start_matches('\n').to_string(),
+            parents: entry[1].split_whitespace().map(String::from).collect(),
+            author_time: entry[2].parse().unwrap_or(0),
+            commit_time: entry[3].parse().unwrap_or(0),
+            reviewed: parse_trailers(entry[4]).iter().any(|trailer| {
+                REVIEW_TRAILERS
+                    .iter()
+                    .any(|key| trailer.key.eq_ignore_ascii_case(key))
+            }),
+        })
+        .collect();
+    Some(commits)
+}
+
+// Work out when each reviewed commit landed.  A commit brought in by a merge on the first-parent
+// history landed when the merge was committed; a commit made directly on the first-parent history
+// landed when it was committed, and counts only if it has a review trailer (e.g. a patch applied
+// by a maintainer after review on a mailing list)
+fn landed_commits(commits: &[LogCommit]) -> Vec<Landed> {
+    let index: HashMap<&str, &LogCommit> = commits.iter().map(|c| (c.hash.as_str(), c)).collect();
+
+    // The log starts at the tip, so its first-parent history is found by following first parents
+    // from the first commit
+    let mut first_parent: Vec<&LogCommit> = Vec::new();
+    let mut next = commits.first();
+    while let Some(commit) = next {
+        first_parent.push(commit);
+        next = commit
+            .parents
+            .first()
+            .and_then(|parent| index.get(parent.as_str()).copied());
+    }
+    let on_first_parent: HashSet<&str> = first_parent.iter().map(|c| c.hash.as_str()).collect();
+
+    // Go from oldest to newest, so that a commit merged more than once (e.g. through a long-lived
+    // branch) is attributed to the first merge that brought it in
+    let mut landed = Vec::new();
+    let mut seen: HashSet<&str> = HashSet::new();
+    for commit in first_parent.iter().rev() {
+        if commit.parents.len() <= 1 {
+            if commit.reviewed {
+                landed.push(Landed {
+                    landed_time: commit.commit_time,
+                    latency: commit.commit_time - commit.author_time,
+                });
+            }
+            continue;
+        }
+
+        let mut stack: Vec<&str> = commit.parents[1..].iter().map(String::as_str).collect();
+        while let Some(hash) = stack.pop() {
+            if on_first_parent.contains(hash) || !seen.insert(hash) {
+                continue;
+            }
+            let Some(merged) = index.get(hash) else {
+                continue;
+            };
+            // Merges of the main line into the branch aren't work that needed review
+            if merged.parents.len() <= 1 {
+                landed.push(Landed {
+                    landed_time: commit.commit_time,
+                    latency: commit.commit_time - merged.author_time,
+                });
+            }
+            stack.extend(merged.parents.iter().map(String::as_str));
+        }
+    }
+
+    landed
+}
+
+// The value below which the given fraction of the (sorted) values fall, by the nearest-rank method
+fn percentile(sorted: &[i64], fraction: f64) -> i64 {
+    let rank = (fraction * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn month_latency(month: String, mut latencies: Vec<i64>) -> MonthLatency {
+    latencies.sort_unstable();
+    MonthLatency {
+        month,
+        commits: latencies.len(),
+        median: percentile(&latencies, 0.5),
+        p75: percentile(&latencies, 0.75),
+        p90: percentile(&latencies, 0.9),
+    }
+}
+
+// Compute, for each month, how long commits took to land after they were authored, as a proxy
+// for review turnaround.  The last entry covers the whole period
+pub fn review_latency(opts: &GitLogOptions) -> Option<Vec<MonthLatency>> {
+    let commits = log_commits(opts)?;
+
+    let mut months: BTreeMap<String, Vec<i64>> = BTreeMap::new();
+    let mut all = Vec::new();
+    for landed in landed_commits(&commits) {
+        // Author dates can be later than commit dates if clocks disagree
+        let latency = landed.latency.max(0);
+        let month = DateTime::from_timestamp(landed.landed_time, 0)
+            .map(|d| d.with_timezone(&Local).format("%Y-%m").to_string())
+            .unwrap_or_default();
+        months.entry(month).or_default().push(latency);
+        all.push(latency);
+    }
+
+    if all.is_empty() {
+        return Some(Vec::new());
+    }
+    let mut latencies: Vec<MonthLatency> = months
+        .into_iter()
+        .map(|(month, latencies)| month_latency(month, latencies))
+        .collect();
+    latencies.push(month_latency(String::from("All"), all));
+    Some(latencies)
+}
+
+fn format_latency(seconds: i64) -> String {
+    if seconds < DAY {
+        format!("{:.1} hours", seconds as f64 / HOUR as f64)
+    } else {
+        format!("{:.1} days", seconds as f64 / DAY as f64)
+    }
+}
+
+pub fn display_review_latency(opts: &GitLogOptions) {
+    let Some(latencies) = review_latency(opts) else {
+        println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
+        return;
+    };
+
+    if opts.json {
+        let latencies: Vec<JsonValue> = latencies
+            .iter()
+            .map(|m| {
+                json::object! {
+                    month: m.month.clone(),
+                    commits: m.commits,
+                    median_hours: m.median as f64 / HOUR as f64,
+                    p75_hours: m.p75 as f64 / HOUR as f64,
+                    p90_hours: m.p90 as f64 / HOUR as f64,
+                }
+            })
+            .collect();
+        println!("{}", JsonValue::Array(latencies).pretty(2));
+        return;
+    }
+
+    if latencies.is_empty() {
+        println!(
+            "No merged or reviewed commits found.  Review latency needs merge commits or {} trailers.",
+            REVIEW_TRAILERS.join("/")
+        );
+        return;
+    }
+
+    let mut table = Table::new("{:<}  {:>}  {:>}  {:>}  {:>}")
+        .with_row(row!("Month", "Commits", "Median", "75th", "90th"));
+    for m in &latencies {
+        table.add_row(row!(
+            &m.month,
+            m.commits,
+            format_latency(m.median),
+            format_latency(m.p75),
+            format_latency(m.p90)
+        ));
+    }
+    print!("{}", table);
+}
@@ -0,0 +1,415 @@
+use super::config;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+// Settings are resolved once at startup: each starts as the value in the config file, which may be
+// overridden for a particular repository in the [gl] section of its git config (e.g. `git config
+// gl.defaultN 20`), which may in turn be overridden by an environment variable:
+//
+//   GL_DEFAULT_N                    gl.defaultN                 Number of commits shown by default
+//   GL_IDENTITY                     gl.identity                 Your identities (names or emails)
+//   GL_PALETTE                      gl.palette                  Colour palette ("default",
+//                                                               "colourblind", or "monochrome")
+//   GL_THEME                        gl.theme                    Theme colours, e.g.
+//                                                               "hash=bold cyan,date=#ff8800"
+//                                                               (elements: hash, refs, head,
+//                                                               branch, remote, tag, date,
+//                                                               author, me, bot, graph, added,
+//                                                               removed, hunk)
+//   GL_SYNTAX_THEME                 gl.syntaxTheme              Colour scheme for syntax in
+//                                                               patches ("" for none)
+//   GL_BOT_PATTERNS                 gl.botPatterns              Regex patterns for bot authors
+//   GL_SHORT_HASH_LENGTH            gl.shortHashLength          Length of abbreviated hashes
+//   GL_LANGUAGE_BAR_MAX_WIDTH       gl.languageBarMaxWidth      Maximum width of language bars
+//   GL_LANGUAGE_HISTORY_TOP_N       gl.languageHistoryTopN      Number of languages tracked in
+//                                                               language history
+//   GL_COMPARE_TOP_N_FILES          gl.compareTopNFiles         Number of busiest files shown in
+//                                                               comparisons
+//   GL_CONTRIBUTORS_MIN_COMMITS     gl.contributorsMinCommits   Commit threshold for
+//                                                               --generate-contributors
+//   GL_CONTRIBUTORS_<...>           gl.contributors<...>        Headers and templates for
+//                                                               --generate-contributors (e.g.
+//                                                               GL_CONTRIBUTORS_TEXT_TEMPLATE or
+//                                                               gl.contributorsTextTemplate)
+//   GL_FORGE_HOSTS                  gl.forgeHosts               Self-hosted forges, e.g.
+//                                                               "git.example.com=gitlab"
+//   GL_CHART_MARKERS                gl.chartMarkers             Milestones to mark on the
+//                                                               contribution graph, e.g.
+//                                                               "2024-03-01=Launch"
+//   GL_RENAME_THRESHOLD             gl.renameThreshold          Similarity (%) for a file to count
+//                                                               as renamed in line statistics
+//   GL_GENERATED_FILES              gl.generatedFiles           Globs for generated files, which
+//                                                               are left out of line statistics
+//   GL_DEFAULT_BRANCH               gl.defaultBranch            Branch to compare others against
+//   GL_PROTECTED_BRANCHES           gl.protectedBranches        Globs for branches whose history
+//                                                               must not be rewritten
+//   GL_COMMIT_GOAL                  gl.commitGoal               Commit goal shown with -c, e.g.
+//                                                               "5/week"
+//   GL_FETCH_TIMEOUT                gl.fetchTimeout             Seconds to wait for --fetch
+//   GL_BASE_DIR                                                 Directory containing your
+//                                                               repositories (for --global)
+//                                   gl.exclude                  Paths to always exclude from the
+//                                                               log and statistics (as --exclude)
+//
+// Lists are comma-separated in environment variables; in git config, list settings may also be
+// given multiple times.  Identities in the repository's git config are added to your own, so that
+// you can add aliases specific to a project
+//
+// Some of git's own settings are respected too, so that gl behaves like the rest of your git setup:
+// user.name and user.email are added to your identities, color.ui = false (or never) disables
+// colour, and init.defaultBranch is used when looking for the default branch
+//
+// NO_COLOR (or NO_COLOUR) disables colour, as per https://no-color.org
+lazy_static! {
+    pub static ref SETTINGS: Settings = Settings::load();
+}
+
+pub struct Settings {
+    pub default_n: usize,
+    pub identity: Vec<String>,
+    pub palette: String,
+    pub theme_overrides: Vec<String>,
+    pub bot_patterns: Vec<String>,
+    pub short_hash_length: usize,
+    pub language_bar_max_width: usize,
+    pub language_history_top_n: usize,
+    pub compare_top_n_files: usize,
+    pub contributors: ContributorsSettings,
+    pub forge_hosts: Vec<String>,
+    pub chart_markers: Vec<String>,
+    pub rename_threshold: usize,
+    pub generated_files: Vec<String>,
+    pub default_branch: Option<String>,
+    pub protected_branches: Vec<String>,
+    pub syntax_theme: String,
+    pub commit_goal: String,
+    pub fetch_timeout: u64,
+    pub base_dir: PathBuf,
+    pub excludes: Vec<String>,
+    pub no_colour: bool,
+    pub init_default_branch: Option<String>,
+}
+
+// Colour specifications for each element (see config.rs for the format)
+pub struct ThemeSettings {
+    pub hash: String,
+    pub refs: String,
+    pub ref_head: String,
+    pub ref_branch: String,
+    pub ref_remote: String,
+    pub ref_tag: String,
+    pub date: String,
+    pub author: String,
+    pub me_author: String,
+    pub bot_author: String,
+    pub graph_line: String,
+    pub diff_added: String,
+    pub diff_removed: String,
+    pub diff_hunk: String,
+}
+
+pub struct ContributorsSettings {
+    pub min_commits: usize,
+    pub markdown_header: String,
+    pub markdown_template: String,
+    pub text_header: String,
+    pub text_template: String,
+    pub email_template: String,
+}
+
+impl Settings {
+    fn load() -> Self {
+        let sources = Sources {
+            repo: repo_config(),
+            git: git_config(),
+        };
+
+        // Theme overrides are applied in order on top of the palette (see theme.rs)
+        let mut theme_overrides: Vec<String> = sources.repo_values("gl.theme").cloned().collect();
+        theme_overrides.extend(env_var("GL_THEME"));
+
+        let mut identity = list(&config::ME_IDENTITY);
+        identity.extend(sources.repo_list("gl.identity"));
+        for key in ["user.name", "user.email"] {
+            if let Some(value) = sources.git_value(key) {
+                if !identity.contains(&value) {
+                    identity.push(value);
+                }
+            }
+        }
+
+        // Like git, we treat "false" and "never" as turning colour off, but don't detect terminals
+        let colour_ui_off = sources
+            .git_value("color.ui")
+            .is_some_and(|v| matches!(v.to_lowercase().as_str(), "false" | "never" | "no" | "off"));
+
+        Settings {
+            default_n: sources.parse("GL_DEFAULT_N", "gl.defaultn", config::DEFAULT_TOP_N_LOG),
+            identity: env_var("GL_IDENTITY")
+                .map(|v| split_list(&v))
+                .unwrap_or(identity),
+            palette: sources.string("GL_PALETTE", "gl.palette", config::PALETTE),
+            theme_overrides,
+            bot_patterns: sources.list("GL_BOT_PATTERNS", "gl.botpatterns", &config::BOT_PATTERNS),
+            short_hash_length: sources.parse(
+                "GL_SHORT_HASH_LENGTH",
+                "gl.shorthashlength",
+                config::SHORT_HASH_LENGTH,
+            ),
+            language_bar_max_width: sources.parse(
+                "GL_LANGUAGE_BAR_MAX_WIDTH",
+                "gl.languagebarmaxwidth",
+                config::LANGUAGE_BAR_MAX_WIDTH,
+            ),
+            language_history_top_n: sources.parse(
+                "GL_LANGUAGE_HISTORY_TOP_N",
+                "gl.languagehistorytopn",
+                config::LANGUAGE_HISTORY_TOP_N,
+            ),
+            compare_top_n_files: sources.parse(
+                "GL_COMPARE_TOP_N_FILES",
+                "gl.comparetopnfiles",
+                config::COMPARE_TOP_N_FILES,
+            ),
+            contributors: ContributorsSettings {
+                min_commits: sources.parse(
+                    "GL_CONTRIBUTORS_MIN_COMMITS",
+                    "gl.contributorsmincommits",
+                    config::CONTRIBUTORS_MIN_COMMITS,
+                ),
+                markdown_header: sources.string(
+                    "GL_CONTRIBUTORS_MARKDOWN_HEADER",
+                    "gl.contributorsmarkdownheader",
+                    config::CONTRIBUTORS_MARKDOWN_HEADER,
+                ),
+                markdown_template: sources.string(
+                    "GL_CONTRIBUTORS_MARKDOWN_TEMPLATE",
+                    "gl.contributorsmarkdowntemplate",
+                    config::CONTRIBUTORS_MARKDOWN_TEMPLATE,
+                ),
+                text_header: sources.string(
+                    "GL_CONTRIBUTORS_TEXT_HEADER",
+                    "gl.contributorstextheader",
+                    config::CONTRIBUTORS_TEXT_HEADER,
+                ),
+                text_template: sources.string(
+                    "GL_CONTRIBUTORS_TEXT_TEMPLATE",
+                    "gl.contributorstexttemplate",
+                    config::CONTRIBUTORS_TEXT_TEMPLATE,
+                ),
+                email_template: sources.string(
+                    "GL_CONTRIBUTORS_EMAIL_TEMPLATE",
+                    "gl.contributorsemailtemplate",
+                    config::CONTRIBUTORS_EMAIL_TEMPLATE,
+                ),
+            },
+            forge_hosts: sources.list("GL_FORGE_HOSTS", "gl.forgehosts", &config::FORGE_HOSTS),
+            chart_markers: sources.list(
+                "GL_CHART_MARKERS",
+                "gl.chartmarkers",
+                &config::CHART_MARKERS,
+            ),
+            rename_threshold: sources.parse(
+                "GL_RENAME_THRESHOLD",
+                "gl.renamethreshold",
+                config::RENAME_THRESHOLD,
+            ),
+            generated_files: sources.list(
+                "GL_GENERATED_FILES",
+                "gl.generatedfiles",
+                &config::GENERATED_FILES,
+            ),
+            default_branch: sources.value("GL_DEFAULT_BRANCH", "gl.defaultbranch"),
+            protected_branches: sources.list(
+                "GL_PROTECTED_BRANCHES",
+                "gl.protectedbranches",
+                &config::PROTECTED_BRANCHES,
+            ),
+            syntax_theme: sources.string("GL_SYNTAX_THEME", "gl.syntaxtheme", config::SYNTAX_THEME),
+            commit_goal: sources.string("GL_COMMIT_GOAL", "gl.commitgoal", config::COMMIT_GOAL),
+            fetch_timeout: sources.parse(
+                "GL_FETCH_TIMEOUT",
+                "gl.fetchtimeout",
+                config::FETCH_TIMEOUT,
+            ),
+            // This is about the machine rather than any one repository, so it is not read from the
+            // repository's git config
+            base_dir: base_dir(),
+            excludes: sources.repo_list("gl.exclude"),
+            no_colour: env::var_os("NO_COLOR").is_some()
+                || env::var_os("NO_COLOUR").is_some()
+                || colour_ui_off,
+            init_default_branch: sources.git_value("init.defaultbranch"),
+        }
+    }
+}
+
+impl ThemeSettings {
+    // Apply overrides of the form "element=spec,element=spec"
+    pub fn apply_overrides(&mut self, overrides: &str) {
+        for assignment in overrides.split(',').filter(|s| !s.trim().is_empty()) {
+            let Some((element, spec)) = assignment.split_once('=') else {
+                eprintln!(
+                    "[WARN] Ignoring theme override {:?}: expected \"element=colour\"",
+                    assignment
+                );
+                continue;
+            };
+            let target = match element.trim() {
+                "hash" => &mut self.hash,
+                "refs" => &mut self.refs,
+                "head" => &mut self.ref_head,
+                "branch" => &mut self.ref_branch,
+                "remote" => &mut self.ref_remote,
+                "tag" => &mut self.ref_tag,
+                "date" => &mut self.date,
+                "author" => &mut self.author,
+                "me" => &mut self.me_author,
+                "bot" => &mut self.bot_author,
+                "graph" => &mut self.graph_line,
+                "added" => &mut self.diff_added,
+                "removed" => &mut self.diff_removed,
+                "hunk" => &mut self.diff_hunk,
+                element => {
+                    eprintln!("[WARN] Ignoring unknown theme element {:?}", element);
+                    continue;
+                }
+            };
+            *target = spec.trim().to_string();
+        }
+    }
+}
+
+// Where settings can be overridden, other than the config file
+struct Sources {
+    // Values from the [gl] section of the repository's git config, keyed by lowercase name
+    repo: HashMap<String, Vec<String>>,
+    // Git's own settings that we respect, from any scope, keyed by lowercase name
+    git: HashMap<String, Vec<String>>,
+}
+
+impl Sources {
+    // The effective value of one of git's own settings (the last one given wins, as in git)
+    fn git_value(&self, key: &str) -> Option<String> {
+        self.git
+            .get(key)
+            .and_then(|values| values.last())
+            .filter(|v| !v.is_empty())
+            .cloned()
+    }
+
+    fn repo_values(&self, key: &str) -> impl Iterator<Item = &String> {
+        self.repo.get(key).into_iter().flatten()
+    }
+
+    // List settings may be given multiple times in git config, and each may be comma-separated
+    fn repo_list(&self, key: &str) -> Vec<String> {
+        self.repo_values(key).flat_map(|v| split_list(v)).collect()
+    }
+
+    // The environment variable if set, or else the last value in the repository's git config
+    fn value(&self, env_name: &str, repo_key: &str) -> Option<String> {
+        env_var(env_name).or_else(|| self.repo_values(repo_key).last().cloned())
+    }
+
+    fn string(&self, env_name: &str, repo_key: &str, default: &str) -> String {
+        self.value(env_name, repo_key)
+            .unwrap_or_else(|| default.to_string())
+    }
+
+    fn parse<T: FromStr>(&self, env_name: &str, repo_key: &str, default: T) -> T {
+        match self.value(env_name, repo_key) {
+            Some(v) => v.trim().parse().unwrap_or_else(|_| {
+                eprintln!(
+                    "[WARN] Ignoring invalid value {:?} for {} ({})",
+                    v, env_name, repo_key
+                );
+                default
+            }),
+            None => default,
+        }
+    }
+
+    fn list(&self, env_name: &str, repo_key: &str, default: &[&str]) -> Vec<String> {
+        if let Some(v) = env_var(env_name) {
+            return split_list(&v);
+        }
+        let repo_list = self.repo_list(repo_key);
+        if repo_list.is_empty() {
+            list(default)
+        } else {
+            repo_list
+        }
+    }
+}
+
+// Read the [gl] section of the current repository's git config (if we are in one)
+fn repo_config() -> HashMap<String, Vec<String>> {
+    read_git_config(&["--local"], r"^gl\.")
+}
+
+// Read the git settings that gl respects, from the system, global, and repository config
+fn git_config() -> HashMap<String, Vec<String>> {
+    read_git_config(
+        &[],
+        r"^(user\.name|user\.email|color\.ui|init\.defaultbranch)$",
+    )
+}
+
+fn read_git_config(scope_args: &[&str], key_regex: &str) -> HashMap<String, Vec<String>> {
+    let mut cmd = Command::new("git");
+    cmd.arg("config");
+    cmd.args(scope_args);
+    cmd.arg("--null");
+    cmd.arg("--get-regexp");
+    cmd.arg(key_regex);
+
+    let mut repo_config: HashMap<String, Vec<String>> = HashMap::new();
+    let Ok(output) = cmd.stdout(Stdio::piped()).stderr(Stdio::null()).output() else {
+        return repo_config;
+    };
+
+    // Each entry is "<key>\n<value>\0", and keys are always lowercase
+    for entry in String::from_utf8_lossy(&output.stdout).split_terminator('\0') {
+        let (key, value) = entry.split_once('\n').unwrap_or((entry, ""));
+        repo_config
+            .entry(key.to_string())
+            .or_default()
+            .push(value.to_string());
+    }
+    repo_config
+}
+
+// The value of an environment variable, treating empty values as unset
+fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+// Replace a leading "~" in a path with the home directory
+// The base directory is needed before we know which repository to read settings from (see
+// --repo), so it can't be set in git config
+pub fn base_dir() -> PathBuf {
+    expand_home(&env_var("GL_BASE_DIR").unwrap_or_else(|| config::BASE_DIR.to_string()))
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest.trim_start_matches('/')),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn split_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn list(default: &[&str]) -> Vec<String> {
+    default.iter().map(|s| s.to_string()).collect()
+}
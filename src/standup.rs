@@ -0,0 +1,214 @@
+use super::identity::{author_filter_args, own_author_filters, AuthorFilter};
+use super::opts::GitLogOptions;
+use super::repo;
+use super::settings::SETTINGS;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, Weekday};
+use colored::*;
+use json::JsonValue;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+pub struct StandupCommit {
+    pub hash: String,
+    pub subject: String,
+}
+
+// Your commits in one repository, by the branch they were found on
+pub struct StandupRepo {
+    pub name: String,
+    pub path: Option<PathBuf>,
+    pub branches: BTreeMap<String, Vec<StandupCommit>>,
+}
+
+pub struct Standup {
+    // The last working day
+    pub day: NaiveDate,
+    pub repos: Vec<StandupRepo>,
+}
+
+// The most recent weekday before the given day, so that on a Monday we report on Friday
+fn last_working_day(today: NaiveDate) -> NaiveDate {
+    let mut day = today.pred_opt().unwrap_or(today);
+    while matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+        day = day.pred_opt().unwrap_or(day);
+    }
+    day
+}
+
+// Your commits (or the given author's) since the start of the last working day, up to the start of
+// today, on any local branch.  This includes anything done over the weekend.  With `global`, every
+// repository under the base directory (see config::BASE_DIR) is searched, rather than the current
+// one
+pub fn standup(
+    author: Option<&str>,
+    global: bool,
+    current_repo: Option<&str>,
+    opts: &GitLogOptions,
+) -> Standup {
+    let today = Local::now().date_naive();
+    let day = last_working_day(today);
+    let at_midnight = |date: NaiveDate| -> Option<DateTime<Local>> {
+        date.and_time(NaiveTime::MIN)
+            .and_local_timezone(Local)
+            .earliest()
+    };
+    let since = at_midnight(day).map_or(0, |t| t.timestamp());
+    let until = at_midnight(today).map_or_else(|| Local::now().timestamp(), |t| t.timestamp());
+
+    let authors = match author {
+        Some(author) => vec![AuthorFilter::Pattern(author.to_string())],
+        None => own_author_filters(&opts.authors),
+    };
+
+    let repos: Vec<(String, Option<PathBuf>)> = if global {
+        repo::repos_under(&SETTINGS.base_dir)
+            .into_iter()
+            .map(|path| (repo_name(&path), Some(path)))
+            .collect()
+    } else {
+        current_repo
+            .map(|name| (name.to_string(), None))
+            .into_iter()
+            .collect()
+    };
+
+    let repos = repos
+        .into_iter()
+        .map(|(name, path)| StandupRepo {
+            branches: commits_by_branch(path.as_deref(), since, until, &authors, opts),
+            name,
+            path,
+        })
+        .filter(|repo| !repo.branches.is_empty())
+        .collect();
+
+    Standup { day, repos }
+}
+
+fn repo_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+// Commits in the repository at the given path (or the current one), oldest first, by the local
+// branch git first reached them from
+fn commits_by_branch(
+    path: Option<&Path>,
+    since: i64,
+    until: i64,
+    authors: &[AuthorFilter],
+    opts: &GitLogOptions,
+) -> BTreeMap<String, Vec<StandupCommit>> {
+    let mut cmd = Command::new("git");
+    if let Some(path) = path {
+        cmd.arg("-C").arg(path);
+    }
+    cmd.arg("log");
+    cmd.arg("--branches");
+    cmd.arg("--source");
+    cmd.arg("--reverse");
+    if !opts.first_parent {
+        cmd.arg("--no-merges");
+    }
+    cmd.arg("--format=%H%x00%S%x00%s");
+    cmd.arg(format!("--since={}", since));
+    cmd.arg(format!("--until={}", until));
+    cmd.args(author_filter_args(authors));
+    if opts.first_parent {
+        cmd.arg("--first-parent");
+    }
+
+    let output = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git log`");
+
+    let mut branches: BTreeMap<String, Vec<StandupCommit>> = BTreeMap::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let mut parts = line.splitn(3, '\0');
+        let (Some(hash), Some(source), Some(subject)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let branch = source.strip_prefix("refs/heads/").unwrap_or(source);
+        branches
+            .entry(branch.to_string())
+            .or_default()
+            .push(StandupCommit {
+                hash: hash.to_string(),
+                subject: subject.to_string(),
+            });
+    }
+    branches
+}
+
+pub fn display_standup(standup: Standup, opts: &GitLogOptions) {
+    if opts.json {
+        let repos: Vec<JsonValue> = standup
+            .repos
+            .iter()
+            .map(|repo| {
+                let mut branches = JsonValue::new_object();
+                for (branch, commits) in &repo.branches {
+                    let commits: Vec<JsonValue> = commits
+                        .iter()
+                        .map(|c| json::object! { hash: c.hash.clone(), subject: c.subject.clone() })
+                        .collect();
+                    branches[branch.as_str()] = JsonValue::Array(commits);
+                }
+                json::object! {
+                    name: repo.name.clone(),
+                    path: repo.path.as_ref().map(|p| p.display().to_string()),
+                    branches: branches,
+                }
+            })
+            .collect();
+        let value = json::object! {
+            since: standup.day.format("%Y-%m-%d").to_string(),
+            repos: JsonValue::Array(repos),
+        };
+        println!("{}", value.pretty(2));
+        return;
+    }
+
+    let since = standup.day.format("%A %Y-%m-%d").to_string();
+    if standup.repos.is_empty() {
+        println!("No commits since {}.", since);
+        return;
+    }
+
+    // Plain enough to paste into standup notes
+    println!("Since {}:", since);
+    for repo in &standup.repos {
+        for (branch, commits) in &repo.branches {
+            println!();
+            let heading = format!("{} ({})", repo.name, branch);
+            if opts.colour {
+                println!("{}", heading.bold());
+            } else {
+                println!("{}", heading);
+            }
+            for commit in commits {
+                println!("- {}", commit.subject);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_weekends() {
+        let date = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        // Monday 8 January 2024
+        assert_eq!(last_working_day(date(8)), date(5));
+        assert_eq!(last_working_day(date(7)), date(5));
+        assert_eq!(last_working_day(date(6)), date(5));
+        assert_eq!(last_working_day(date(9)), date(8));
+    }
+}
@@ -1,16 +1,46 @@
 use super::opts::GitLogOptions;
+use super::theme::THEME;
+use std::cmp::max;
 use std::ffi::OsString;
+use std::io::Write;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
 
 pub fn get_git_status(dir: &Option<String>, opts: &GitLogOptions) {
-    let given_dir: PathBuf = if (dir).is_none() {
+    let given_dir = status_dir(dir);
+    let status: String = git_status(&given_dir.into_os_string(), opts);
+    println!("{}", status.trim_end())
+}
+
+// Re-render the status whenever it changes, checking every interval (in seconds).  The status is
+// only redrawn when it differs from what is on screen, so rapid successive changes to files are
+// naturally debounced by the interval
+pub fn watch_git_status(dir: &Option<String>, interval: u64, opts: &GitLogOptions) {
+    let given_dir = status_dir(dir).into_os_string();
+    let interval = Duration::from_secs(max(interval, 1));
+    let mut previous_status: Option<String> = None;
+
+    loop {
+        let status = git_status(&given_dir, opts);
+        if previous_status.as_ref() != Some(&status) {
+            // Clear the screen and move the cursor to the top left before redrawing
+            print!("\x1B[2J\x1B[H");
+            println!("{}", status.trim_end());
+            let _ = std::io::stdout().flush();
+            previous_status = Some(status);
+        }
+        thread::sleep(interval);
+    }
+}
+
+fn status_dir(dir: &Option<String>) -> PathBuf {
+    if (dir).is_none() {
         std::env::current_dir().unwrap()
     } else {
         PathBuf::from(dir.clone().unwrap())
-    };
-    let status: String = git_status(&given_dir.into_os_string(), opts);
-    println!("{}", status.trim_end())
+    }
 }
 
 fn git_status(dir: &OsString, opts: &GitLogOptions) -> String {
@@ -18,6 +48,7 @@ fn git_status(dir: &OsString, opts: &GitLogOptions) -> String {
     if opts.colour {
         cmd.arg("-c");
         cmd.arg("color.status=always");
+        cmd.args(THEME.palette.git_status_config_args());
     }
     cmd.arg("status");
     cmd.arg("--short");
@@ -32,7 +63,22 @@ fn git_status(dir: &OsString, opts: &GitLogOptions) -> String {
     if output.status.success() {
         let git_status = String::from_utf8_lossy(&output.stdout).into_owned();
 
-        git_status
+        // Stashes are easily forgotten, so count them in the "## branch...upstream" header
+        match stash_count() {
+            0 => git_status,
+            n => {
+                let stashes = format!("[stash: {}]", n);
+                let stashes = if opts.colour {
+                    THEME.ref_tag.paint(&stashes).to_string()
+                } else {
+                    stashes
+                };
+                match git_status.split_once('\n') {
+                    Some((header, rest)) => format!("{} {}\n{}", header, stashes, rest),
+                    None => format!("{} {}", git_status, stashes),
+                }
+            }
+        }
     } else {
         println!("An error has occured.  It is likely that you aren't in a git repository, or you may not have `git` installed.");
 
@@ -40,6 +86,26 @@ fn git_status(dir: &OsString, opts: &GitLogOptions) -> String {
     }
 }
 
+// The number of entries in the stash, which git keeps as the reflog of refs/stash
+fn stash_count() -> usize {
+    let output = Command::new("git")
+        .arg("rev-list")
+        .arg("--walk-reflogs")
+        .arg("--count")
+        .arg("refs/stash")
+        .arg("--")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .expect("Failed to execute `git rev-list`");
+
+    // There is no refs/stash if nothing has been stashed
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .unwrap_or(0)
+}
+
 #[allow(dead_code)]
 fn git_diff_exit_code(dir: &OsString) {
     let mut cmd = Command::new("git");
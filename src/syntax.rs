@@ -0,0 +1,110 @@
+use super::settings::SETTINGS;
+use lazy_static::lazy_static;
+use std::path::Path;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Color, Style, Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+
+lazy_static! {
+    static ref SYNTAXES: SyntaxSet = SyntaxSet::load_defaults_newlines();
+    // None if syntax highlighting is turned off (see config::SYNTAX_THEME)
+    static ref SYNTAX_THEME: Option<Theme> = {
+        let name = SETTINGS.syntax_theme.trim();
+        if name.is_empty() {
+            return None;
+        }
+        let theme = ThemeSet::load_defaults().themes.remove(name);
+        if theme.is_none() {
+            eprintln!("[WARN] Unknown syntax theme {:?}; patches will not be syntax highlighted", name);
+        }
+        theme
+    };
+}
+
+// A run of text in one colour (as 24-bit RGB), or in the terminal's own colour
+pub struct Highlighted {
+    pub colour: Option<(u8, u8, u8)>,
+    pub text: String,
+}
+
+// The language of a file, from its name (e.g. "Makefile") or else its extension.  Plain text isn't
+// worth highlighting
+fn syntax_for_path(path: &str) -> Option<&'static SyntaxReference> {
+    let path = Path::new(path);
+    let by = |s: Option<&std::ffi::OsStr>| SYNTAXES.find_syntax_by_extension(s?.to_str()?);
+    by(path.file_name())
+        .or_else(|| by(path.extension()))
+        .filter(|syntax| syntax.name != "Plain Text")
+}
+
+// Highlights the lines of one file's patch.  Each side of the patch is highlighted separately, so
+// that removed lines are read in the context of the old file and added lines in that of the new.
+// Hunks start part way through a file, so a hunk starting inside (e.g.) a block comment will be
+// highlighted as if it weren't until the comment ends
+pub struct FileHighlighter {
+    old: HighlightLines<'static>,
+    new: HighlightLines<'static>,
+    // The theme's background, which removed and added lines are tinted from
+    pub background: (u8, u8, u8),
+}
+
+impl FileHighlighter {
+    pub fn for_path(path: &str) -> Option<Self> {
+        let theme = SYNTAX_THEME.as_ref()?;
+        let syntax = syntax_for_path(path)?;
+        let background = theme.settings.background.unwrap_or(Color::BLACK);
+        Some(FileHighlighter {
+            old: HighlightLines::new(syntax, theme),
+            new: HighlightLines::new(syntax, theme),
+            background: (background.r, background.g, background.b),
+        })
+    }
+
+    pub fn context(&mut self, line: &str) -> Vec<Highlighted> {
+        highlight(&mut self.old, line);
+        highlight(&mut self.new, line)
+    }
+
+    pub fn removed(&mut self, line: &str) -> Vec<Highlighted> {
+        highlight(&mut self.old, line)
+    }
+
+    pub fn added(&mut self, line: &str) -> Vec<Highlighted> {
+        highlight(&mut self.new, line)
+    }
+}
+
+fn highlight(highlighter: &mut HighlightLines, line: &str) -> Vec<Highlighted> {
+    // The syntaxes expect each line to end with a newline
+    let line = format!("{}\n", line);
+    let regions: Vec<(Style, &str)> = highlighter
+        .highlight_line(&line, &SYNTAXES)
+        .unwrap_or_else(|_| vec![(Style::default(), line.as_str())]);
+    regions
+        .into_iter()
+        .map(|(style, text)| Highlighted {
+            // Themes with a transparent foreground leave the text in the terminal's colour
+            colour: (style.foreground.a > 0).then_some((
+                style.foreground.r,
+                style.foreground.g,
+                style.foreground.b,
+            )),
+            text: text.trim_end_matches('\n').to_string(),
+        })
+        .filter(|region| !region.text.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_languages_by_path() {
+        let name = |path| syntax_for_path(path).map(|syntax| syntax.name.as_str());
+        assert_eq!(name("src/main.rs"), Some("Rust"));
+        assert_eq!(name("build/Makefile"), Some("Makefile"));
+        assert_eq!(name("notes.txt"), None);
+        assert_eq!(name("LICENSE"), None);
+    }
+}
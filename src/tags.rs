@@ -0,0 +1,264 @@
+use super::branch::for_each_ref;
+use super::commit::HashFormat;
+use super::opts::GitLogOptions;
+use chrono::{DateTime, FixedOffset};
+use clap::ValueEnum;
+use colored::*;
+use json::JsonValue;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::process::{Command, Stdio};
+use tabular::{row, Table};
+
+pub struct Tag {
+    pub name: String,
+    pub hash: String,
+    pub date: Option<DateTime<FixedOffset>>,
+    pub annotation: Option<TagAnnotation>,
+}
+
+// Details only present for annotated tags
+pub struct TagAnnotation {
+    pub tagger: String,
+    pub date: Option<DateTime<FixedOffset>>,
+    pub signature: TagSignature,
+}
+
+pub enum TagSignature {
+    Unsigned,
+    Verified,
+    Unverified,
+}
+
+impl TagSignature {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TagSignature::Unsigned => "unsigned",
+            TagSignature::Verified => "verified",
+            TagSignature::Unverified => "unverified",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum TagSort {
+    Date,
+    Version,
+    Name,
+}
+
+impl Tag {
+    fn to_json(&self) -> JsonValue {
+        json::object! {
+            name: self.name.clone(),
+            hash: self.hash.clone(),
+            date: self.date.map(|d| d.to_rfc3339()),
+            annotated: self.annotation.is_some(),
+            tagger: self.annotation.as_ref().map(|a| a.tagger.clone()),
+            tagger_date: self.annotation.as_ref().and_then(|a| a.date).map(|d| d.to_rfc3339()),
+            signature: self.annotation.as_ref().map(|a| a.signature.as_str()),
+        }
+    }
+}
+
+// List tags (optionally only those matching a glob), sorted in ascending order
+pub fn tags(pattern: Option<&str>, sort: &TagSort) -> Vec<Tag> {
+    let pattern = format!("refs/tags/{}", pattern.unwrap_or(""));
+
+    // Annotated tags point to a tag object, so we peel them to get the commit and its date
+    let mut tags: Vec<Tag> = for_each_ref(
+        &pattern,
+        "%(refname:short)%00%(objectname)%00%(*objectname)%00%(committerdate:iso-strict)%00%(*committerdate:iso-strict)%00%(objecttype)%00%(taggername) %(taggeremail)%00%(taggerdate:iso-strict)%00%(if)%(contents:signature)%(then)signed%(end)",
+    )
+    .into_iter()
+    .filter_map(|fields| {
+        let [name, hash, peeled_hash, date, peeled_date, object_type, tagger, tagger_date, signed] =
+            <[String; 9]>::try_from(fields).ok()?;
+        let (hash, date) = if peeled_hash.is_empty() {
+            (hash, date)
+        } else {
+            (peeled_hash, peeled_date)
+        };
+        let annotation = if object_type == "tag" {
+            Some(TagAnnotation {
+                tagger,
+                date: DateTime::parse_from_rfc3339(&tagger_date).ok(),
+                signature: if signed.is_empty() {
+                    TagSignature::Unsigned
+                } else if verify_tag(&name) {
+                    TagSignature::Verified
+                } else {
+                    TagSignature::Unverified
+                },
+            })
+        } else {
+            None
+        };
+        Some(Tag {
+            name,
+            hash,
+            date: DateTime::parse_from_rfc3339(&date).ok(),
+            annotation,
+        })
+    })
+    .collect();
+
+    match sort {
+        TagSort::Date => tags.sort_by_key(|tag| tag.date),
+        TagSort::Version => tags.sort_by(|a, b| compare_versions(&a.name, &b.name)),
+        TagSort::Name => tags.sort_by(|a, b| a.name.cmp(&b.name)),
+    }
+
+    tags
+}
+
+pub fn display_tags(tags: Vec<Tag>, opts: &GitLogOptions) {
+    if opts.json {
+        let tags: Vec<JsonValue> = tags.iter().map(|t| t.to_json()).collect();
+        println!("{}", JsonValue::Array(tags).pretty(2));
+        return;
+    }
+
+    let mut table = Table::new("{:<}  {:<}  {:<}  {:<}  {:<}  {:<}  {:<}").with_row(row!(
+        "Tag",
+        "Commit",
+        "Date",
+        "Type",
+        "Tagger",
+        "Tagged",
+        "Signature"
+    ));
+    let format_date = |d: Option<DateTime<FixedOffset>>| {
+        d.map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    };
+    for tag in tags {
+        let date = format_date(tag.date);
+        let (kind, tagger, tagged, signature) = match &tag.annotation {
+            Some(annotation) => (
+                "annotated",
+                annotation.tagger.clone(),
+                format_date(annotation.date),
+                annotation.signature.as_str(),
+            ),
+            None => ("lightweight", String::new(), String::new(), ""),
+        };
+        if opts.colour {
+            let signature = match tag.annotation.as_ref().map(|a| &a.signature) {
+                Some(TagSignature::Verified) => signature.green(),
+                Some(TagSignature::Unverified) => signature.red(),
+                _ => signature.dimmed(),
+            };
+            table.add_row(row!(
+                tag.name.bold().green(),
+                tag.hash.short().yellow(),
+                date.red(),
+                kind,
+                tagger.blue(),
+                tagged.red(),
+                signature
+            ));
+        } else {
+            table.add_row(row!(
+                tag.name,
+                tag.hash.short(),
+                date,
+                kind,
+                tagger,
+                tagged,
+                signature
+            ));
+        }
+    }
+    print!("{}", table);
+}
+
+// Whether the signature on a tag can be verified (i.e., it is valid and the key is known)
+fn verify_tag(name: &str) -> bool {
+    Command::new("git")
+        .arg("verify-tag")
+        .arg(name)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+// A version parsed from a tag name such as "v1.2.3", "release-2.0", or "1.0.0-rc.1"
+struct Version<'a> {
+    core: Vec<u64>,
+    pre_release: Option<&'a str>,
+}
+
+impl<'a> Version<'a> {
+    fn parse(s: &'a str) -> Option<Self> {
+        // Skip any non-numeric prefix (e.g. "v" or "release-")
+        let s = &s[s.find(|c: char| c.is_ascii_digit())?..];
+
+        // Build metadata does not affect version precedence
+        let s = s.split('+').next().unwrap_or(s);
+        let (core, pre_release) = match s.split_once('-') {
+            Some((core, pre_release)) => (core, Some(pre_release)),
+            None => (s, None),
+        };
+        let core = core
+            .split('.')
+            .map(|n| n.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Version { core, pre_release })
+    }
+}
+
+// Order tag names by version precedence (following semantic versioning, but allowing any number
+// of version components).  Tags that are not versions are ordered by name after all versions
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    match (Version::parse(a), Version::parse(b)) {
+        (Some(va), Some(vb)) => {
+            let n = va.core.len().max(vb.core.len());
+            let component = |v: &Version, i: usize| v.core.get(i).copied().unwrap_or(0);
+            (0..n)
+                .map(|i| component(&va, i).cmp(&component(&vb, i)))
+                .find(|o| o.is_ne())
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| compare_pre_releases(va.pre_release, vb.pre_release))
+                .then_with(|| a.cmp(b))
+        }
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+// A pre-release version has lower precedence than the release itself; pre-release identifiers are
+// compared numerically if they are both numbers, and lexically otherwise
+fn compare_pre_releases(a: Option<&str>, b: Option<&str>) -> Ordering {
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => {
+            let mut a_ids = a.split('.');
+            let mut b_ids = b.split('.');
+            loop {
+                match (a_ids.next(), b_ids.next()) {
+                    (None, None) => return Ordering::Equal,
+                    (None, Some(_)) => return Ordering::Less,
+                    (Some(_), None) => return Ordering::Greater,
+                    (Some(x), Some(y)) => {
+                        let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+                            (Ok(x), Ok(y)) => x.cmp(&y),
+                            (Ok(_), Err(_)) => Ordering::Less,
+                            (Err(_), Ok(_)) => Ordering::Greater,
+                            (Err(_), Err(_)) => x.cmp(y),
+                        };
+                        if ordering.is_ne() {
+                            return ordering;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use super::config;
+use super::settings::{ThemeSettings, SETTINGS};
+use clap::ValueEnum;
+use colored::{Color, ColoredString, Colorize};
+use colorsys::Rgb;
+use lazy_static::lazy_static;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+// The theme is parsed once from the colour specifications in the settings
+lazy_static! {
+    pub static ref THEME: Theme = Theme::from_settings();
+}
+
+// The palette chosen on the command line, which takes precedence over the settings.  This must be
+// set before the theme is first used
+static PALETTE_OVERRIDE: OnceLock<Palette> = OnceLock::new();
+
+// The Okabe-Ito palette, whose colours remain distinguishable with the common forms of colour
+// blindness (https://jfly.uni-koeln.de/color/)
+const OKABE_ITO: [&str; 7] = [
+    "#e69f00", "#56b4e9", "#009e73", "#f0e442", "#0072b2", "#d55e00", "#cc79a7",
+];
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Palette {
+    Default,
+    #[value(alias = "colorblind")]
+    Colourblind,
+    Monochrome,
+}
+
+pub struct Theme {
+    pub palette: Palette,
+    pub hash: ThemeColour,
+    pub refs: ThemeColour,
+    pub ref_head: ThemeColour,
+    pub ref_branch: ThemeColour,
+    pub ref_remote: ThemeColour,
+    pub ref_tag: ThemeColour,
+    pub date: ThemeColour,
+    pub author: ThemeColour,
+    pub me_author: ThemeColour,
+    pub bot_author: ThemeColour,
+    pub graph_line: ThemeColour,
+    pub diff_added: ThemeColour,
+    pub diff_removed: ThemeColour,
+    pub diff_hunk: ThemeColour,
+}
+
+// A colour specification such as "bold yellow", "dim bright blue", or "#c0cfe3"
+#[derive(Clone)]
+pub struct ThemeColour {
+    bold: bool,
+    dimmed: bool,
+    // None for the terminal's own colour
+    colour: Option<Color>,
+}
+
+impl Palette {
+    // The colour specification of each element of the theme in this palette
+    fn theme_settings(&self) -> ThemeSettings {
+        let [hash, refs, ref_head, ref_branch, ref_remote, ref_tag, date, author, me_author, bot_author, graph_line, diff_added, diff_removed, diff_hunk] =
+            match self {
+                Palette::Default => [
+                    config::THEME_HASH,
+                    config::THEME_REFS,
+                    config::THEME_REF_HEAD,
+                    config::THEME_REF_BRANCH,
+                    config::THEME_REF_REMOTE,
+                    config::THEME_REF_TAG,
+                    config::THEME_DATE,
+                    config::THEME_AUTHOR,
+                    config::THEME_ME_AUTHOR,
+                    config::THEME_BOT_AUTHOR,
+                    config::THEME_GRAPH_LINE,
+                    config::THEME_DIFF_ADDED,
+                    config::THEME_DIFF_REMOVED,
+                    config::THEME_DIFF_HUNK,
+                ],
+                Palette::Colourblind => [
+                    "bold #f0e442",
+                    "bold #56b4e9",
+                    "bold #cc79a7",
+                    "bold #56b4e9",
+                    "bold #e69f00",
+                    "bold #f0e442",
+                    "bold #e69f00",
+                    "bold #0072b2",
+                    "#cc79a7",
+                    "dim white",
+                    "#56b4e9",
+                    "#56b4e9",
+                    "#e69f00",
+                    "#cc79a7",
+                ],
+                Palette::Monochrome => [
+                    "bold normal",
+                    "bold normal",
+                    "bold normal",
+                    "bold normal",
+                    "normal",
+                    "normal",
+                    "normal",
+                    "normal",
+                    "bold normal",
+                    "dim normal",
+                    "normal",
+                    "bold normal",
+                    "dim normal",
+                    "normal",
+                ],
+            };
+        ThemeSettings {
+            hash: hash.to_string(),
+            refs: refs.to_string(),
+            ref_head: ref_head.to_string(),
+            ref_branch: ref_branch.to_string(),
+            ref_remote: ref_remote.to_string(),
+            ref_tag: ref_tag.to_string(),
+            date: date.to_string(),
+            author: author.to_string(),
+            me_author: me_author.to_string(),
+            bot_author: bot_author.to_string(),
+            graph_line: graph_line.to_string(),
+            diff_added: diff_added.to_string(),
+            diff_removed: diff_removed.to_string(),
+            diff_hunk: diff_hunk.to_string(),
+        }
+    }
+
+    // The colour of a language in the language breakdown, given its rank and its colour from the
+    // linguist database (if it has one)
+    pub fn language_colour(
+        &self,
+        linguist_colour: Option<rgb::RGB8>,
+        rank: usize,
+    ) -> Option<rgb::RGB8> {
+        match self {
+            Palette::Default => linguist_colour,
+            Palette::Colourblind => {
+                Some(ThemeColour::parse(OKABE_ITO[rank % OKABE_ITO.len()]).rgb())
+            }
+            Palette::Monochrome => None,
+        }
+    }
+
+    // Git configuration (as `-c` arguments) to colour the output of `git status` in this palette.
+    // The default palette leaves git's own colours (and the user's configuration of them) alone
+    pub fn git_status_config_args(&self) -> Vec<String> {
+        let colours: &[(&str, &str)] = match self {
+            Palette::Default => &[],
+            Palette::Colourblind => &[
+                ("added", "#56b4e9"),
+                ("changed", "#e69f00"),
+                ("untracked", "#cc79a7"),
+                ("unmerged", "bold #d55e00"),
+                ("localBranch", "#56b4e9"),
+                ("remoteBranch", "#e69f00"),
+            ],
+            Palette::Monochrome => &[
+                ("added", "bold"),
+                ("changed", "normal"),
+                ("untracked", "dim"),
+                ("unmerged", "bold ul"),
+                ("localBranch", "bold"),
+                ("remoteBranch", "normal"),
+            ],
+        };
+        colours
+            .iter()
+            .flat_map(|(slot, colour)| {
+                vec![
+                    String::from("-c"),
+                    format!("color.status.{}={}", slot, colour),
+                ]
+            })
+            .collect()
+    }
+}
+
+// Choose the palette from the command line.  Has no effect if the theme has already been used
+pub fn set_palette(palette: Palette) {
+    let _ = PALETTE_OVERRIDE.set(palette);
+}
+
+impl Theme {
+    fn from_settings() -> Self {
+        let palette = PALETTE_OVERRIDE.get().copied().unwrap_or_else(|| {
+            // The same names as --palette accepts
+            Palette::from_str(&SETTINGS.palette, false).unwrap_or_else(|_| {
+                eprintln!(
+                    "[WARN] Unknown palette {:?}; using the default palette",
+                    SETTINGS.palette
+                );
+                Palette::Default
+            })
+        });
+
+        let mut theme = palette.theme_settings();
+        for theme_overrides in &SETTINGS.theme_overrides {
+            theme.apply_overrides(theme_overrides);
+        }
+
+        Theme {
+            palette,
+            hash: ThemeColour::parse(&theme.hash),
+            refs: ThemeColour::parse(&theme.refs),
+            ref_head: ThemeColour::parse(&theme.ref_head),
+            ref_branch: ThemeColour::parse(&theme.ref_branch),
+            ref_remote: ThemeColour::parse(&theme.ref_remote),
+            ref_tag: ThemeColour::parse(&theme.ref_tag),
+            date: ThemeColour::parse(&theme.date),
+            author: ThemeColour::parse(&theme.author),
+            me_author: ThemeColour::parse(&theme.me_author),
+            bot_author: ThemeColour::parse(&theme.bot_author),
+            graph_line: ThemeColour::parse(&theme.graph_line),
+            diff_added: ThemeColour::parse(&theme.diff_added),
+            diff_removed: ThemeColour::parse(&theme.diff_removed),
+            diff_hunk: ThemeColour::parse(&theme.diff_hunk),
+        }
+    }
+}
+
+impl ThemeColour {
+    pub fn parse(spec: &str) -> Self {
+        let mut bold = false;
+        let mut dimmed = false;
+        let mut colour_words = Vec::new();
+        for word in spec.split_whitespace() {
+            match word {
+                "bold" => bold = true,
+                "dim" => dimmed = true,
+                _ => colour_words.push(word),
+            }
+        }
+
+        let colour_str = colour_words.join(" ");
+        let colour = if colour_str.is_empty() || colour_str == "normal" {
+            Some(None)
+        } else if colour_str.starts_with('#') {
+            Rgb::from_hex_str(&colour_str).ok().map(|rgb| {
+                Some(Color::TrueColor {
+                    r: rgb.red().round() as u8,
+                    g: rgb.green().round() as u8,
+                    b: rgb.blue().round() as u8,
+                })
+            })
+        } else {
+            Color::from_str(&colour_str).ok().map(Some)
+        };
+
+        let colour = colour.unwrap_or_else(|| {
+            eprintln!(
+                "[WARN] Unable to parse colour {:?} in theme; using white",
+                spec
+            );
+            Some(Color::White)
+        });
+
+        ThemeColour {
+            bold,
+            dimmed,
+            colour,
+        }
+    }
+
+    // Apply this colour to a string for display
+    pub fn paint(&self, s: &str) -> ColoredString {
+        let mut painted = match self.colour {
+            Some(colour) => s.color(colour),
+            None => s.normal(),
+        };
+        if self.bold {
+            painted = painted.bold();
+        }
+        if self.dimmed {
+            painted = painted.dimmed();
+        }
+        painted
+    }
+
+    // The equivalent colour specification for git's --pretty=format "%C(...)" placeholder
+    pub fn git_spec(&self) -> String {
+        let mut spec = Vec::new();
+        if self.bold {
+            spec.push(String::from("bold"));
+        }
+        if self.dimmed {
+            spec.push(String::from("dim"));
+        }
+        spec.push(match self.colour {
+            None => String::from("normal"),
+            Some(Color::TrueColor { r, g, b }) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+            Some(colour) => {
+                // Git spells bright colours without a space (e.g. "brightblue")
+                let (bright, name) = named_colour(colour);
+                format!("{}{}", if bright { "bright" } else { "" }, name)
+            }
+        });
+        spec.join(" ")
+    }
+
+    // An RGB approximation of this colour, for charts
+    pub fn rgb(&self) -> rgb::RGB8 {
+        let Some(colour) = self.colour else {
+            return rgb::RGB8 {
+                r: 229,
+                g: 229,
+                b: 229,
+            };
+        };
+        let (r, g, b) = match colour {
+            Color::TrueColor { r, g, b } => (r, g, b),
+            // Standard xterm palette
+            Color::Black => (0, 0, 0),
+            Color::Red => (205, 0, 0),
+            Color::Green => (0, 205, 0),
+            Color::Yellow => (205, 205, 0),
+            Color::Blue => (0, 0, 238),
+            Color::Magenta => (205, 0, 205),
+            Color::Cyan => (0, 205, 205),
+            Color::White => (229, 229, 229),
+            Color::BrightBlack => (127, 127, 127),
+            Color::BrightRed => (255, 0, 0),
+            Color::BrightGreen => (0, 255, 0),
+            Color::BrightYellow => (255, 255, 0),
+            Color::BrightBlue => (92, 92, 255),
+            Color::BrightMagenta => (255, 0, 255),
+            Color::BrightCyan => (0, 255, 255),
+            Color::BrightWhite => (255, 255, 255),
+        };
+        rgb::RGB8 { r, g, b }
+    }
+}
+
+fn named_colour(colour: Color) -> (bool, &'static str) {
+    match colour {
+        Color::Black => (false, "black"),
+        Color::Red => (false, "red"),
+        Color::Green => (false, "green"),
+        Color::Yellow => (false, "yellow"),
+        Color::Blue => (false, "blue"),
+        Color::Magenta => (false, "magenta"),
+        Color::Cyan => (false, "cyan"),
+        Color::White => (false, "white"),
+        Color::BrightBlack => (true, "black"),
+        Color::BrightRed => (true, "red"),
+        Color::BrightGreen => (true, "green"),
+        Color::BrightYellow => (true, "yellow"),
+        Color::BrightBlue => (true, "blue"),
+        Color::BrightMagenta => (true, "magenta"),
+        Color::BrightCyan => (true, "cyan"),
+        Color::BrightWhite => (true, "white"),
+        Color::TrueColor { .. } => (false, "normal"),
+    }
+}
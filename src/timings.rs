@@ -0,0 +1,51 @@
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Phase timings are recorded globally so that we needn't thread a timer through every subsystem.
+// They are always recorded (it's cheap), but only reported when requested
+lazy_static! {
+    static ref TIMINGS: Mutex<Vec<(&'static str, Duration)>> = Mutex::new(Vec::new());
+}
+
+// Run the given closure, recording how long it took under the given phase name
+pub fn time<T>(phase: &'static str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+
+    if let Ok(mut timings) = TIMINGS.lock() {
+        // Phases may be run multiple times (e.g., once per author), so accumulate them
+        if let Some((_, total)) = timings.iter_mut().find(|(p, _)| *p == phase) {
+            *total += elapsed;
+        } else {
+            timings.push((phase, elapsed));
+        }
+    }
+
+    result
+}
+
+// Report the time taken for each recorded phase to stderr, in the order that they were first run
+pub fn report(total: Duration) {
+    if let Ok(timings) = TIMINGS.lock() {
+        let width = timings
+            .iter()
+            .map(|(phase, _)| phase.len())
+            .max()
+            .unwrap_or(0)
+            .max("total".len());
+        for (phase, elapsed) in timings.iter() {
+            eprintln!(
+                "{:<width$}  {:>10.3}ms",
+                phase,
+                elapsed.as_secs_f64() * 1000.0
+            );
+        }
+        eprintln!(
+            "{:<width$}  {:>10.3}ms",
+            "total",
+            total.as_secs_f64() * 1000.0
+        );
+    }
+}
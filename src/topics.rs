@@ -0,0 +1,115 @@
+use super::commit::git_log;
+use super::opts::GitLogOptions;
+use json::JsonValue;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use tabular::{row, Table};
+
+lazy_static! {
+    // Conventional commit prefixes, e.g. "feat:", "fix(parser):", or "refactor!:"
+    static ref CONVENTIONAL_PREFIX_RE: Regex = Regex::new(r"^(?P<kind>[A-Za-z]+)(\((?P<scope>[^)]*)\))?(?P<breaking>!)?:\s*").unwrap();
+}
+
+// The prefix of a conventional commit subject, e.g. "fix(parser)!: ..."
+pub struct ConventionalCommit {
+    pub kind: String,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    // The rest of the subject
+    pub description: String,
+}
+
+pub fn conventional_commit(subject: &str) -> Option<ConventionalCommit> {
+    let caps = CONVENTIONAL_PREFIX_RE.captures(subject.trim())?;
+    Some(ConventionalCommit {
+        kind: caps["kind"].to_lowercase(),
+        scope: caps.name("scope").map(|scope| scope.as_str().to_string()),
+        breaking: caps.name("breaking").is_some(),
+        description: subject.trim()[caps[0].len()..].to_string(),
+    })
+}
+
+// Common words that say nothing about what a commit is about
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "with", "from", "into", "onto", "that", "this", "when", "not", "but",
+    "are", "was", "were", "has", "have", "had", "its", "also", "more", "less", "some", "all",
+    "any", "can", "use", "using", "used", "add", "adds", "added", "fix", "fixes", "fixed",
+    "update", "updates", "updated", "remove", "removes", "removed", "change", "changes", "changed",
+    "make", "makes", "made", "minor", "small", "wip", "via", "per", "now", "only", "new",
+    "instead", "other", "out", "bump", "merge", "revert", "should", "don't", "doesn't", "does",
+    "did", "just", "than", "then", "there", "they", "them", "what", "which", "will", "would",
+    "been", "being", "our", "you", "your", "too", "very",
+];
+
+// The most common terms in commit subjects, by the number of commits that mention them
+pub fn topics(opts: &GitLogOptions) -> Vec<(String, usize)> {
+    // Only the filters are relevant to topics; we need every matching commit
+    let opts = GitLogOptions {
+        rev: opts.rev.clone(),
+        authors: opts.authors.clone(),
+        needles: opts.needles.clone(),
+        since: opts.since.clone(),
+        until: opts.until.clone(),
+        first_parent: opts.first_parent,
+        merges_only: opts.merges_only,
+        excludes: opts.excludes.clone(),
+        no_bots: opts.no_bots,
+        all: true,
+        colour: false,
+        ..Default::default()
+    };
+
+    let mut term_frequency: HashMap<String, usize> = HashMap::new();
+    for commit in git_log(None, Some(&opts)) {
+        // Count each term once per commit
+        let terms: HashSet<String> = subject_terms(&commit.message).into_iter().collect();
+        for term in terms {
+            *term_frequency.entry(term).or_insert(0) += 1;
+        }
+    }
+
+    let mut term_frequency: Vec<(String, usize)> = term_frequency.into_iter().collect();
+    term_frequency.sort_by(|(a, a_n), (b, b_n)| b_n.cmp(a_n).then_with(|| a.cmp(b)));
+    term_frequency
+}
+
+pub fn display_topics(top_n: usize, opts: &GitLogOptions) {
+    let topics: Vec<(String, usize)> = topics(opts).into_iter().take(top_n).collect();
+
+    if opts.json {
+        let topics: Vec<JsonValue> = topics
+            .iter()
+            .map(|(term, commits)| json::object! { term: term.clone(), commits: *commits })
+            .collect();
+        println!("{}", JsonValue::Array(topics).pretty(2));
+        return;
+    }
+
+    if topics.is_empty() {
+        println!("No commits found.");
+        return;
+    }
+
+    let mut table = Table::new("{:<}  {:>}").with_row(row!("Term", "Commits"));
+    for (term, commits) in topics {
+        table.add_row(row!(term, commits));
+    }
+    print!("{}", table);
+}
+
+// Split a commit subject into lowercase terms, leaving out any conventional commit prefix, stop
+// words, numbers, and very short words
+fn subject_terms(subject: &str) -> Vec<String> {
+    CONVENTIONAL_PREFIX_RE
+        .replace(subject.trim(), "")
+        .to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '\''))
+        .map(|word| word.trim_matches(|c| c == '-' || c == '\'').to_string())
+        .filter(|word| {
+            word.chars().count() >= 3
+                && !word.chars().all(|c| c.is_ascii_digit())
+                && !STOP_WORDS.contains(&word.as_str())
+        })
+        .collect()
+}
@@ -0,0 +1,179 @@
+// Parsing of commit message trailers ("Key: value" lines at the end of a message, as written by
+// `git commit --trailer` or `git interpret-trailers`)
+
+#[derive(Debug, PartialEq)]
+pub struct Trailer {
+    pub key: String,
+    pub value: String,
+}
+
+// Lines that git itself writes at the end of a message, which mark the last paragraph as trailers
+// even if it has other lines in it
+const GIT_GENERATED_PREFIXES: [&str; 2] = ["Signed-off-by: ", "(cherry picked from commit "];
+
+// The trailers of a commit message.  As in git, trailers are only looked for in the last paragraph
+// of the message, which must not also be the subject, and lines starting with whitespace continue
+// the value of the trailer before them.  The paragraph counts as trailers if every line of it is a
+// trailer, or if it has one that git generated and at least a quarter of it is trailers (so that,
+// e.g., a "(cherry picked from ...)" line or a stray line of prose doesn't hide a Reviewed-by)
+pub fn parse_trailers(message: &str) -> Vec<Trailer> {
+    let lines: Vec<&str> = message.trim_end().lines().collect();
+    let start = match lines.iter().rposition(|line| line.trim().is_empty()) {
+        Some(blank) => blank + 1,
+        // The message is a single paragraph, i.e. only a subject
+        None => return Vec::new(),
+    };
+    if !lines[..start].iter().any(|line| !line.trim().is_empty()) {
+        return Vec::new();
+    }
+
+    let mut trailers: Vec<Trailer> = Vec::new();
+    let (mut trailer_lines, mut other_lines) = (0, 0);
+    let mut git_generated = false;
+    // Whether the line before was a trailer (or a continuation of one)
+    let mut continues = false;
+    for line in &lines[start..] {
+        if line.starts_with(char::is_whitespace) {
+            match trailers.last_mut() {
+                Some(trailer) if continues => {
+                    trailer.value.push(' ');
+                    trailer.value.push_str(line.trim());
+                    trailer_lines += 1;
+                }
+                _ => other_lines += 1,
+            }
+            continue;
+        }
+        let generated = GIT_GENERATED_PREFIXES
+            .iter()
+            .any(|prefix| line.starts_with(prefix));
+        git_generated |= generated;
+        match parse_trailer_line(line) {
+            Some(trailer) => {
+                trailers.push(trailer);
+                trailer_lines += 1;
+                continues = true;
+            }
+            None if generated => {
+                trailer_lines += 1;
+                continues = false;
+            }
+            None => {
+                other_lines += 1;
+                continues = false;
+            }
+        }
+    }
+
+    if other_lines == 0 || (git_generated && trailer_lines * 3 >= other_lines) {
+        trailers
+    } else {
+        Vec::new()
+    }
+}
+
+// A "Key: value" line, where the key is made of letters, digits and hyphens
+fn parse_trailer_line(line: &str) -> Option<Trailer> {
+    let (key, value) = line.split_once(':')?;
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+    Some(Trailer {
+        key: key.to_string(),
+        value: value.trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arbitrary::{Gen, ANY, CASES};
+
+    fn trailer(key: &str, value: &str) -> Trailer {
+        Trailer {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_trailers_in_last_paragraph() {
+        let message = "Fix the thing\n\nLonger description: with a colon.\n\nReviewed-by: Zoë Ünicode <zoe@example.com>\nSigned-off-by: Anon <>\n";
+        assert_eq!(
+            parse_trailers(message),
+            vec![
+                trailer("Reviewed-by", "Zoë Ünicode <zoe@example.com>"),
+                trailer("Signed-off-by", "Anon <>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_continuation_lines() {
+        let message = "Subject\n\nCo-authored-by: A\n  and B\n";
+        assert_eq!(
+            parse_trailers(message),
+            vec![trailer("Co-authored-by", "A and B")]
+        );
+    }
+
+    #[test]
+    fn subject_is_not_a_trailer() {
+        assert!(parse_trailers("fix: a bug").is_empty());
+        assert!(parse_trailers("\n\nfix: a bug\n").is_empty());
+        assert!(parse_trailers("").is_empty());
+    }
+
+    #[test]
+    fn paragraph_with_other_text_has_no_trailers() {
+        let message = "Subject\n\nReviewed-by: A\nThis is prose\n";
+        assert!(parse_trailers(message).is_empty());
+    }
+
+    #[test]
+    fn paragraph_with_git_generated_trailers_may_have_other_lines() {
+        let message = "Subject\n\nReviewed-by: A\n(cherry picked from commit 0123abc)\n";
+        assert_eq!(parse_trailers(message), vec![trailer("Reviewed-by", "A")]);
+
+        let message = "Subject\n\nReviewed-by: A\nwrapped onto a second line\nSigned-off-by: B\n";
+        assert_eq!(
+            parse_trailers(message),
+            vec![trailer("Reviewed-by", "A"), trailer("Signed-off-by", "B")]
+        );
+
+        // Less than a quarter trailers
+        let message = "Subject\n\nSigned-off-by: B\none\ntwo\nthree\nfour\n";
+        assert!(parse_trailers(message).is_empty());
+    }
+
+    #[test]
+    fn arbitrary_messages_do_not_panic() {
+        for seed in 0..CASES {
+            let message = Gen::new(seed).string(200, ANY);
+            for trailer in parse_trailers(&message) {
+                assert!(!trailer.key.is_empty(), "seed {}", seed);
+                assert!(!trailer.value.contains('\n'), "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_trailers_round_trip() {
+        let key_chars: Vec<char> = "Ab-9".chars().collect();
+        for seed in 0..CASES {
+            let mut gen = Gen::new(seed);
+            let subject = format!("x{}", gen.string_without(40, &['\n', '\r']));
+            let expected: Vec<Trailer> = (0..gen.below(4) + 1)
+                .map(|_| {
+                    let key = format!("K{}", gen.string(10, &key_chars));
+                    let value = gen.string_without(30, &['\n', '\r']);
+                    trailer(&key, value.trim())
+                })
+                .collect();
+            let message = expected.iter().fold(format!("{}\n\n", subject), |m, t| {
+                format!("{}{}: {}\n", m, t.key, t.value)
+            });
+            assert_eq!(parse_trailers(&message), expected, "seed {}", seed);
+        }
+    }
+}
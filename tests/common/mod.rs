@@ -0,0 +1,146 @@
+// Fixture layer for end-to-end tests: build small repositories with plain git, then run gl in them
+// with an environment that makes its output reproducible (no colour, a fixed time zone, and no
+// system or global git config or gl settings leaking in from the machine running the tests)
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+pub struct TestRepo {
+    // The repository is always called "repo" (gl prints the repository's name in some modes), so
+    // each one gets its own parent directory
+    root: PathBuf,
+    path: PathBuf,
+}
+
+impl TestRepo {
+    pub fn new() -> Self {
+        let root = std::env::temp_dir().join(format!(
+            "gl-test-{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::SeqCst)
+        ));
+        let path = root.join("repo");
+        fs::create_dir_all(&path).expect("Failed to create test repository directory");
+        let repo = TestRepo { root, path };
+        repo.git(&["init", "--quiet", "--initial-branch=main"]);
+        repo.git(&["config", "user.name", "Test User"]);
+        repo.git(&["config", "user.email", "test@example.com"]);
+        repo
+    }
+
+    pub fn write(&self, file: &str, contents: &str) {
+        let path = self.path.join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("Failed to create directory");
+        }
+        fs::write(path, contents).expect("Failed to write file");
+    }
+
+    // Commit everything in the working tree as the given author ("Name <email>"), at the given date,
+    // so that neither hashes nor dates in output depend on when the tests were run
+    pub fn commit_at(&self, author: &str, date: &str, message: &str) {
+        self.git(&["add", "--all"]);
+        let status = self
+            .git_command(&[
+                "commit",
+                "--quiet",
+                "--allow-empty",
+                "--allow-empty-message",
+                "--author",
+                author,
+                "--message",
+                message,
+            ])
+            .env("GIT_AUTHOR_DATE", date)
+            .env("GIT_COMMITTER_DATE", date)
+            .status()
+            .expect("Failed to run git commit");
+        assert!(status.success(), "git commit failed");
+    }
+
+    // Merge the given branch into the current one with a merge commit, at the given date
+    pub fn merge_at(&self, branch: &str, date: &str, message: &str) {
+        let status = self
+            .git_command(&["merge", "--quiet", "--no-ff", "--message", message, branch])
+            .env("GIT_AUTHOR_DATE", date)
+            .env("GIT_COMMITTER_DATE", date)
+            .status()
+            .expect("Failed to run git merge");
+        assert!(status.success(), "git merge failed");
+    }
+
+    pub fn git(&self, args: &[&str]) -> String {
+        let output = self.git_command(args).output().expect("Failed to run git");
+        assert!(
+            output.status.success(),
+            "git {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    fn git_command(&self, args: &[&str]) -> Command {
+        let mut cmd = Command::new("git");
+        isolate(&mut cmd, &self.root);
+        cmd.current_dir(&self.path).args(args);
+        cmd
+    }
+
+    // Run gl in the repository, returning what it printed to stdout
+    pub fn gl(&self, args: &[&str]) -> String {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_gl"));
+        isolate(&mut cmd, &self.root);
+        let output = cmd
+            .current_dir(&self.path)
+            .args(args)
+            .output()
+            .expect("Failed to run gl");
+        assert!(
+            output.status.success(),
+            "gl {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+}
+
+impl Drop for TestRepo {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+fn isolate(cmd: &mut Command, home: &Path) {
+    cmd.env_clear()
+        .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+        .env("HOME", home)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("TZ", "UTC")
+        .env("NO_COLOR", "1");
+}
+
+// Compare output against tests/snapshots/<name>.txt.  Run the tests with UPDATE_SNAPSHOTS=1 to
+// write the current output as the new snapshot (and review the diff before committing it)
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).expect("Failed to write snapshot");
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("Missing snapshot {:?}; run with UPDATE_SNAPSHOTS=1", path));
+    assert_eq!(
+        actual, expected,
+        "Output differs from snapshot {:?}; if the change is intended, run with UPDATE_SNAPSHOTS=1",
+        path
+    );
+}
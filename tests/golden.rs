@@ -0,0 +1,252 @@
+// Golden-output tests: run gl against small repositories and compare what it prints with the
+// snapshots in tests/snapshots
+
+mod common;
+
+use common::{assert_snapshot, TestRepo};
+
+// A small history with the identities that have caused trouble before: a name with non-ASCII
+// characters, and an author with no email at all (as in some old or imported repositories)
+fn sample_repo() -> TestRepo {
+    let repo = TestRepo::new();
+
+    repo.write("README.md", "# Sample\n\nA repository for tests.\n");
+    repo.commit_at(
+        "Ada Lovelace <ada@example.com>",
+        "2024-01-01T09:00:00+0000",
+        "Add readme",
+    );
+
+    repo.write("src/lib.rs", "pub fn one() -> u8 {\n    1\n}\n");
+    repo.commit_at(
+        "Zoë Ünicode <zoe@example.com>",
+        "2024-01-02T10:00:00+0000",
+        "Add library",
+    );
+    repo.git(&["tag", "v1.0"]);
+
+    repo.write("README.md", "# Sample\n\nA repository for testing.\n");
+    repo.commit_at("Anon <>", "2024-01-03T11:00:00+0000", "Fix typo");
+
+    repo.write("tests/one.rs", "#[test]\nfn one() {}\n");
+    repo.commit_at(
+        "Ada Lovelace <ada@example.com>",
+        "2024-01-04T12:00:00+0000",
+        "Add tests",
+    );
+
+    repo
+}
+
+// The sample history with a feature branch merged into it
+fn merged_repo() -> TestRepo {
+    let repo = sample_repo();
+    repo.git(&["checkout", "--quiet", "-b", "feature", "HEAD~1"]);
+    repo.write("src/two.rs", "pub fn two() -> u8 {\n    2\n}\n");
+    repo.commit_at(
+        "Zoë Ünicode <zoe@example.com>",
+        "2024-01-05T09:00:00+0000",
+        "Add two",
+    );
+    repo.git(&["checkout", "--quiet", "main"]);
+    repo.merge_at(
+        "feature",
+        "2024-01-06T10:00:00+0000",
+        "Merge branch 'feature'",
+    );
+    repo
+}
+
+#[test]
+fn log() {
+    let repo = sample_repo();
+    assert_snapshot("log", &repo.gl(&["--abs"]));
+}
+
+#[test]
+fn log_limited() {
+    let repo = sample_repo();
+    assert_snapshot("log_limited", &repo.gl(&["--abs", "2"]));
+}
+
+#[test]
+fn log_range() {
+    let repo = sample_repo();
+    assert_snapshot("log_range", &repo.gl(&["--abs", "v1.0..HEAD"]));
+}
+
+#[test]
+fn log_reversed_filtered() {
+    let repo = sample_repo();
+    assert_snapshot(
+        "log_reversed_filtered",
+        &repo.gl(&["--abs", "--since", "2024-01-02T00:00:00+0000", "--rev", "2"]),
+    );
+}
+
+#[test]
+fn log_merged() {
+    let repo = merged_repo();
+    assert_snapshot("log_merged", &repo.gl(&["--abs"]));
+}
+
+#[test]
+fn log_first_parent() {
+    let repo = merged_repo();
+    assert_snapshot("log_first_parent", &repo.gl(&["--abs", "--first-parent"]));
+}
+
+#[test]
+fn log_parents() {
+    let repo = merged_repo();
+    assert_snapshot(
+        "log_parents",
+        &repo.gl(&["--abs", "--first-parent", "--parents"]),
+    );
+}
+
+#[test]
+fn log_all_refs_by_size() {
+    let repo = sample_repo();
+    repo.git(&["checkout", "--quiet", "-b", "side"]);
+    let big: String = (1..=100).map(|i| format!("{}\n", i)).collect();
+    repo.write("big.txt", &big);
+    repo.commit_at(
+        "Zoë Ünicode <zoe@example.com>",
+        "2024-01-05T09:00:00+0000",
+        "Add big file",
+    );
+    repo.git(&["checkout", "--quiet", "main"]);
+    assert_snapshot(
+        "log_all_refs_by_size",
+        &repo.gl(&["--abs", "--all-refs", "--min-changes", "50"]),
+    );
+}
+
+#[test]
+fn log_stat_summary() {
+    let repo = sample_repo();
+    assert_snapshot(
+        "log_stat_summary",
+        &repo.gl(&["--abs", "--stat-summary", "--author", "Lovelace"]),
+    );
+}
+
+#[test]
+fn status() {
+    let repo = sample_repo();
+    repo.write("README.md", "# Sample\n");
+    repo.write("notes.txt", "untracked\n");
+    assert_snapshot("status", &repo.gl(&["--status"]));
+}
+
+#[test]
+fn status_with_stashes() {
+    let repo = sample_repo();
+    for contents in ["# Stashed\n", "# Stashed again\n"] {
+        repo.write("README.md", contents);
+        repo.git(&["stash", "--quiet"]);
+    }
+    repo.write("notes.txt", "untracked\n");
+    assert_snapshot("status_with_stashes", &repo.gl(&["--status"]));
+}
+
+#[test]
+fn author_commit_counts() {
+    let repo = sample_repo();
+    assert_snapshot("author_commit_counts", &repo.gl(&["-A"]));
+}
+
+#[test]
+fn author_commit_counts_shortlog() {
+    let repo = sample_repo();
+    assert_snapshot(
+        "author_commit_counts_shortlog",
+        &repo.gl(&["-A", "--shortlog"]),
+    );
+}
+
+#[test]
+fn author_commit_counts_shortlog_by_name() {
+    // As in `git shortlog -sne`, a name used with two emails, and an email used with two names,
+    // are each counted separately
+    let repo = sample_repo();
+    repo.write("src/lib.rs", "pub fn one() -> u8 {\n    1 + 0\n}\n");
+    repo.commit_at(
+        "Ada Lovelace <ada@work.example.com>",
+        "2024-01-05T09:00:00+0000",
+        "Tidy library",
+    );
+    repo.write("tests/one.rs", "#[test]\nfn one() {\n}\n");
+    repo.commit_at(
+        "A. Lovelace <ada@example.com>",
+        "2024-01-06T09:00:00+0000",
+        "Tidy tests",
+    );
+    assert_eq!(
+        repo.gl(&["-A", "--shortlog"]),
+        repo.git(&["shortlog", "-sne", "--all", "--no-merges"])
+    );
+    assert_snapshot(
+        "author_commit_counts_shortlog_by_name",
+        &repo.gl(&["-A", "--shortlog"]),
+    );
+}
+
+#[test]
+fn author_contrib_stats() {
+    let repo = sample_repo();
+    assert_snapshot("author_contrib_stats", &repo.gl(&["-S"]));
+}
+
+#[test]
+fn count() {
+    let repo = sample_repo();
+    assert_snapshot("count", &repo.gl(&["--count"]));
+}
+
+#[test]
+fn count_authors_one_day() {
+    let repo = sample_repo();
+    assert_snapshot("count_authors_one_day", &repo.gl(&["--count-authors", "1"]));
+}
+
+#[test]
+fn code_age_non_ascii_paths() {
+    let repo = sample_repo();
+    repo.write("docs/café.md", "# Café\n");
+    repo.commit_at(
+        "Zoë Ünicode <zoe@example.com>",
+        "2024-01-05T09:00:00+0000",
+        "Add café notes",
+    );
+    assert_snapshot("code_age_non_ascii_paths", &repo.gl(&["--code-age"]));
+}
+
+#[test]
+fn dir_activity_non_ascii_paths() {
+    let repo = sample_repo();
+    repo.write("café/notes.md", "# Notes\n");
+    repo.commit_at(
+        "Zoë Ünicode <zoe@example.com>",
+        "2024-01-05T09:00:00+0000",
+        "Add café notes",
+    );
+    assert_snapshot(
+        "dir_activity_non_ascii_paths",
+        &repo.gl(&["--dir-activity"]),
+    );
+}
+
+#[test]
+fn log_exclude_refs() {
+    let repo = sample_repo();
+    repo.git(&["tag", "dependabot-1"]);
+    repo.git(&["branch", "dependabot-x"]);
+    repo.git(&["update-ref", "refs/remotes/origin/dependabot-y", "HEAD"]);
+    repo.git(&["update-ref", "refs/remotes/origin/main", "HEAD"]);
+    assert_snapshot(
+        "log_exclude_refs",
+        &repo.gl(&["--abs", "--exclude-refs", "dependabot-*"]),
+    );
+}